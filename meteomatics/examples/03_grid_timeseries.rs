@@ -34,7 +34,7 @@ async fn main(){
     println!("{:?}", df_ts);
 
     // Do some calculations
-    for col in vec!["t_2m:C", "precip_1h:mm"] {
+    for col in ["t_2m:C", "precip_1h:mm"] {
         let mean: f64 = df_ts[col].mean().unwrap();
         let max: f64 = df_ts[col].max().unwrap();
         let min: f64 = df_ts[col].min().unwrap();
@@ -42,9 +42,9 @@ async fn main(){
     }
 
     // Do some groupby calculations
-    for col in vec!["t_2m:C", "precip_1h:mm"] {
-        let lat_means = df_ts.groupby(["lat"]).unwrap().select(&[col]).mean().unwrap();
-        let lon_means = df_ts.groupby(["lon"]).unwrap().select(&[col]).mean().unwrap();
+    for col in ["t_2m:C", "precip_1h:mm"] {
+        let lat_means = df_ts.groupby(["lat"]).unwrap().select([col]).mean().unwrap();
+        let lon_means = df_ts.groupby(["lon"]).unwrap().select([col]).mean().unwrap();
         println!("{:?}", lat_means);
         println!("{:?}", lon_means);
     }
@@ -12,7 +12,7 @@
 //! API package.
 
 use chrono::{Utc, TimeZone};
-use meteomatics::APIClient;
+use meteomatics::{APIClient, Parameter};
 use meteomatics::errors::ConnectorError;
 use polars::frame::DataFrame;
 
@@ -34,16 +34,16 @@ async fn main(){
 async fn example_request(api: &APIClient) -> std::result::Result<DataFrame, ConnectorError>{
     let location = "50.705502,10.467007";
     let elevation = None;
-    let parameters = vec!["t_2m:C"];
-    let startdate = Utc.ymd(2018, 1, 1).and_hms(0, 0, 0);
+    let parameters = vec![Parameter::new("t", "C").level("2m")];
+    let startdate = Utc.with_ymd_and_hms(2018, 1, 1, 0, 0, 0).unwrap();
     let enddate = None;
 
     let result = api.query_station_list(
         &Option::from(location),
         &Option::from(parameters),
-        &Option::from(elevation),
+        &elevation,
         &Option::from(startdate),
-        &Option::from(enddate)
+        &enddate
     ).await.unwrap();
 
     Ok(result)
@@ -1,3 +1,60 @@
-fn main() {
-    unimplemented!()
-}
\ No newline at end of file
+//! # Query Model Runs
+//! Forecast models are run several times a day. The ```get_init_date``` query tells you which run
+//! (identified by its initialization time) produced the data for a valid time, for each parameter.
+//! The respective method is [`meteomatics::APIClient::query_init_dates`]. All columns of the result,
+//! including ```validdate```, are ```Datetime``` columns; valid times without a run are missing.
+//!
+//!# The Example
+//! The example demonstrates how to find the runs of the ECMWF IFS model that cover the next two days
+//! in steps of six hours for temperature and precipitation.
+//!
+//! # The account
+//! You can use the provided credentials or your own if you already have them.
+//! Check out <https://www.meteomatics.com/en/request-business-wather-api-package/> to request an
+//! API package.
+
+use chrono::{Utc, Duration};
+use meteomatics::{APIClient, TimeSeries};
+use meteomatics::errors::ConnectorError;
+use polars::prelude::*;
+
+#[tokio::main]
+async fn main(){
+    // Credentials
+    let api: APIClient = APIClient::new("rust-community", "5GhAwL3HCpFB", 10);
+
+    let df_init_dates = example_request(&api).await.unwrap();
+
+    // Print the query result
+    println!("{:?}", df_init_dates);
+
+    // Find the most recent run for each parameter
+    for col in ["t_2m:C", "precip_1h:mm"] {
+        let latest = df_init_dates[col].max::<i64>()
+            .and_then(chrono::DateTime::from_timestamp_millis);
+        match latest {
+            Some(run) => println!("{}: latest run initialized at {}.", col, run),
+            None => println!("{}: no run available.", col),
+        }
+    }
+}
+
+/// Query the model runs for the next two days and two parameters.
+async fn example_request(api: &APIClient) -> std::result::Result<DataFrame, ConnectorError>{
+    // Time series definition (valid times)
+    let start_date = Utc::now();
+    let time_series = TimeSeries {
+        start: start_date,
+        end: start_date + Duration::days(2),
+        timedelta: Option::from(Duration::hours(6))
+    };
+
+    // Parameter selection
+    let t_2m = String::from("t_2m:C");
+    let precip_1h = String::from("precip_1h:mm");
+    let params = vec![t_2m, precip_1h];
+
+    let result = api.query_init_dates("ecmwf-ifs", &time_series, &params).await;
+
+    result
+}
@@ -1,3 +1,69 @@
-fn main() {
-    unimplemented!()
-}
\ No newline at end of file
+//! # Query Available Time Ranges
+//! Every model covers a different period: reanalyses go back decades, forecasts only reach a few days
+//! ahead. The ```get_time_range``` query tells you for which period a model has data of a parameter.
+//! The respective method is [`meteomatics::APIClient::query_available_time_range`], it returns one
+//! [`TimeRange`](meteomatics::availability::TimeRange) per parameter.
+//!
+//! The client can also check every time series and grid query against these ranges before it is sent
+//! ([`meteomatics::APIClientBuilder::time_range_check`]). Queries outside the range then fail with a
+//! clear error instead of an error of the API.
+//!
+//!# The Example
+//! The example demonstrates how to find the time ranges of temperature and precipitation for the
+//! ECMWF IFS model and how a query beyond the end of the forecast is rejected.
+//!
+//! # The account
+//! You can use the provided credentials or your own if you already have them.
+//! Check out <https://www.meteomatics.com/en/request-business-wather-api-package/> to request an
+//! API package.
+
+use chrono::{Utc, Duration};
+use meteomatics::{APIClient, Point, QueryOptions, TimeSeries};
+use meteomatics::availability::TimeRange;
+use meteomatics::errors::ConnectorError;
+
+#[tokio::main]
+async fn main(){
+    // Credentials and the check of the time ranges
+    let api: APIClient = APIClient::builder("rust-community", "5GhAwL3HCpFB")
+        .timeout_seconds(10)
+        .time_range_check(true)
+        .build()
+        .unwrap();
+
+    let time_ranges = example_request(&api).await.unwrap();
+
+    // Print the query result
+    for range in &time_ranges {
+        match (range.start, range.end) {
+            (Some(start), Some(end)) => println!("{} is available from {} to {}.", range.parameter, start, end),
+            _ => println!("{} is not available.", range.parameter),
+        }
+    }
+
+    // A forecast a year ahead is not available and rejected before it is sent
+    let start_date = Utc::now() + Duration::days(365);
+    let time_series = TimeSeries {
+        start: start_date,
+        end: start_date + Duration::days(1),
+        timedelta: Option::from(Duration::hours(1))
+    };
+    let zurich = Point { lat: 47.3769, lon: 8.5417 };
+    let optionals = QueryOptions::new().model("ecmwf-ifs");
+    match api.query_time_series(&time_series, &[String::from("t_2m:C")], &[zurich], &optionals).await {
+        Err(ConnectorError::UnavailableTimeRange(message)) => println!("Rejected: {}", message),
+        other => println!("Unexpected result: {:?}", other),
+    }
+}
+
+/// Query the time ranges of two parameters.
+async fn example_request(api: &APIClient) -> std::result::Result<Vec<TimeRange>, ConnectorError>{
+    // Parameter selection
+    let t_2m = String::from("t_2m:C");
+    let precip_1h = String::from("precip_1h:mm");
+    let params = vec![t_2m, precip_1h];
+
+    let result = api.query_available_time_range("ecmwf-ifs", &params).await;
+
+    result
+}
@@ -0,0 +1,209 @@
+//! # Auth
+//! By default the [`crate::APIClient`] sends the username and password with every request (HTTP basic
+//! authentication). With [`AuthMode::Token`] the credentials are instead exchanged for a short-lived
+//! access token at the token endpoint of Meteomatics, and the queries only carry the token (see
+//! [`crate::APIClientBuilder::auth`]).
+//!
+//! The token is shared by all clones of a client, such that concurrent queries never request more
+//! than one token at a time. It is renewed shortly before it expires and whenever the API rejects it
+//! with ```401 Unauthorized```.
+//!
+//! ```rust, no_run
+//! use meteomatics::APIClient;
+//! use meteomatics::auth::{AuthMode, TokenConfig, TokenPlacement};
+//!
+//! let client = APIClient::builder("ferris_loves_rustaceans", "0123456789")
+//!     .auth(AuthMode::Token(TokenConfig { placement: TokenPlacement::Query, ..TokenConfig::default() }))
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use crate::errors::ConnectorError;
+use serde::Deserialize;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// URL of the token endpoint of Meteomatics.
+pub const TOKEN_URL: &str = "https://login.meteomatics.com/api/v1/token";
+
+/// How the client authenticates its requests.
+#[derive(Clone, Debug, Default)]
+pub enum AuthMode {
+    /// Username and password are sent with every request (HTTP basic authentication).
+    #[default]
+    Basic,
+    /// The credentials are exchanged for an access token that is sent instead.
+    Token(TokenConfig),
+}
+
+/// Where the access token is attached to a request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenPlacement {
+    /// ```Authorization: Bearer <token>``` header.
+    Bearer,
+    /// ```access_token=<token>``` query parameter (e.g. for URLs that are handed to other programs).
+    Query,
+}
+
+/// Configuration of the token authentication.
+#[derive(Clone, Debug)]
+pub struct TokenConfig {
+    /// URL of the token endpoint (e.g. a local stand-in in tests).
+    pub token_url: String,
+    /// Where the token is attached to the requests.
+    pub placement: TokenPlacement,
+    /// Lifetime of a token if the token endpoint does not state it (```expires_in```).
+    pub lifetime: Duration,
+    /// Tokens are renewed this long before they expire.
+    pub refresh_margin: Duration,
+}
+
+impl Default for TokenConfig {
+    fn default() -> Self {
+        Self {
+            token_url: String::from(TOKEN_URL),
+            placement: TokenPlacement::Bearer,
+            lifetime: Duration::from_secs(2 * 60 * 60),
+            refresh_margin: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// Response of the token endpoint. The token is not printed by ```Debug```.
+#[derive(Clone, Deserialize)]
+pub struct TokenResponse {
+    /// The access token.
+    pub access_token: String,
+    /// Lifetime of the token in seconds (if stated).
+    #[serde(default)]
+    pub expires_in: Option<u64>,
+}
+
+impl fmt::Debug for TokenResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TokenResponse")
+            .field("access_token", &"<redacted>")
+            .field("expires_in", &self.expires_in)
+            .finish()
+    }
+}
+
+struct AccessToken {
+    value: String,
+    refresh_at: Instant,
+}
+
+impl fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AccessToken")
+            .field("value", &"<redacted>")
+            .field("refresh_at", &self.refresh_at)
+            .finish()
+    }
+}
+
+/// The access token of a client that is shared between its clones. The token is not printed by
+/// ```Debug```.
+pub struct TokenManager {
+    config: TokenConfig,
+    token: Mutex<Option<AccessToken>>,
+}
+
+impl fmt::Debug for TokenManager {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let token = match self.token.try_lock() {
+            Ok(token) => format!("{:?}", *token),
+            Err(_) => String::from("<locked>"),
+        };
+        f.debug_struct("TokenManager")
+            .field("config", &self.config)
+            .field("token", &format_args!("{}", token))
+            .finish()
+    }
+}
+
+impl TokenManager {
+    /// Creates a manager without a token.
+    pub fn new(config: TokenConfig) -> Self {
+        Self { config, token: Mutex::new(None) }
+    }
+
+    /// Returns the configuration.
+    pub fn config(&self) -> &TokenConfig {
+        &self.config
+    }
+
+    /// Returns the current token or obtains a new one with the given function if there is none or it
+    /// is about to expire. Concurrent callers wait for the same request instead of sending their own.
+    pub async fn token<F, Fut>(&self, fetch: F) -> Result<String, ConnectorError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<TokenResponse, ConnectorError>>,
+    {
+        let mut token = self.token.lock().await;
+        if let Some(current) = token.as_ref().filter(|t| Instant::now() < t.refresh_at) {
+            return Ok(current.value.clone());
+        }
+        let response = fetch().await?;
+        let lifetime = response.expires_in.map(Duration::from_secs).unwrap_or(self.config.lifetime);
+        let refresh_at = Instant::now() + lifetime.saturating_sub(self.config.refresh_margin);
+        *token = Some(AccessToken { value: response.access_token.clone(), refresh_at });
+        Ok(response.access_token)
+    }
+
+    /// Discards the token after the API rejected it. A token that was already replaced by another
+    /// caller is kept.
+    pub async fn invalidate(&self, rejected: &str) {
+        let mut token = self.token.lock().await;
+        if token.as_ref().is_some_and(|t| t.value == rejected) {
+            *token = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::auth::{TokenConfig, TokenManager, TokenResponse};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn tokens_are_reused_until_they_expire() {
+        let config = TokenConfig { refresh_margin: Duration::from_secs(60), ..TokenConfig::default() };
+        let manager = TokenManager::new(config);
+        let fetched = AtomicU32::new(0);
+        let fetch = |expires_in| {
+            let n = fetched.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(TokenResponse { access_token: format!("token-{}", n), expires_in }) }
+        };
+
+        assert_eq!(manager.token(|| fetch(None)).await.unwrap(), "token-0");
+        assert_eq!(manager.token(|| fetch(None)).await.unwrap(), "token-0");
+
+        // A rejected token is replaced, an outdated rejection does not discard the new token.
+        manager.invalidate("token-0").await;
+        assert_eq!(manager.token(|| fetch(None)).await.unwrap(), "token-1");
+        manager.invalidate("token-0").await;
+        assert_eq!(manager.token(|| fetch(None)).await.unwrap(), "token-1");
+
+        // Tokens that expire within the refresh margin are renewed right away.
+        manager.invalidate("token-1").await;
+        assert_eq!(manager.token(|| fetch(Some(30))).await.unwrap(), "token-2");
+        assert_eq!(manager.token(|| fetch(None)).await.unwrap(), "token-3");
+        assert_eq!(fetched.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn tokens_are_not_printed() {
+        let manager = TokenManager::new(TokenConfig::default());
+        let response = TokenResponse { access_token: String::from("s3cr3t-token"), expires_in: Some(60) };
+        assert!(!format!("{:?}", response).contains("s3cr3t-token"));
+        manager.token(|| async { Ok(response) }).await.unwrap();
+        let printed = format!("{:?}", manager);
+        assert!(printed.contains("AccessToken") && !printed.contains("s3cr3t-token"));
+    }
+}
@@ -0,0 +1,235 @@
+//! # Availability
+//! Which model runs produced the data of a model (see [`crate::APIClient::query_init_dates`]). The
+//! API answers with the initialization time of the run for every valid time and parameter. These
+//! are returned as ```Datetime``` columns (in milliseconds since the epoch, UTC), such that they can
+//! be joined with the results of other queries, e.g. to verify a forecast against the run that
+//! produced it. Valid times without a run are missing (```null```).
+//!
+//! For which period a model has data of a parameter is returned as a [`TimeRange`] (see
+//! [`crate::APIClient::query_available_time_range`]). The client can check every query against
+//! these ranges before it is sent (see [`crate::APIClientBuilder::time_range_check`]).
+//!
+//! ```rust, no_run
+//! use chrono::{Duration, Utc};
+//! use meteomatics::{APIClient, TimeSeries};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = APIClient::new("ferris_loves_rustaceans", "0123456789", 10);
+//!     let time_series = TimeSeries {
+//!         start: Utc::now(),
+//!         end: Utc::now() + Duration::days(2),
+//!         timedelta: Option::from(Duration::hours(6))
+//!     };
+//!     let df = client
+//!         .query_init_dates("ecmwf-ifs", &time_series, &["t_2m:C", "precip_1h:mm"])
+//!         .await
+//!         .unwrap();
+//!     println!("{:?}", df);
+//! }
+//! ```
+
+use crate::errors::ConnectorError;
+use crate::util::TimeSeries;
+use chrono::{DateTime, Utc};
+use polars::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long the time range check keeps the ranges by default.
+pub const DEFAULT_TIME_RANGE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Value of the API for a missing date (e.g. a valid time without a model run).
+pub const MISSING_DATE: &str = "0000-00-00T00:00:00Z";
+
+/// Formats a date for the query string of the API (e.g. "2022-05-17T00:00:00Z"). Unlike
+/// ```to_rfc3339``` this avoids the '+' of the offset, which a query string would turn into a space.
+pub(crate) fn format_query_date(date: &DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// Converts the columns of a ```get_init_date``` response (```validdate``` and one column per
+/// parameter) from text to ```Datetime``` columns.
+pub(crate) fn parse_init_dates(df: DataFrame) -> std::result::Result<DataFrame, ConnectorError> {
+    let polars_error = |e: PolarsError| ConnectorError::PolarsError(e.to_string());
+    let mut columns = Vec::with_capacity(df.width());
+    for series in df.get_columns() {
+        let values = series.cast(&DataType::Utf8).map_err(polars_error)?;
+        let millis = values
+            .utf8()
+            .map_err(polars_error)?
+            .into_iter()
+            .map(|value| match value {
+                None | Some(MISSING_DATE) => Ok(None),
+                Some(date) => DateTime::parse_from_rfc3339(date)
+                    .map(|date| Some(date.timestamp_millis()))
+                    .map_err(|_| {
+                        ConnectorError::LibraryError(format!("Invalid date {} in column {}", date, series.name()))
+                    }),
+            })
+            .collect::<std::result::Result<Vec<Option<i64>>, ConnectorError>>()?;
+        let dates = Int64Chunked::new(series.name(), millis).into_datetime(TimeUnit::Milliseconds, None);
+        columns.push(dates.into_series());
+    }
+    DataFrame::new(columns).map_err(polars_error)
+}
+
+/// The period for which a model has data of a parameter (both ends included). Both ends are
+/// ```None``` if the model has no data of the parameter at all.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeRange {
+    /// The parameter (e.g. "t_2m:C").
+    pub parameter: String,
+    /// The first point in time with data.
+    pub start: Option<DateTime<Utc>>,
+    /// The last point in time with data.
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl TimeRange {
+    /// Checks if the model has any data of the parameter.
+    pub fn has_data(&self) -> bool {
+        self.start.is_some() && self.end.is_some()
+    }
+
+    /// Checks if there is data for a point in time.
+    pub fn contains(&self, date: &DateTime<Utc>) -> bool {
+        matches!((self.start, self.end), (Some(start), Some(end)) if start <= *date && *date <= end)
+    }
+
+    /// Checks if there is data for the whole time series.
+    pub fn covers(&self, time_series: &TimeSeries) -> bool {
+        self.contains(&time_series.start) && self.contains(&time_series.end)
+    }
+}
+
+impl fmt::Display for TimeRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) => {
+                write!(f, "{}: {}--{}", self.parameter, format_query_date(&start), format_query_date(&end))
+            }
+            _ => write!(f, "{}: no data", self.parameter),
+        }
+    }
+}
+
+/// Reads the time ranges of a ```get_time_range``` response (columns ```parameter```,
+/// ```min_date``` and ```max_date```). Parameters without data (```0000-00-00T00:00:00Z```) are
+/// returned without start and end.
+pub(crate) fn parse_time_ranges(df: &DataFrame) -> std::result::Result<Vec<TimeRange>, ConnectorError> {
+    let polars_error = |e: PolarsError| ConnectorError::PolarsError(e.to_string());
+    let column = |name: &str| df.column(name).and_then(|s| s.cast(&DataType::Utf8)).map_err(polars_error);
+    let (parameters, min_dates, max_dates) = (column("parameter")?, column("min_date")?, column("max_date")?);
+    let parse = |date: Option<&str>| match date {
+        None | Some(MISSING_DATE) => Ok(None),
+        Some(date) => DateTime::parse_from_rfc3339(date)
+            .map(|date| Some(date.with_timezone(&Utc)))
+            .map_err(|_| ConnectorError::LibraryError(format!("Invalid date {} in time range", date))),
+    };
+
+    let mut ranges = Vec::with_capacity(df.height());
+    let rows = parameters
+        .utf8()
+        .map_err(polars_error)?
+        .into_iter()
+        .zip(min_dates.utf8().map_err(polars_error)?)
+        .zip(max_dates.utf8().map_err(polars_error)?);
+    for ((parameter, start), end) in rows {
+        if let Some(parameter) = parameter {
+            let (start, end) = match (parse(start)?, parse(end)?) {
+                (Some(start), Some(end)) => (Some(start), Some(end)),
+                _ => (None, None),
+            };
+            ranges.push(TimeRange { parameter: parameter.to_string(), start, end });
+        }
+    }
+    Ok(ranges)
+}
+
+// The range of a parameter (if the API returned one) and when it was requested.
+type KeptRange = (Option<TimeRange>, Instant);
+
+/// Time ranges by model and parameter, kept for a limited time such that the time range check (see
+/// [`crate::APIClientBuilder::time_range_check`]) does not cost a request per query. Parameters the
+/// API did not answer for are kept as ```None```.
+#[derive(Debug)]
+pub(crate) struct TimeRangeCache {
+    ttl: Duration,
+    ranges: Mutex<HashMap<(String, String), KeptRange>>,
+}
+
+impl TimeRangeCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self { ttl, ranges: Mutex::new(HashMap::new()) }
+    }
+
+    /// Returns the kept ranges of the parameters, ```None``` if any of them is missing or outdated.
+    pub(crate) fn get(&self, model: &str, parameters: &[String]) -> Option<Vec<TimeRange>> {
+        let ranges = self.ranges.lock().unwrap();
+        let mut found = Vec::with_capacity(parameters.len());
+        for parameter in parameters {
+            let (range, at) = ranges.get(&(model.to_string(), parameter.clone()))?;
+            if at.elapsed() >= self.ttl {
+                return None;
+            }
+            found.extend(range.clone());
+        }
+        Some(found)
+    }
+
+    /// Keeps the ranges the API returned for the parameters.
+    pub(crate) fn put(&self, model: &str, parameters: &[String], found: &[TimeRange]) {
+        let mut ranges = self.ranges.lock().unwrap();
+        let now = Instant::now();
+        for parameter in parameters {
+            let range = found.iter().find(|range| &range.parameter == parameter).cloned();
+            ranges.insert((model.to_string(), parameter.clone()), (range, now));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::availability::{parse_init_dates, parse_time_ranges};
+    use chrono::{TimeZone, Utc};
+    use polars::prelude::*;
+
+    #[tokio::test]
+    async fn init_dates_are_parsed() {
+        let df = df!(
+            "validdate" => &["2022-05-17T00:00:00Z", "2022-05-17T06:00:00Z"],
+            "t_2m:C" => &["2022-05-16T12:00:00Z", "0000-00-00T00:00:00Z"]
+        ).unwrap();
+        let df = parse_init_dates(df).unwrap();
+
+        assert_eq!(df.column("t_2m:C").unwrap().dtype(), &DataType::Datetime(TimeUnit::Milliseconds, None));
+        let init_dates: Vec<Option<i64>> = df.column("t_2m:C").unwrap().datetime().unwrap().into_iter().collect();
+        let run = Utc.with_ymd_and_hms(2022, 5, 16, 12, 0, 0).unwrap();
+        assert_eq!(init_dates, vec![Some(run.timestamp_millis()), None]);
+
+        assert_eq!(df.column("t_2m:C").unwrap().max::<i64>(), Some(run.timestamp_millis()));
+        let invalid = df!("validdate" => &["yesterday"]).unwrap();
+        assert!(parse_init_dates(invalid).is_err());
+    }
+
+    #[tokio::test]
+    async fn time_ranges_are_parsed() {
+        let df = df!(
+            "parameter" => &["t_2m:C", "sst:K"],
+            "min_date" => &["2000-01-01T00:00:00Z", "0000-00-00T00:00:00Z"],
+            "max_date" => &["2022-05-27T00:00:00Z", "0000-00-00T00:00:00Z"]
+        ).unwrap();
+        let ranges = parse_time_ranges(&df).unwrap();
+
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(ranges[0].to_string(), "t_2m:C: 2000-01-01T00:00:00Z--2022-05-27T00:00:00Z");
+        assert!(ranges[0].contains(&Utc.with_ymd_and_hms(2022, 5, 27, 0, 0, 0).unwrap()));
+        assert!(!ranges[0].contains(&Utc.with_ymd_and_hms(2022, 5, 27, 1, 0, 0).unwrap()));
+        assert_eq!(ranges[1].to_string(), "sst:K: no data");
+        assert!(!ranges[1].has_data() && !ranges[1].contains(&Utc.with_ymd_and_hms(2022, 5, 27, 0, 0, 0).unwrap()));
+    }
+}
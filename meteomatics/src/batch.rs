@@ -0,0 +1,153 @@
+//! # Batch
+//! A batch runs many independent queries with a bounded number of requests in flight (see
+//! [`crate::APIClient::query_batch`]). Each query is described by a [`BatchQuery`] that owns all the
+//! information of the corresponding ```query_*``` method of the [`crate::APIClient`]. The results are
+//! returned in the order of the queries, such that a failed query does not discard the results of the
+//! successful ones. A batch can be cancelled with a [`CancellationToken`].
+//!
+//! ```rust, no_run
+//! use chrono::{Duration, Utc};
+//! use meteomatics::{APIClient, Point, TimeSeries};
+//! use meteomatics::batch::{BatchOptions, BatchQuery};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = APIClient::new("ferris_loves_rustaceans", "0123456789", 10);
+//!     let start_date = Utc::now();
+//!
+//!     let sites = vec![(47.423, 9.370), (46.210, 6.143), (46.026, 7.748)];
+//!     let queries: Vec<BatchQuery> = sites.iter().map(|(lat, lon)| BatchQuery::TimeSeries {
+//!         time_series: TimeSeries {
+//!             start: start_date,
+//!             end: start_date + Duration::days(1),
+//!             timedelta: Option::from(Duration::hours(1))
+//!         },
+//!         parameters: vec![String::from("t_2m:C")],
+//!         coordinates: vec![Point { lat: *lat, lon: *lon }],
+//!         optionals: None,
+//!     }).collect();
+//!
+//!     let options = BatchOptions { concurrency: 2, ..BatchOptions::default() };
+//!     for result in client.query_batch(queries, &options).await {
+//!         match result {
+//!             Ok(df) => println!("{:?}", df),
+//!             Err(e) => println!("query failed: {}", e),
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::errors::ConnectorError;
+use crate::location::{BBox, Point};
+use crate::util::TimeSeries;
+use crate::APIClient;
+use polars::frame::DataFrame;
+pub use tokio_util::sync::CancellationToken;
+
+/// Description of a single query in a batch. The variants correspond to the ```query_*``` methods
+/// of the [`APIClient`] that return a [`DataFrame`].
+#[derive(Debug)]
+pub enum BatchQuery {
+    /// See [`APIClient::query_time_series`].
+    TimeSeries {
+        time_series: TimeSeries,
+        parameters: Vec<String>,
+        coordinates: Vec<Point>,
+        optionals: Option<Vec<String>>,
+    },
+    /// See [`APIClient::query_time_series_postal`].
+    TimeSeriesPostal {
+        time_series: TimeSeries,
+        parameters: Vec<String>,
+        postals: Vec<String>,
+        optionals: Option<Vec<String>>,
+    },
+    /// See [`APIClient::query_grid_pivoted`].
+    GridPivoted {
+        timestamp: chrono::DateTime<chrono::Utc>,
+        parameter: String,
+        bbox: BBox,
+        optionals: Option<Vec<String>>,
+    },
+    /// See [`APIClient::query_grid_unpivoted`].
+    GridUnpivoted {
+        timestamp: chrono::DateTime<chrono::Utc>,
+        parameters: Vec<String>,
+        bbox: BBox,
+        optionals: Option<Vec<String>>,
+    },
+    /// See [`APIClient::query_grid_unpivoted_time_series`].
+    GridUnpivotedTimeSeries {
+        time_series: TimeSeries,
+        parameters: Vec<String>,
+        bbox: BBox,
+        optionals: Option<Vec<String>>,
+    },
+    /// See [`APIClient::query_lightning`].
+    Lightning {
+        time_series: TimeSeries,
+        bbox: BBox,
+    },
+    /// See [`APIClient::route_query_points`].
+    RoutePoints {
+        dates: Vec<chrono::DateTime<chrono::Utc>>,
+        points: Vec<Point>,
+        parameters: Vec<String>,
+    },
+    /// See [`APIClient::route_query_postal`].
+    RoutePostal {
+        dates: Vec<chrono::DateTime<chrono::Utc>>,
+        postals: Vec<String>,
+        parameters: Vec<String>,
+    },
+}
+
+impl BatchQuery {
+    /// Runs the query with the given client.
+    pub async fn run(&self, client: &APIClient) -> Result<DataFrame, ConnectorError> {
+        match self {
+            BatchQuery::TimeSeries { time_series, parameters, coordinates, optionals } => {
+                client.query_time_series(time_series, parameters, coordinates, optionals).await
+            }
+            BatchQuery::TimeSeriesPostal { time_series, parameters, postals, optionals } => {
+                client.query_time_series_postal(time_series, parameters, postals, optionals).await
+            }
+            BatchQuery::GridPivoted { timestamp, parameter, bbox, optionals } => {
+                client.query_grid_pivoted(timestamp, parameter, bbox, optionals).await
+            }
+            BatchQuery::GridUnpivoted { timestamp, parameters, bbox, optionals } => {
+                client.query_grid_unpivoted(timestamp, parameters, bbox, optionals).await
+            }
+            BatchQuery::GridUnpivotedTimeSeries { time_series, parameters, bbox, optionals } => {
+                client.query_grid_unpivoted_time_series(time_series, parameters, bbox, optionals).await
+            }
+            BatchQuery::Lightning { time_series, bbox } => {
+                client.query_lightning(time_series, bbox).await
+            }
+            BatchQuery::RoutePoints { dates, points, parameters } => {
+                client.route_query_points(dates, points, parameters).await
+            }
+            BatchQuery::RoutePostal { dates, postals, parameters } => {
+                client.route_query_postal(dates, postals, parameters).await
+            }
+        }
+    }
+}
+
+/// Options for [`APIClient::query_batch`].
+#[derive(Clone, Debug)]
+pub struct BatchOptions {
+    /// Maximum number of queries in flight at the same time.
+    pub concurrency: usize,
+    /// Token to cancel the batch. Queries that did not finish yet return [`ConnectorError::Cancelled`].
+    pub cancel: Option<CancellationToken>,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            cancel: None,
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! # Blocking
+//! A synchronous wrapper around the [`APIClient`] for programs that do not use async Rust (e.g. simple
+//! command line tools). The module is only available with the ```blocking``` feature:
+//!
+//! ```toml
+//! [dependencies]
+//! meteomatics = { version = "0.2", features = ["blocking"] }
+//! ```
+//!
+//! The [`BlockingAPIClient`] offers the same queries as the [`APIClient`] and runs them on its own
+//! single-threaded runtime. It must not be used from within an async context (e.g. inside a function
+//! annotated with ```#[tokio::main]```), use the [`APIClient`] there instead.
+//!
+//! ```rust, no_run
+//! use chrono::{Duration, Utc};
+//! use meteomatics::{BlockingAPIClient, Point, TimeSeries};
+//!
+//! let client = BlockingAPIClient::new("ferris_loves_rustaceans", "0123456789", 10);
+//! let time_series = TimeSeries {
+//!     start: Utc::now(),
+//!     end: Utc::now() + Duration::days(1),
+//!     timedelta: Option::from(Duration::hours(1))
+//! };
+//! let parameters = vec![String::from("t_2m:C")];
+//! let coordinates = vec![Point { lat: 52.52, lon: 13.405 }];
+//! let df = client.query_time_series(&time_series, &parameters, &coordinates, &None).unwrap();
+//! println!("{:?}", df);
+//! ```
+
+use crate::availability::TimeRange;
+use crate::batch::{BatchOptions, BatchQuery};
+use crate::errors::ConnectorError;
+use crate::frames::{FrameManifest, FrameOptions};
+use crate::location::{BBox, Point};
+use crate::options::ToQueryOptions;
+use crate::util::{TimeSeries, UStatsResponse};
+use crate::{APIClient, APIClientBuilder};
+use bytes::Bytes;
+use polars::frame::DataFrame;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+/// Synchronous version of the [`APIClient`]. Every method blocks the current thread until the
+/// corresponding query of the [`APIClient`] finished.
+#[derive(Clone, Debug)]
+pub struct BlockingAPIClient {
+    client: APIClient,
+    runtime: Arc<Runtime>,
+}
+
+impl BlockingAPIClient {
+    /// Creates a new blocking client (see [`APIClient::new`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - Provide your username for the Meteomatics API account.
+    /// * `password` - Provide your password for the Meteomatics API account.
+    /// * `timeout_seconds` - Specifies the request timeout (for [`reqwest::Client`] in seconds).
+    pub fn new(username: &str, password: &str, timeout_seconds: u64) -> Self {
+        // safe to use unwrap, since we want to panic if the client cannot be created.
+        Self::from_client(APIClient::new(username, password, timeout_seconds)).unwrap()
+    }
+
+    /// Wraps an (e.g. with the [`APIClientBuilder`] configured) async client. Fails if the runtime
+    /// cannot be created.
+    pub fn from_client(client: APIClient) -> Result<Self, ConnectorError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(Self { client, runtime: Arc::new(runtime) })
+    }
+
+    /// Returns the wrapped async client.
+    pub fn client(&self) -> &APIClient {
+        &self.client
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// See [`APIClient::refresh_credentials`].
+    pub fn refresh_credentials(&self) {
+        self.block_on(self.client.refresh_credentials())
+    }
+
+    /// See [`APIClient::query_station_list`].
+    pub fn query_station_list<P: Display>(
+        &self,
+        location: &Option<&str>,
+        parameters: &Option<Vec<P>>,
+        elevation: &Option<u64>,
+        startdate: &Option<chrono::DateTime<chrono::Utc>>,
+        enddate: &Option<chrono::DateTime<chrono::Utc>>
+    ) -> Result<DataFrame, ConnectorError> {
+        self.block_on(self.client.query_station_list(location, parameters, elevation, startdate, enddate))
+    }
+
+    /// See [`APIClient::route_query_postal`].
+    pub fn route_query_postal<P: Display>(
+        &self,
+        dates: &[chrono::DateTime<chrono::Utc>],
+        pcodes: &[String],
+        params: &[P],
+    ) -> Result<DataFrame, ConnectorError> {
+        self.block_on(self.client.route_query_postal(dates, pcodes, params))
+    }
+
+    /// See [`APIClient::route_query_points`].
+    pub fn route_query_points<P: Display>(
+        &self,
+        dates: &[chrono::DateTime<chrono::Utc>],
+        points: &[Point],
+        params: &[P],
+    ) -> Result<DataFrame, ConnectorError> {
+        self.block_on(self.client.route_query_points(dates, points, params))
+    }
+
+    /// See [`APIClient::query_lightning`].
+    pub fn query_lightning(&self, time_series: &TimeSeries, bbox: &BBox) -> Result<DataFrame, ConnectorError> {
+        self.block_on(self.client.query_lightning(time_series, bbox))
+    }
+
+    /// See [`APIClient::query_user_features`].
+    pub fn query_user_features(&self) -> Result<UStatsResponse, ConnectorError> {
+        self.block_on(self.client.query_user_features())
+    }
+
+    /// See [`APIClient::query_init_dates`].
+    pub fn query_init_dates<P: Display>(
+        &self,
+        model: &str,
+        time_series: &TimeSeries,
+        parameters: &[P],
+    ) -> Result<DataFrame, ConnectorError> {
+        self.block_on(self.client.query_init_dates(model, time_series, parameters))
+    }
+
+    /// See [`APIClient::query_available_time_range`].
+    pub fn query_available_time_range<P: Display>(
+        &self,
+        model: &str,
+        parameters: &[P],
+    ) -> Result<Vec<TimeRange>, ConnectorError> {
+        self.block_on(self.client.query_available_time_range(model, parameters))
+    }
+
+    /// See [`APIClient::query_time_series`].
+    pub fn query_time_series<P: Display, O: ToQueryOptions + ?Sized>(
+        &self,
+        time_series: &TimeSeries,
+        parameters: &[P],
+        coordinates: &[Point],
+        optionals: &O,
+    ) -> Result<DataFrame, ConnectorError> {
+        self.block_on(self.client.query_time_series(time_series, parameters, coordinates, optionals))
+    }
+
+    /// See [`APIClient::query_time_series_postal`].
+    pub fn query_time_series_postal<P: Display, O: ToQueryOptions + ?Sized>(
+        &self,
+        time_series: &TimeSeries,
+        parameters: &[P],
+        postals: &[String],
+        optionals: &O,
+    ) -> Result<DataFrame, ConnectorError> {
+        self.block_on(self.client.query_time_series_postal(time_series, parameters, postals, optionals))
+    }
+
+    /// See [`APIClient::query_model_comparison`].
+    pub fn query_model_comparison<P: Display, O: ToQueryOptions + ?Sized>(
+        &self,
+        time_series: &TimeSeries,
+        parameters: &[P],
+        coordinates: &[Point],
+        models: &[String],
+        optionals: &O,
+    ) -> Result<DataFrame, ConnectorError> {
+        self.block_on(self.client.query_model_comparison(time_series, parameters, coordinates, models, optionals))
+    }
+
+    /// See [`APIClient::query_grid_pivoted`].
+    pub fn query_grid_pivoted<P: Display + ?Sized, O: ToQueryOptions + ?Sized>(
+        &self,
+        timestamp: &chrono::DateTime<chrono::Utc>,
+        parameter: &P,
+        bbox: &BBox,
+        optionals: &O,
+    ) -> Result<DataFrame, ConnectorError> {
+        self.block_on(self.client.query_grid_pivoted(timestamp, parameter, bbox, optionals))
+    }
+
+    /// See [`APIClient::query_grid_unpivoted`].
+    pub fn query_grid_unpivoted<P: Display, O: ToQueryOptions + ?Sized>(
+        &self,
+        timestamp: &chrono::DateTime<chrono::Utc>,
+        parameters: &[P],
+        bbox: &BBox,
+        optionals: &O,
+    ) -> Result<DataFrame, ConnectorError> {
+        self.block_on(self.client.query_grid_unpivoted(timestamp, parameters, bbox, optionals))
+    }
+
+    /// See [`APIClient::query_grid_unpivoted_time_series`].
+    pub fn query_grid_unpivoted_time_series<P: Display, O: ToQueryOptions + ?Sized>(
+        &self,
+        time_series: &TimeSeries,
+        parameters: &[P],
+        bbox: &BBox,
+        optionals: &O,
+    ) -> Result<DataFrame, ConnectorError> {
+        self.block_on(self.client.query_grid_unpivoted_time_series(time_series, parameters, bbox, optionals))
+    }
+
+    /// See [`APIClient::query_netcdf`].
+    pub fn query_netcdf<P: Display + ?Sized, O: ToQueryOptions + ?Sized>(
+        &self,
+        time_series: &TimeSeries,
+        parameter: &P,
+        bbox: &BBox,
+        file_name: &String,
+        optionals: &O,
+    ) -> Result<(), ConnectorError> {
+        self.block_on(self.client.query_netcdf(time_series, parameter, bbox, file_name, optionals))
+    }
+
+    /// See [`APIClient::query_netcdf_bytes`].
+    pub fn query_netcdf_bytes<P: Display + ?Sized, O: ToQueryOptions + ?Sized>(
+        &self,
+        time_series: &TimeSeries,
+        parameter: &P,
+        bbox: &BBox,
+        optionals: &O,
+    ) -> Result<Bytes, ConnectorError> {
+        self.block_on(self.client.query_netcdf_bytes(time_series, parameter, bbox, optionals))
+    }
+
+    /// See [`APIClient::query_grid_png`].
+    pub fn query_grid_png<P: Display + ?Sized, O: ToQueryOptions + ?Sized>(
+        &self,
+        date: &chrono::DateTime<chrono::Utc>,
+        parameter: &P,
+        bbox: &BBox,
+        file_name: &String,
+        optionals: &O,
+    ) -> Result<(), ConnectorError> {
+        self.block_on(self.client.query_grid_png(date, parameter, bbox, file_name, optionals))
+    }
+
+    /// See [`APIClient::query_grid_png_bytes`].
+    pub fn query_grid_png_bytes<P: Display + ?Sized, O: ToQueryOptions + ?Sized>(
+        &self,
+        date: &chrono::DateTime<chrono::Utc>,
+        parameter: &P,
+        bbox: &BBox,
+        optionals: &O,
+    ) -> Result<Bytes, ConnectorError> {
+        self.block_on(self.client.query_grid_png_bytes(date, parameter, bbox, optionals))
+    }
+
+    /// See [`APIClient::query_grid_png_timeseries`].
+    pub fn query_grid_png_timeseries<P: Display + ?Sized, O: ToQueryOptions + ?Sized>(
+        &self,
+        time_series: &TimeSeries,
+        parameter: &P,
+        bbox: &BBox,
+        prefixpath: &str,
+        optionals: &O,
+    ) -> Result<(), ConnectorError> {
+        self.block_on(self.client.query_grid_png_timeseries(time_series, parameter, bbox, prefixpath, optionals))
+    }
+
+    /// See [`APIClient::query_grid_png_frames`].
+    pub fn query_grid_png_frames<P: Display + ?Sized, O: ToQueryOptions + ?Sized>(
+        &self,
+        time_series: &TimeSeries,
+        parameter: &P,
+        bbox: &BBox,
+        prefixpath: &str,
+        optionals: &O,
+        options: &FrameOptions,
+    ) -> Result<FrameManifest, ConnectorError> {
+        self.block_on(self.client.query_grid_png_frames(time_series, parameter, bbox, prefixpath, optionals, options))
+    }
+
+    /// See [`APIClient::query_grid_png_timeseries_bytes`].
+    pub fn query_grid_png_timeseries_bytes<P: Display + ?Sized, O: ToQueryOptions + ?Sized>(
+        &self,
+        time_series: &TimeSeries,
+        parameter: &P,
+        bbox: &BBox,
+        optionals: &O,
+    ) -> Result<Vec<(chrono::DateTime<chrono::Utc>, Bytes)>, ConnectorError> {
+        self.block_on(self.client.query_grid_png_timeseries_bytes(time_series, parameter, bbox, optionals))
+    }
+
+    /// See [`APIClient::query_batch`].
+    pub fn query_batch<I>(&self, queries: I, options: &BatchOptions) -> Vec<Result<DataFrame, ConnectorError>>
+    where
+        I: IntoIterator<Item = BatchQuery>,
+    {
+        self.block_on(self.client.query_batch(queries, options))
+    }
+}
+
+impl APIClientBuilder {
+    /// Creates a [`BlockingAPIClient`] with the configuration of the builder.
+    pub fn build_blocking(self) -> Result<BlockingAPIClient, ConnectorError> {
+        BlockingAPIClient::from_client(self.build()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::location::Point;
+    use crate::testing::{Endpoint, FakeResponse, FakeServer};
+    use crate::util::TimeSeries;
+    use crate::APIClient;
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn blocking_client_runs_queries() {
+        // The server runs on its own runtime, the client blocks the test thread.
+        let server_runtime = tokio::runtime::Runtime::new().unwrap();
+        let body = "validdate;t_2m:C\n1989-11-09T18:00:00Z;6.8\n1989-11-09T19:00:00Z;6.5\n";
+        let server = server_runtime.block_on(FakeServer::start());
+        server.respond(Endpoint::TimeSeries, FakeResponse::csv(body));
+
+        let client = APIClient::builder("test_user", "test_password")
+            .base_url(server.url())
+            .build_blocking()
+            .unwrap();
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date + Duration::hours(1), timedelta: Some(Duration::hours(1)) };
+        let parameters = vec![String::from("t_2m:C")];
+        let coordinates = vec![Point { lat: 52.52, lon: 13.405 }];
+        let df = client.query_time_series(&time_series, &parameters, &coordinates, &None).unwrap();
+        assert_eq!(df.shape(), (2, 4));
+        assert_eq!(server.requests().len(), 1);
+    }
+}
@@ -0,0 +1,240 @@
+//! # Catalog
+//! An offline catalog of well-known parameters of the Meteomatics API with their units, levels,
+//! aggregations and intervals. It answers questions like "which units does ```wind_speed```
+//! support?" without a request, suggests names for misspelled parameters and can check the
+//! parameters of every query before it is sent (see [`crate::APIClientBuilder::parameter_check`]).
+//! Parameters that are missing from the catalog are rejected by [`Catalog::check`], while
+//! [`Catalog::check_known`] only rejects close misspellings and unsupported variants of known ones.
+//!
+//! The catalog that is bundled with the crate (see [`Catalog::bundled`]) is versioned and covers the
+//! most common parameters, not all of them. Parameters that are missing can be added with a catalog
+//! of your own (see [`Catalog::from_toml`]).
+//!
+//! ```rust
+//! use meteomatics::catalog::Catalog;
+//!
+//! let catalog = Catalog::bundled();
+//! assert!(catalog.get("wind_speed").unwrap().units.contains(&String::from("kmh")));
+//! assert_eq!(catalog.suggestions("wind_sped"), vec!["wind_speed"]);
+//! assert!(catalog.check_name("t_2m:C").is_ok());
+//! assert!(catalog.check_name("t_2m:X").is_err());
+//! ```
+
+use crate::errors::ConnectorError;
+use crate::parameter::{Aggregation, Parameter};
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+/// The catalog that is bundled with the crate.
+const BUNDLED: &str = include_str!("../data/parameters.toml");
+
+/// What the catalog knows about a parameter. Levels and intervals are exact values (e.g. "2m"),
+/// patterns with a number placeholder (e.g. "*m" for "2m" or "10m") or "*" for any value.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct ParameterInfo {
+    /// Name of the parameter (e.g. "t" or "wind_speed").
+    pub name: String,
+    /// Short description.
+    pub description: String,
+    /// Supported units (e.g. "C", "F" and "K").
+    pub units: Vec<String>,
+    /// Supported levels (none if the parameter has no level).
+    #[serde(default)]
+    pub levels: Vec<String>,
+    /// Supported aggregations.
+    #[serde(default)]
+    pub aggregations: Vec<Aggregation>,
+    /// Supported intervals (none if the parameter has no interval).
+    #[serde(default)]
+    pub intervals: Vec<String>,
+}
+
+impl ParameterInfo {
+    /// Checks if the parameter is available in a unit.
+    pub fn supports_unit(&self, unit: &str) -> bool {
+        self.units.iter().any(|u| u == unit)
+    }
+
+    /// Checks if the parameter is available at a level (e.g. "850hPa").
+    pub fn supports_level(&self, level: &str) -> bool {
+        self.levels.iter().any(|pattern| matches_pattern(pattern, level))
+    }
+
+    /// Checks if the parameter is available for an interval (e.g. "24h").
+    pub fn supports_interval(&self, interval: &str) -> bool {
+        self.intervals.iter().any(|pattern| matches_pattern(pattern, interval))
+    }
+}
+
+// Matches exact values, "*" and number placeholders like "*hPa".
+fn matches_pattern(pattern: &str, value: &str) -> bool {
+    match pattern.strip_prefix('*') {
+        Some("") => true,
+        Some(suffix) => value.strip_suffix(suffix).is_some_and(|number| {
+            let digits = number.strip_prefix('-').unwrap_or(number);
+            !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+        }),
+        None => pattern == value,
+    }
+}
+
+/// A versioned catalog of parameters.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Catalog {
+    /// Version of the catalog (e.g. "2026.10").
+    pub version: String,
+    /// The known parameters.
+    pub parameters: Vec<ParameterInfo>,
+}
+
+impl Catalog {
+    /// Returns the catalog that is bundled with the crate.
+    pub fn bundled() -> &'static Catalog {
+        static CATALOG: OnceLock<Catalog> = OnceLock::new();
+        CATALOG.get_or_init(|| Catalog::from_toml(BUNDLED).expect("the bundled parameter catalog is valid"))
+    }
+
+    /// Reads a catalog in the format of the bundled one (```data/parameters.toml``` in the crate).
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The TOML document.
+    pub fn from_toml(content: &str) -> Result<Self, ConnectorError> {
+        toml::from_str(content).map_err(|e| ConnectorError::LibraryError(format!("Invalid parameter catalog: {}", e)))
+    }
+
+    /// Returns the entry of a parameter by its name (e.g. "wind_speed", without level or unit).
+    pub fn get(&self, name: &str) -> Option<&ParameterInfo> {
+        self.parameters.iter().find(|info| info.name == name)
+    }
+
+    /// Returns up to three known names that are close to a (misspelled) name, the closest first.
+    pub fn suggestions(&self, name: &str) -> Vec<&str> {
+        let max_distance = (name.chars().count() / 3).max(2);
+        let mut candidates: Vec<(usize, &str)> = self
+            .parameters
+            .iter()
+            .map(|info| (edit_distance(name, &info.name), info.name.as_str()))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect();
+        candidates.sort();
+        candidates.into_iter().take(3).map(|(_, name)| name).collect()
+    }
+
+    /// Checks a parameter against the catalog. Fails with [`ConnectorError::UnknownParameter`] if the
+    /// name is not in the catalog or the unit, level, aggregation or interval is not supported.
+    pub fn check(&self, parameter: &Parameter) -> Result<(), ConnectorError> {
+        let unknown = |reason: String| ConnectorError::UnknownParameter(format!("{}: {}", parameter, reason));
+        let info = match self.get(&parameter.name) {
+            Some(info) => info,
+            None => return Err(unknown(self.unknown_name(&parameter.name))),
+        };
+        if !info.supports_unit(&parameter.unit) {
+            return Err(unknown(format!("unit {} is not supported (use {})", parameter.unit, info.units.join(", "))));
+        }
+        if let Some(level) = parameter.level.as_ref().filter(|level| !info.supports_level(level)) {
+            return Err(unknown(format!("level {} is not supported", level)));
+        }
+        if let Some(aggregation) = parameter.aggregation.filter(|a| !info.aggregations.contains(a)) {
+            return Err(unknown(format!("aggregation {} is not supported", aggregation)));
+        }
+        if let Some(interval) = parameter.interval.as_ref().filter(|interval| !info.supports_interval(interval)) {
+            return Err(unknown(format!("interval {} is not supported", interval)));
+        }
+        Ok(())
+    }
+
+    /// Checks a parameter as far as the catalog knows it, such that valid parameters that are missing
+    /// from the catalog pass. Fails with [`ConnectorError::UnknownParameter`] only for close
+    /// misspellings of known names (e.g. "precipp") and for known parameters with an unsupported unit,
+    /// level, aggregation or interval (e.g. "t_2m:X" or "t_2n:C", see [`Catalog::check`]).
+    pub fn check_known(&self, parameter: &Parameter) -> Result<(), ConnectorError> {
+        match self.get(&parameter.name).is_some() || self.is_close_to_known(&parameter.name) {
+            true => self.check(parameter),
+            false => Ok(()),
+        }
+    }
+
+    /// Parses a parameter (e.g. "t_2m:C") and checks it against the catalog (see [`Catalog::check`]).
+    pub fn check_name(&self, parameter: &str) -> Result<Parameter, ConnectorError> {
+        let parameter: Parameter = parameter.parse()?;
+        self.check(&parameter)?;
+        Ok(parameter)
+    }
+
+    // A name that is one edit away from a known name (e.g. "wind_sped") or a known name followed by
+    // something that looks like a level or interval (e.g. "t_2n"). Short names are not compared, as
+    // they are easily one edit apart (e.g. "t" and "tp").
+    fn is_close_to_known(&self, name: &str) -> bool {
+        let misspelled = name.chars().count() >= 5 && self.parameters.iter().any(|info| edit_distance(name, &info.name) == 1);
+        misspelled || self.parameters.iter().any(|info| {
+            name.strip_prefix(&info.name)
+                .and_then(|rest| rest.strip_prefix('_'))
+                .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit() || c == '-'))
+        })
+    }
+
+    // Explains why a name is unknown, e.g. a known parameter with an unsupported level ("t_2n").
+    fn unknown_name(&self, name: &str) -> String {
+        let tokens: Vec<&str> = name.split('_').collect();
+        if let Some(known) = (1..tokens.len()).rev().map(|n| tokens[..n].join("_")).find(|n| self.get(n).is_some()) {
+            return format!("{} is not a known level, aggregation or interval of {}", &name[known.len() + 1..], known);
+        }
+        match self.suggestions(name).as_slice() {
+            [] => format!("unknown parameter {}", name),
+            suggestions => format!("unknown parameter {} (did you mean {}?)", name, suggestions.join(", ")),
+        }
+    }
+}
+
+// Levenshtein distance between two names.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::catalog::Catalog;
+    use crate::errors::ConnectorError;
+
+    #[tokio::test]
+    async fn parameters_are_checked_against_the_catalog() {
+        let catalog = Catalog::bundled();
+        for valid in ["t_2m:C", "t_max_2m_24h:F", "t_850hPa:K", "t_-5cm:C", "precip_1h:mm", "wind_speed_mean_10m_1h:kmh"] {
+            assert!(catalog.check_name(valid).is_ok(), "{}", valid);
+        }
+
+        let message = |parameter: &str| match catalog.check_name(parameter) {
+            Err(ConnectorError::UnknownParameter(message)) => message,
+            other => panic!("unexpected result for {}: {:?}", parameter, other),
+        };
+        assert_eq!(message("precipp_1h:mm"), "precipp_1h:mm: unknown parameter precipp (did you mean precip?)");
+        assert_eq!(message("t_2m:X"), "t_2m:X: unit X is not supported (use C, F, K)");
+        assert_eq!(message("t_2n:C"), "t_2n:C: 2n is not a known level, aggregation or interval of t");
+        assert_eq!(message("precip_2h:mm"), "precip_2h:mm: interval 2h is not supported");
+        assert_eq!(message("wind_gusts_max_10m_1h:ms"), "wind_gusts_max_10m_1h:ms: aggregation max is not supported");
+
+        // Only the strict check rejects valid parameters that are missing from the catalog.
+        let known = |parameter: &str| catalog.check_known(&parameter.parse().unwrap());
+        assert!(known("frost_depth:cm").is_ok() && known("wind_speed_u_10m:ms").is_ok());
+        assert!(catalog.check_name("frost_depth:cm").is_err());
+        for invalid in ["precipp_1h:mm", "wind_sped_10m:ms", "t_2m:X", "t_2n:C", "precip_2h:mm"] {
+            assert!(matches!(known(invalid), Err(ConnectorError::UnknownParameter(_))), "{}", invalid);
+        }
+
+        let custom = Catalog::from_toml("version = \"1\"\n[[parameters]]\nname = \"t\"\ndescription = \"\"\nunits = [\"C\"]\n").unwrap();
+        assert!(custom.check_name("t:C").is_ok());
+        assert!(custom.check_name("t_2m:C").is_err());
+    }
+}
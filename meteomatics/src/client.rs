@@ -1,18 +1,348 @@
 //! # Client 
-//! The ```APIClient``` provides access to different query types.
+//! The ```APIClient``` provides access to different query types. A client is either created directly
+//! with [`APIClient::new`] or configured in more detail with the [`APIClientBuilder`] (e.g. to talk to
+//! a different endpoint than <https://api.meteomatics.com>).
+use crate::auth::{AuthMode, TokenManager, TokenPlacement, TokenResponse};
+use crate::availability::{
+    format_query_date, parse_init_dates, parse_time_ranges, TimeRange, TimeRangeCache, DEFAULT_TIME_RANGE_TTL
+};
+use crate::batch::{BatchOptions, BatchQuery};
+use crate::cache::{canonical_url, read_cacheable_body, CacheConfig, CacheMode, CachedResponse, ResponseCache};
+use crate::cassette::{Cassette, CassetteConfig, CassetteMode, Interaction};
+use crate::catalog::Catalog;
+use crate::download::{stream_to_writer, AtomicFile, DownloadProgress, FileFormat, ProgressCallback};
+use crate::comparison::combine_models;
+use crate::credentials::{CachedCredentials, CredentialProvider, Credentials, DEFAULT_CREDENTIALS_TTL};
+use crate::ensemble::reshape_ensemble;
 use crate::errors::ConnectorError;
+use crate::frames::{frame_file_name, is_complete_png, Frame, FrameManifest, FrameOptions, FrameStatus};
+use crate::options::{QueryOptions, ToQueryOptions};
+use crate::parameter::parameter_names;
+use crate::ratelimit::{hold_until_read, RateLimitConfig, RateLimiter};
+use crate::retry::{RetryEvent, RetryPolicy, RetryReason};
+use crate::split::{combine_parameter_chunks, concat_frames, group_by_location, time_windows, SplitLimits};
+use crate::tiling::{stitch_tiles, tiles, TileLimits};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, USER_AGENT};
 use reqwest::{Client, Response, StatusCode};
 use url::Url;
 use crate::location::{Point, BBox};
 use crate::util::*;
+use bytes::Bytes;
+use futures::future;
+use futures::stream::{self, StreamExt};
+use tokio::io::AsyncWrite;
+use std::fmt::Display;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 /// This is the entry point for users of the library.
-/// Please be aware that the password and username are **not** encrypted!
+/// Please be aware that the password and username are **not** encrypted! They are resolved through
+/// a [`CredentialProvider`] and kept for a limited time (see [`crate::credentials`]).
 #[derive(Clone, Debug)]
 pub struct APIClient {
     http_client: Client,
-    username: String,
-    password: String,
+    credentials: Arc<CachedCredentials>,
+    token: Option<Arc<TokenManager>>,
+    base_url: Url,
+    timeout: Option<std::time::Duration>,
+    default_headers: HeaderMap,
+    retry_policy: RetryPolicy,
+    retry_count: Arc<AtomicU64>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    split_limits: Option<SplitLimits>,
+    tile_limits: Option<TileLimits>,
+    catalog: Option<Arc<Catalog>>,
+    strict_parameter_check: bool,
+    time_ranges: Option<Arc<TimeRangeCache>>,
+    cache: Option<Arc<ResponseCache>>,
+    cassette: Option<Arc<Cassette>>,
+    download_progress: Option<ProgressCallback>,
+}
+
+/// Builder for an [`APIClient`] that allows to configure the endpoint and the HTTP behaviour of the 
+/// client. All settings are optional, by default the client talks to <https://api.meteomatics.com>.
+/// 
+/// # Examples
+/// 
+/// ```rust, no_run
+/// use meteomatics::APIClient;
+/// 
+/// let client = APIClient::builder("ferris_loves_rustaceans", "0123456789")
+///     .base_url("https://egress.example.com/meteomatics/")
+///     .timeout_seconds(30)
+///     .user_agent("forecast-dashboard/1.0")
+///     .default_header("x-request-source", "dashboard")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct APIClientBuilder {
+    credentials: Arc<dyn CredentialProvider>,
+    credentials_ttl: std::time::Duration,
+    auth: AuthMode,
+    base_url: String,
+    timeout: Option<std::time::Duration>,
+    user_agent: Option<String>,
+    default_headers: Vec<(String, String)>,
+    http_client: Option<Client>,
+    retry_policy: RetryPolicy,
+    rate_limit: Option<RateLimitConfig>,
+    split_limits: Option<SplitLimits>,
+    tile_limits: Option<TileLimits>,
+    catalog: Option<Catalog>,
+    strict_parameter_check: bool,
+    time_range_check: bool,
+    time_range_ttl: std::time::Duration,
+    cache: Option<CacheConfig>,
+    cassette: Option<CassetteConfig>,
+    download_progress: Option<ProgressCallback>,
+}
+
+impl APIClientBuilder {
+    /// Creates a new builder with the credentials for the Meteomatics API account.
+    pub fn new(username: &str, password: &str) -> Self {
+        Self::with_provider(Credentials::new(username, password))
+    }
+
+    /// Creates a new builder that resolves the credentials through the given provider (see
+    /// [`crate::credentials`]).
+    pub fn with_provider<P: CredentialProvider + 'static>(provider: P) -> Self {
+        Self {
+            credentials: Arc::new(provider),
+            credentials_ttl: DEFAULT_CREDENTIALS_TTL,
+            auth: AuthMode::Basic,
+            base_url: BASE_URL.to_string(),
+            timeout: None,
+            user_agent: None,
+            default_headers: Vec::new(),
+            http_client: None,
+            retry_policy: RetryPolicy::none(),
+            rate_limit: None,
+            split_limits: None,
+            tile_limits: None,
+            catalog: None,
+            strict_parameter_check: false,
+            time_range_check: false,
+            time_range_ttl: DEFAULT_TIME_RANGE_TTL,
+            cache: None,
+            cassette: None,
+            download_progress: None,
+        }
+    }
+
+    /// Replaces the credentials of the builder with the given provider.
+    pub fn credentials<P: CredentialProvider + 'static>(mut self, provider: P) -> Self {
+        self.credentials = Arc::new(provider);
+        self
+    }
+
+    /// Sets how long resolved credentials are kept before the provider is asked again. Defaults to
+    /// [`DEFAULT_CREDENTIALS_TTL`] (15 minutes).
+    pub fn credentials_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.credentials_ttl = ttl;
+        self
+    }
+
+    /// Sets how the requests are authenticated (see [`crate::auth`]). By default the credentials are
+    /// sent with every request.
+    pub fn auth(mut self, auth: AuthMode) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Sets the URL under which the API is reachable (e.g. a corporate gateway or a local server).
+    /// A missing trailing '/' is added, such that queries are appended to the full path.
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    /// Sets the request timeout in seconds.
+    pub fn timeout_seconds(mut self, timeout_seconds: u64) -> Self {
+        self.timeout = Some(std::time::Duration::from_secs(timeout_seconds));
+        self
+    }
+
+    /// Sets the request timeout.
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the ```User-Agent``` header sent with every request.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// Adds a header that is sent with every request. 
+    pub fn default_header(mut self, name: &str, value: &str) -> Self {
+        self.default_headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Uses a pre-built [`reqwest::Client`] (e.g. with a proxy or custom TLS configuration) instead 
+    /// of creating a new one. Timeout, user agent and default headers are still applied per request.
+    pub fn http_client(mut self, http_client: Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Sets the [`RetryPolicy`] for transient failures (by default requests are not retried).
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Enables the client-side rate limiting based on the limits of the account (see [`RateLimiter`]).
+    pub fn rate_limit(mut self, config: RateLimitConfig) -> Self {
+        self.rate_limit = Some(config);
+        self
+    }
+
+    /// Splits time series queries that exceed the given limits into several requests (see
+    /// [`SplitLimits`]). By default queries are never split.
+    pub fn split_limits(mut self, split_limits: SplitLimits) -> Self {
+        self.split_limits = Some(split_limits);
+        self
+    }
+
+    /// Splits the bounding box of unpivoted grid queries into tiles that are fetched concurrently (see
+    /// [`TileLimits`]). By default grids are fetched with a single request.
+    pub fn grid_tiling(mut self, tile_limits: TileLimits) -> Self {
+        self.tile_limits = Some(tile_limits);
+        self
+    }
+
+    /// Checks the parameters of every query against a parameter catalog before the request is sent
+    /// (e.g. [`Catalog::bundled`], see [`crate::catalog`]). Queries with close misspellings of known
+    /// parameters or unsupported units, levels or intervals fail with
+    /// [`ConnectorError::UnknownParameter`] (see [`Catalog::check_known`]), parameters that are missing
+    /// from the catalog are left to the API. By default parameters are only checked by the API.
+    pub fn parameter_check(mut self, catalog: Catalog) -> Self {
+        self.catalog = Some(catalog);
+        self.strict_parameter_check = false;
+        self
+    }
+
+    /// Like [`APIClientBuilder::parameter_check`], but queries with parameters that are missing from
+    /// the catalog fail as well (see [`Catalog::check`]). Useful with a catalog that covers all
+    /// parameters of the account.
+    pub fn strict_parameter_check(mut self, catalog: Catalog) -> Self {
+        self.catalog = Some(catalog);
+        self.strict_parameter_check = true;
+        self
+    }
+
+    /// Checks the dates of every time series and grid query against the time range for which the model
+    /// (```model``` or ```source``` option, "mix" by default) has data of the parameters before the
+    /// request is sent (see [`APIClient::query_available_time_range`]). Queries outside the range fail
+    /// with [`ConnectorError::UnavailableTimeRange`], as do parameters the model has no data of. The
+    /// ranges are kept per model and parameter (see [`APIClientBuilder::time_range_ttl`]), such that
+    /// only the first query of a parameter costs an additional request. The check is disabled by
+    /// default.
+    pub fn time_range_check(mut self, enabled: bool) -> Self {
+        self.time_range_check = enabled;
+        self
+    }
+
+    /// Sets how long the time range check keeps the ranges before they are requested again. Defaults
+    /// to [`DEFAULT_TIME_RANGE_TTL`] (10 minutes).
+    pub fn time_range_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.time_range_ttl = ttl;
+        self
+    }
+
+    /// Stores the responses of the API on disk and answers repeated queries from there (see
+    /// [`ResponseCache`]). By default nothing is cached.
+    pub fn cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(config);
+        self
+    }
+
+    /// Records the HTTP traffic of the client to a cassette or replays it from there (see
+    /// [`Cassette`]). By default all requests are sent to the API.
+    pub fn cassette(mut self, config: CassetteConfig) -> Self {
+        self.cassette = Some(config);
+        self
+    }
+
+    /// Registers a callback that reports the progress of file downloads (NetCDF and PNG) after every
+    /// received chunk.
+    pub fn on_download_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&DownloadProgress) + Send + Sync + 'static
+    {
+        self.download_progress = Some(ProgressCallback::new(callback));
+        self
+    }
+
+    /// Creates the [`APIClient`]. Fails if the base URL or one of the headers is invalid, if the
+    /// cache directory cannot be created or if the cassette to replay cannot be read.
+    pub fn build(self) -> Result<APIClient, ConnectorError> {
+        // Make sure the queries are appended to the full path of the base URL.
+        let mut base_url = self.base_url;
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+        let base_url = Url::parse(&base_url)?;
+        if base_url.cannot_be_a_base() {
+            return Err(ConnectorError::LibraryError(format!("Invalid base URL: {}", base_url)));
+        }
+
+        let mut default_headers = HeaderMap::new();
+        if let Some(user_agent) = &self.user_agent {
+            let value = HeaderValue::from_str(user_agent)
+                .map_err(|e| ConnectorError::LibraryError(e.to_string()))?;
+            default_headers.insert(USER_AGENT, value);
+        }
+        for (name, value) in &self.default_headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| ConnectorError::LibraryError(e.to_string()))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| ConnectorError::LibraryError(e.to_string()))?;
+            default_headers.append(name, value);
+        }
+
+        let http_client = match self.http_client {
+            Some(http_client) => http_client,
+            None => Client::builder().build()?,
+        };
+
+        let cache = match self.cache {
+            Some(config) => Some(Arc::new(ResponseCache::new(config)?)),
+            None => None,
+        };
+        let cassette = match self.cassette {
+            Some(config) => Some(Arc::new(Cassette::new(config)?)),
+            None => None,
+        };
+
+        Ok(APIClient {
+            http_client,
+            credentials: Arc::new(CachedCredentials::new(self.credentials, self.credentials_ttl)),
+            token: match self.auth {
+                AuthMode::Basic => None,
+                AuthMode::Token(config) => Some(Arc::new(TokenManager::new(config))),
+            },
+            base_url,
+            timeout: self.timeout,
+            default_headers,
+            retry_policy: self.retry_policy,
+            retry_count: Arc::new(AtomicU64::new(0)),
+            rate_limiter: self.rate_limit.map(|config| Arc::new(RateLimiter::new(config))),
+            split_limits: self.split_limits,
+            tile_limits: self.tile_limits,
+            catalog: self.catalog.map(Arc::new),
+            strict_parameter_check: self.strict_parameter_check,
+            time_ranges: self.time_range_check.then(|| Arc::new(TimeRangeCache::new(self.time_range_ttl))),
+            cache,
+            cassette,
+            download_progress: self.download_progress,
+        })
+    }
 }
 
 impl APIClient {
@@ -33,16 +363,62 @@ impl APIClient {
     /// ```
     pub fn new(username: &str, password: &str, timeout_seconds: u64) -> Self {
         // safe to use unwrap, since we want to panic if the client builder fails.
-        let http_client = Client::builder()
-            .timeout(std::time::Duration::from_secs(timeout_seconds))
+        APIClientBuilder::new(username, password)
+            .timeout_seconds(timeout_seconds)
             .build()
-            .unwrap();
+            .unwrap()
+    }
 
-        Self {
-            http_client: http_client,
-            username: username.to_string(),
-            password: password.to_string(),
-        }
+    /// Returns an [`APIClientBuilder`] to configure a new client in more detail.
+    /// 
+    /// # Arguments
+    ///
+    /// * `username` - Provide your username for the Meteomatics API account.
+    /// * `password` - Provide your password for the Meteomatics API account.
+    pub fn builder(username: &str, password: &str) -> APIClientBuilder {
+        APIClientBuilder::new(username, password)
+    }
+
+    /// Returns an [`APIClientBuilder`] that resolves the credentials through the given provider 
+    /// (e.g. environment variables or a profile, see [`crate::credentials`]).
+    /// 
+    /// # Arguments
+    ///
+    /// * `provider` - The source of the credentials for the Meteomatics API account.
+    pub fn builder_with<P: CredentialProvider + 'static>(provider: P) -> APIClientBuilder {
+        APIClientBuilder::with_provider(provider)
+    }
+
+    /// Returns the base URL the client sends its queries to.
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Returns the current (parallel, per minute) request limits if rate limiting is enabled.
+    pub fn rate_limits(&self) -> Option<(Option<u32>, Option<u32>)> {
+        self.rate_limiter.as_ref().map(|limiter| limiter.limits())
+    }
+
+    /// Returns the response cache if caching is enabled (e.g. to clear it).
+    pub fn cache(&self) -> Option<&ResponseCache> {
+        self.cache.as_deref()
+    }
+
+    /// Returns the cassette if the client records or replays its traffic (e.g. to save a recording
+    /// before the client is dropped).
+    pub fn cassette(&self) -> Option<&Cassette> {
+        self.cassette.as_deref()
+    }
+
+    /// Returns the number of retries this client (and its clones) performed so far.
+    pub fn retry_count(&self) -> u64 {
+        self.retry_count.load(Ordering::Relaxed)
+    }
+
+    /// Drops the kept credentials, such that the next request resolves them again through the
+    /// provider (e.g. after a password was rotated).
+    pub async fn refresh_credentials(&self) {
+        self.credentials.refresh().await;
     }
 
     /// Finds weather measurement stations matching certains criteria. 
@@ -50,26 +426,29 @@ impl APIClient {
     /// # Arguments
     /// 
     /// * `location` - lat/lon pair (47.3,9.3), bounding box (47.3,9.3_40,10) or other specifier 
-    /// (germany)  specifying the location
+    ///   (germany)  specifying the location
     /// * `elevation` - Elevation in m (2500)
-    /// * `parameters` - A number of parameter names (t_2m:C, wind_speed_10m:ms)
+    /// * `parameters` - Names of parameters or [`crate::Parameter`]s (t_2m:C, wind_speed_10m:ms)
     /// * `startdate` - The earliest time you are interested in
     /// * `enddate` - The latest time you are interested in 
-    pub async fn query_station_list(
+    pub async fn query_station_list<P: Display>(
         &self,
         location: &Option<&str>,
-        parameters: &Option<Vec<&str>>,
+        parameters: &Option<Vec<P>>,
         elevation: &Option<u64>,
         startdate: &Option<chrono::DateTime<chrono::Utc>>,
         enddate: &Option<chrono::DateTime<chrono::Utc>>
     ) -> std::result::Result<polars::frame::DataFrame, ConnectorError> {
+        let names = parameters.as_ref().map(|parameters| parameter_names(parameters));
+        let names: Option<Vec<&str>> = names.as_ref().map(|names| names.iter().map(String::as_str).collect());
+
         // Create the query specs
         let query_specs = build_station_list_query_specs(
-            location, parameters, elevation, startdate, enddate
+            location, &names, elevation, startdate, enddate
         ).await;
 
         // Create the full URL
-        let full_url = build_url(&query_specs).await.map_err(|_| ConnectorError::ParseError)?;
+        let full_url = build_url_from(&self.base_url, &query_specs).await?;
 
         // Get the query result
         let result = self.do_http_get(full_url).await;
@@ -78,17 +457,12 @@ impl APIClient {
         match result {
             Ok(response) => match response.status() {
                 StatusCode::OK => {
-                    let df = parse_response_to_df(response).await
-                    .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
+                    let df = parse_response_to_df(response).await?;
                     Ok(df)
                 }
-                status => Err(ConnectorError::HttpError(
-                    status.to_string(),
-                    response.text().await.unwrap(),
-                    status,
-                )),
+                _ => Err(error_from_response(response).await),
             },
-            Err(e) => Err(ConnectorError::ReqwestError(e.to_string())),
+            Err(e) => Err(e),
         }
     }
 
@@ -98,7 +472,7 @@ impl APIClient {
     /// 
     /// * `dates` - These dates specify the points in time for the respective locations. 
     /// * `pcodes` - Specify locations based on their zip code (postal code e.g. "postal_CH9000").
-    /// * `params` - Names of individual parameters (e.g. "t_2m:C" or "wind_speed_10m:ms").
+    /// * `params` - Names of individual parameters or [`crate::Parameter`]s (e.g. "t_2m:C" or "wind_speed_10m:ms").
     ///  
     /// # Examples
     ///
@@ -115,12 +489,18 @@ impl APIClient {
     ///     let df_route = client.route_query_postal(&dates, &pcodes, &params).await.unwrap();
     /// }
     /// ```
-    pub async fn route_query_postal(
+    pub async fn route_query_postal<P>(
         &self,
         dates: &[chrono::DateTime<chrono::Utc>],
         pcodes: &[String],
-        params: &[String],
-    ) -> std::result::Result<polars::frame::DataFrame, ConnectorError> {
+        params: &[P],
+    ) -> std::result::Result<polars::frame::DataFrame, ConnectorError>
+    where
+        P: Display,
+    {
+        let params = &parameter_names(params);
+        self.check_parameters(params)?;
+
         // Create the dates formatted string
         let dates_str: String = dates.iter().map(|d| d.to_rfc3339()).collect::<Vec<String>>().join(",");
 
@@ -134,7 +514,7 @@ impl APIClient {
         let query_specs = build_route_query_specs(&dates_str, &params_str, &points_str).await;
 
         // Create the full URL
-        let full_url = build_url(&query_specs).await.map_err(|_| ConnectorError::ParseError)?;
+        let full_url = build_url_from(&self.base_url, &query_specs).await?;
 
         // Get the query result
         let result = self.do_http_get(full_url).await;
@@ -143,17 +523,12 @@ impl APIClient {
         match result {
             Ok(response) => match response.status() {
                 StatusCode::OK => {
-                    let df = parse_response_to_df(response).await
-                        .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
+                    let df = parse_response_to_df(response).await?;
                     Ok(df)
                 }
-                status => Err(ConnectorError::HttpError(
-                    status.to_string(),
-                    response.text().await.unwrap(),
-                    status,
-                )),
+                _ => Err(error_from_response(response).await),
             },
-            Err(e) => Err(ConnectorError::ReqwestError(e.to_string())),
+            Err(e) => Err(e),
         }
     }
 
@@ -163,7 +538,7 @@ impl APIClient {
     /// 
     /// * `dates` - These dates specify the points in time for the respective locations.
     /// * `points` - Specify locations based on latitude and longitude (see [`crate::location::Point`]).
-    /// * `params` - Names of individual parameters (e.g. "t_2m:C" or "wind_speed_10m:ms").
+    /// * `params` - Names of individual parameters or [`crate::Parameter`]s (e.g. "t_2m:C" or "wind_speed_10m:ms").
     /// 
     /// # Examples
     ///
@@ -191,12 +566,18 @@ impl APIClient {
     ///     let df_route = client.route_query_points(&dates, &coords, &parameters).await.unwrap();
     /// }
     /// ```
-    pub async fn route_query_points(
+    pub async fn route_query_points<P>(
         &self,
         dates: &[chrono::DateTime<chrono::Utc>],
         points: &[crate::location::Point],
-        params: &[String],
-    ) -> std::result::Result<polars::frame::DataFrame, ConnectorError> {
+        params: &[P],
+    ) -> std::result::Result<polars::frame::DataFrame, ConnectorError>
+    where
+        P: Display,
+    {
+        let params = &parameter_names(params);
+        self.check_parameters(params)?;
+
         // Create the dates formatted string
         let dates_str: String = dates.iter().map(|d| d.to_rfc3339()).collect::<Vec<String>>().join(",");
 
@@ -210,7 +591,7 @@ impl APIClient {
         let query_specs = build_route_query_specs(&dates_str, &params_str, &points_str).await;
 
         // Create the full URL
-        let full_url = build_url(&query_specs).await.map_err(|_| ConnectorError::ParseError)?;
+        let full_url = build_url_from(&self.base_url, &query_specs).await?;
 
         // Get the query result
         let result = self.do_http_get(full_url).await;
@@ -219,17 +600,12 @@ impl APIClient {
         match result {
             Ok(response) => match response.status() {
                 StatusCode::OK => {
-                    let df = parse_response_to_df(response).await
-                        .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
+                    let df = parse_response_to_df(response).await?;
                     Ok(df)
                 }
-                status => Err(ConnectorError::HttpError(
-                    status.to_string(),
-                    response.text().await.unwrap(),
-                    status,
-                )),
+                _ => Err(error_from_response(response).await),
             },
-            Err(e) => Err(ConnectorError::ReqwestError(e.to_string())),
+            Err(e) => Err(e),
         }
     }
 
@@ -289,7 +665,7 @@ impl APIClient {
         let query_specs = build_grid_ts_lightning_query_specs(time_series, &coords_str).await;
 
         // Create the full URL
-        let full_url = build_url(&query_specs).await.map_err(|_| ConnectorError::ParseError)?;
+        let full_url = build_url_from(&self.base_url, &query_specs).await?;
 
         // Get the query result
         let result = self.do_http_get(full_url).await;
@@ -298,8 +674,7 @@ impl APIClient {
         match result {
             Ok(response) => match response.status() {
                 StatusCode::OK => {
-                    let mut df = parse_response_to_df(response).await
-                        .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
+                    let mut df = parse_response_to_df(response).await?;
                     df.rename("stroke_time:sql", "validdate")
                         .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
                     df.rename("stroke_lat:d", "lat")
@@ -308,13 +683,9 @@ impl APIClient {
                         .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
                     Ok(df)
                 }
-                status => Err(ConnectorError::HttpError(
-                    status.to_string(),
-                    response.text().await.unwrap(),
-                    status,
-                )),
+                _ => Err(error_from_response(response).await),
             },
-            Err(e) => Err(ConnectorError::ReqwestError(e.to_string())),
+            Err(e) => Err(e),
         }
     }
 
@@ -333,7 +704,7 @@ impl APIClient {
     /// ```
     pub async fn query_user_features(&self) -> Result<UStatsResponse, ConnectorError>{
         let query_specs = String::from("user_stats_json");
-        let full_url = build_url(&query_specs).await.map_err(|_| ConnectorError::ParseError)?;
+        let full_url = build_url_from(&self.base_url, &query_specs).await?;
         let result = self.do_http_get(full_url).await;
         match result {
             Ok(response) => match response.status() {
@@ -341,22 +712,143 @@ impl APIClient {
                     let user_stats = extract_user_statistics(response).await?;
                     Ok(user_stats)
                 }
-                status => Err(ConnectorError::HttpError(
-                    status.to_string(),
-                    response.text().await.unwrap(),
-                    status,
-                )),
+                _ => Err(error_from_response(response).await),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the model runs that produced the data of a model: the initialization time of the run
+    /// for every valid time of the time series (rows) and parameter (columns). All columns, including
+    /// ```validdate```, are ```Datetime``` columns; valid times without a run are missing (see
+    /// [`crate::availability`]).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `model` - The model (e.g. "ecmwf-ifs" or "dwd-icon-eu").
+    /// * `time_series` - The valid times (time and date of start and a timedelta).
+    /// * `parameters` - Names of individual parameters or [`crate::Parameter`]s (e.g. "t_2m:C" or "wind_speed_10m:ms").
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust, no_run
+    /// use chrono::{Utc, Duration};
+    /// use meteomatics::{APIClient, TimeSeries};
+    /// 
+    /// #[tokio::main] 
+    /// async fn main() {
+    ///     let client = APIClient::new("ferris_loves_rustaceans", "0123456789", 10);
+    ///     let time_series = TimeSeries {
+    ///         start: Utc::now(),
+    ///         end: Utc::now() + Duration::days(1),
+    ///         timedelta: Option::from(Duration::hours(6))
+    ///     };
+    ///     let df_init_dates = client
+    ///         .query_init_dates("ecmwf-ifs", &time_series, &[String::from("t_2m:C")])
+    ///         .await
+    ///         .unwrap();
+    /// }
+    /// ```
+    pub async fn query_init_dates<P>(
+        &self,
+        model: &str,
+        time_series: &TimeSeries,
+        parameters: &[P],
+    ) -> Result<polars::frame::DataFrame, ConnectorError>
+    where
+        P: Display,
+    {
+        let parameters = &parameter_names(parameters);
+        self.check_parameters(parameters)?;
+
+        // Create the query specs
+        let query_specs = build_init_date_query_specs(model, time_series, parameters).await;
+
+        // Create the full URL
+        let full_url = build_url_from(&self.base_url, &query_specs).await?;
+
+        // Get the query result
+        let result = self.do_http_get(full_url).await;
+
+        // Match the result
+        match result {
+            Ok(response) => match response.status() {
+                StatusCode::OK => {
+                    let df = parse_response_to_df(response).await?;
+                    parse_init_dates(df)
+                }
+                _ => Err(error_from_response(response).await),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the time ranges for which a model has data of the parameters, one per parameter.
+    /// Parameters without data are returned without start and end (see [`TimeRange::has_data`]).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `model` - The model (e.g. "mix" or "ecmwf-ifs").
+    /// * `parameters` - Names of individual parameters or [`crate::Parameter`]s (e.g. "t_2m:C" or "wind_speed_10m:ms").
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust, no_run
+    /// use meteomatics::APIClient;
+    /// 
+    /// #[tokio::main] 
+    /// async fn main() {
+    ///     let client = APIClient::new("ferris_loves_rustaceans", "0123456789", 10);
+    ///     let ranges = client
+    ///         .query_available_time_range("ecmwf-ifs", &[String::from("t_2m:C")])
+    ///         .await
+    ///         .unwrap();
+    ///     for range in ranges {
+    ///         println!("{}", range);
+    ///     }
+    /// }
+    /// ```
+    pub async fn query_available_time_range<P>(
+        &self,
+        model: &str,
+        parameters: &[P],
+    ) -> Result<Vec<TimeRange>, ConnectorError>
+    where
+        P: Display,
+    {
+        let parameters = &parameter_names(parameters);
+        self.check_parameters(parameters)?;
+
+        // Create the query specs
+        let query_specs = build_time_range_query_specs(model, parameters).await;
+
+        // Create the full URL
+        let full_url = build_url_from(&self.base_url, &query_specs).await?;
+
+        // Get the query result
+        let result = self.do_http_get(full_url).await;
+
+        // Match the result
+        match result {
+            Ok(response) => match response.status() {
+                StatusCode::OK => {
+                    let df = parse_response_to_df(response).await?;
+                    parse_time_ranges(&df)
+                }
+                _ => Err(error_from_response(response).await),
             },
-            Err(e) => Err(ConnectorError::ReqwestError(e.to_string())),
+            Err(e) => Err(e),
         }
     }
 
     /// Download a ```polars``` DataFrame from the API for one or more ```Point``` locations.
+    /// With an ensemble selection ([`crate::options::QueryOptions::ens_select`]) the DataFrame holds
+    /// one row per member, statistic or quantile (see [`crate::ensemble`]).
     /// 
     /// # Arguments
     /// 
     /// * `time_series` - Defines the temporal extent (time and date of start and a timedelta).
-    /// * `parameters` - Names of individual parameters (e.g. "t_2m:C" or "wind_speed_10m:ms").
+    /// * `parameters` - Names of individual parameters or [`crate::Parameter`]s (e.g. "t_2m:C" or "wind_speed_10m:ms").
     /// * `coordinates` - Individual point locations.
     /// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
     /// 
@@ -392,29 +884,65 @@ impl APIClient {
     ///         .unwrap();
     /// }
     /// ```
-    pub async fn query_time_series(
+    pub async fn query_time_series<P, O>(
+        &self,
+        time_series: &TimeSeries,
+        parameters: &[P],
+        coordinates: &[Point],
+        optionals: &O,
+    ) -> Result<polars::frame::DataFrame, ConnectorError>
+    where
+        P: Display,
+        O: ToQueryOptions + ?Sized,
+    {
+        let parameters = &parameter_names(parameters);
+        self.check_parameters(parameters)?;
+        let optionals = &optionals.to_query_options()?;
+        self.check_time_range(&time_series.start, &time_series.end, parameters, optionals).await?;
+        self.time_series_query(time_series, parameters, coordinates, optionals).await
+    }
+
+    /// Runs a time series query whose parameters, options and time range are already checked.
+    async fn time_series_query(
+        &self,
+        time_series: &TimeSeries,
+        parameters: &[String],
+        coordinates: &[Point],
+        optionals: &QueryOptions,
+    ) -> Result<polars::frame::DataFrame, ConnectorError> {
+        let df = match &self.split_limits {
+            Some(limits) => {
+                self.query_split(limits, time_series, parameters, coordinates, optionals, |ts, params, chunk| async move {
+                    self.fetch_time_series(&ts, &params, chunk, optionals).await
+                }).await
+            }
+            None => self.fetch_time_series(time_series, parameters, coordinates, optionals).await,
+        }?;
+        reshape_ensemble(df, optionals)
+    }
+
+    /// Downloads a time series for one or more ```Point``` locations with a single request.
+    async fn fetch_time_series(
         &self,
         time_series: &TimeSeries,
         parameters: &[String],
         coordinates: &[Point],
-        optionals: &Option<Vec<String>>,
+        optionals: &QueryOptions,
     ) -> Result<polars::frame::DataFrame, ConnectorError> {
         // Check if there is only a single Point in the coordinates. This is important because in this
         // case the HTTP "csv" response does not contain the information about the location (-.-). To 
         // produce a consistent DataFrame we need to create a lat and lon column (as does the python
         // connector).
-        let needs_latlon: bool = coordinates.len() == 1;
-
         // Create the coordinates
         let coords_str = points_to_str(coordinates).await;
 
         // Create the query specifications (time, location, etc.)
         let query_specs = build_ts_query_specs(
             time_series, parameters, &coords_str, optionals, "csv"
-        ).await;
+        ).await?;
 
         // Create the complete URL
-        let full_url = build_url(&query_specs).await.map_err(|_| ConnectorError::ParseError)?;
+        let full_url = build_url_from(&self.base_url, &query_specs).await?;
 
         // Get the query result
         let result = self.do_http_get(full_url).await;
@@ -423,35 +951,28 @@ impl APIClient {
         match result {
             Ok(response) => match response.status() {
                 StatusCode::OK => {
-                    if needs_latlon {
-                        let df = parse_response_to_df(response).await
-                            .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
-                        let df = df_add_latlon(df, coordinates.get(0).unwrap()).await
-                            .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
-                        Ok(df)
-                    } else {
-                        let df = parse_response_to_df(response).await
-                            .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
-                        Ok(df)
+                    let df = parse_response_to_df(response).await?;
+                    match coordinates {
+                        [coordinate] => df_add_latlon(df, coordinate).await
+                            .map_err(|e| ConnectorError::PolarsError(e.to_string())),
+                        _ => Ok(df),
                     }
                 }
-                status => Err(ConnectorError::HttpError(
-                    status.to_string(),
-                    response.text().await.unwrap(),
-                    status,
-                )),
+                _ => Err(error_from_response(response).await),
             },
-            Err(e) => Err(ConnectorError::ReqwestError(e.to_string())),
+            Err(e) => Err(e),
         }
     }
 
     /// Download a ```polars``` DataFrame from the API for one or more postal code location identifiers
     /// (e.g. postal_CH8000, postal_CH9000).
+    /// With an ensemble selection ([`crate::options::QueryOptions::ens_select`]) the DataFrame holds
+    /// one row per member, statistic or quantile (see [`crate::ensemble`]).
     /// 
     /// # Arguments
     /// 
     /// * `time_series` - Defines the temporal extent (time and date of start and a timedelta).
-    /// * `parameters` - Names of individual parameters (e.g. "t_2m:C" or "wind_speed_10m:ms").
+    /// * `parameters` - Names of individual parameters or [`crate::Parameter`]s (e.g. "t_2m:C" or "wind_speed_10m:ms").
     /// * `postals` - Individual locations defined as postal codes (e.g. "postal_CH9000").
     /// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
     /// 
@@ -486,28 +1007,52 @@ impl APIClient {
     ///         .unwrap();
     /// }
     /// ```
-    pub async fn query_time_series_postal(&self,
+    pub async fn query_time_series_postal<P, O>(&self,
+        time_series: &TimeSeries,
+        parameters: &[P],
+        postals: &[String],
+        optionals: &O,
+    ) -> Result<polars::frame::DataFrame, ConnectorError>
+    where
+        P: Display,
+        O: ToQueryOptions + ?Sized,
+    {
+        let parameters = &parameter_names(parameters);
+        self.check_parameters(parameters)?;
+        let optionals = &optionals.to_query_options()?;
+        self.check_time_range(&time_series.start, &time_series.end, parameters, optionals).await?;
+        let df = match &self.split_limits {
+            Some(limits) => {
+                self.query_split(limits, time_series, parameters, postals, optionals, |ts, params, chunk| async move {
+                    self.fetch_time_series_postal(&ts, &params, chunk, optionals).await
+                }).await
+            }
+            None => self.fetch_time_series_postal(time_series, parameters, postals, optionals).await,
+        }?;
+        reshape_ensemble(df, optionals)
+    }
+
+    /// Downloads a time series for one or more postal codes with a single request.
+    async fn fetch_time_series_postal(&self,
         time_series: &TimeSeries,
         parameters: &[String],
         postals: &[String],
-        optionals: &Option<Vec<String>>,
+        optionals: &QueryOptions,
     ) -> Result<polars::frame::DataFrame, ConnectorError> {
         // Check if there is only a single zipcode in the postals. This is important because in this
         // case the HTTP "csv" response does not contain the information about the location (-.-). To 
         // produce a consistent DataFrame we need to create a postal_code column (as does the python
         // connector).
-        let needs_latlon: bool = postals.len() == 1;
-
         // Create the coordinates
         let coords_str = postals.join("+");
 
         // Create the query specifications (time, location, etc.)
         let query_specs = build_ts_query_specs(
             time_series, parameters, &coords_str, optionals, "csv"
-        ).await;
+        ).await?;
 
         // Create the complete URL
-        let full_url = build_url(&query_specs).await.map_err(|_| ConnectorError::ParseError)?;
+        let full_url = build_url_from(&self.base_url, &query_specs).await?;
 
         // Get the query result
         let result = self.do_http_get(full_url).await;
@@ -516,30 +1061,77 @@ impl APIClient {
         match result {
             Ok(response) => match response.status() {
                 StatusCode::OK => {
-                    if needs_latlon {
-                        let df = parse_response_to_df(response).await
-                            .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
-                        let df = df_add_postal(df, postals.get(0).unwrap()).await
-                            .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
-                        Ok(df)
-                    } else {
-                        let df = parse_response_to_df(response).await
-                            .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
-                        Ok(df)
+                    let df = parse_response_to_df(response).await?;
+                    match postals {
+                        [postal] => df_add_postal(df, postal).await
+                            .map_err(|e| ConnectorError::PolarsError(e.to_string())),
+                        _ => Ok(df),
                     }
                 }
-                status => Err(ConnectorError::HttpError(
-                    status.to_string(),
-                    response.text().await.unwrap(),
-                    status,
-                )),
+                _ => Err(error_from_response(response).await),
             },
-            Err(e) => Err(ConnectorError::ReqwestError(e.to_string())),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Download the same time series from several models (e.g. "mix", "ecmwf-ifs" and
+    /// "dwd-icon-eu") and combine them into a single ```polars``` DataFrame with a ```model``` column
+    /// (see [`crate::comparison`]). The models are first checked against the model set of the
+    /// account (see [`APIClient::query_user_features`]) and then queried concurrently. A model (or
+    /// source) that is set in the optionals is replaced by each of the models.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `time_series` - Defines the temporal extent (time and date of start and a timedelta).
+    /// * `parameters` - Names of individual parameters or [`crate::Parameter`]s (e.g. "t_2m:C" or "wind_speed_10m:ms").
+    /// * `coordinates` - Individual point locations.
+    /// * `models` - The models to compare.
+    /// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
+    pub async fn query_model_comparison<P, O>(
+        &self,
+        time_series: &TimeSeries,
+        parameters: &[P],
+        coordinates: &[Point],
+        models: &[String],
+        optionals: &O,
+    ) -> Result<polars::frame::DataFrame, ConnectorError>
+    where
+        P: Display,
+        O: ToQueryOptions + ?Sized,
+    {
+        let parameters = &parameter_names(parameters);
+        self.check_parameters(parameters)?;
+        let optionals = optionals.to_query_options()?;
+        if models.is_empty() {
+            return Err(ConnectorError::InvalidOption(String::from("no models to compare")));
+        }
+        let stats = self.query_user_features().await?.stats;
+        if let Some(model) = models.iter().find(|model| !stats.has_model(model)) {
+            return Err(ConnectorError::InvalidOption(format!(
+                "model {} is not in the model set of the account ({})", model, stats.models.join(", ")
+            )));
         }
+
+        let options: Vec<QueryOptions> = models
+            .iter()
+            .map(|model| optionals.clone().without("model").without("source").model(model))
+            .collect();
+        future::try_join_all(options.iter().map(|options| {
+            self.check_time_range(&time_series.start, &time_series.end, parameters, options)
+        })).await?;
+
+        let frames = future::try_join_all(models.iter().zip(&options).map(|(model, options)| async move {
+            let df = self.time_series_query(time_series, parameters, coordinates, options).await?;
+            Ok::<_, ConnectorError>((model.clone(), df))
+        })).await?;
+        combine_models(frames, parameters)
     }
 
     /// Download a ```polars``` DataFrame from the API for a grid of locations bounded by a 
     /// bounding box object ```BBox``` and a single parameter. 
+    /// A pivoted grid holds a single value per location, so an ensemble selection
+    /// ([`crate::options::QueryOptions::ens_select`]) fails with [`ConnectorError::InvalidOption`]
+    /// (see [`APIClient::query_grid_unpivoted`] for the long format).
     /// 
     /// # Arguments
     /// 
@@ -582,22 +1174,36 @@ impl APIClient {
     ///         .unwrap();
     /// }
     /// ```
-    pub async fn query_grid_pivoted(&self,
+    pub async fn query_grid_pivoted<P, O>(&self,
         timestamp: &chrono::DateTime<chrono::Utc>,
-        parameter: &String,
+        parameter: &P,
         bbox: &BBox,
-        optionals: &Option<Vec<String>>,
-    ) -> Result<polars::frame::DataFrame, ConnectorError> {
+        optionals: &O,
+    ) -> Result<polars::frame::DataFrame, ConnectorError>
+    where
+        P: Display + ?Sized,
+        O: ToQueryOptions + ?Sized,
+    {
+        let parameter = &parameter.to_string();
+        self.check_parameters(std::slice::from_ref(parameter))?;
+        let optionals = &optionals.to_query_options()?;
+        if optionals.lookup("ens_select").is_some() {
+            return Err(ConnectorError::InvalidOption(String::from(
+                "ens_select is not supported by pivoted grids (use query_grid_unpivoted)"
+            )));
+        }
+        self.check_time_range(timestamp, timestamp, std::slice::from_ref(parameter), optionals).await?;
+
         // Create the bounding box string according to API specification.
         let coords_str = format!("{}", bbox);
 
         // Create the query specifications (time, location, etc.)
         let query_specs = build_grid_query_specs(
             timestamp, parameter, &coords_str, optionals, "csv"
-        ).await;
+        ).await?;
 
         // Create the complete URL
-        let full_url = build_url(&query_specs).await.map_err(|_| ConnectorError::ParseError)?;
+        let full_url = build_url_from(&self.base_url, &query_specs).await?;
 
         // Get the query result
         let result = self.do_http_get(full_url).await;
@@ -606,22 +1212,19 @@ impl APIClient {
         match result {
             Ok(response) => match response.status() {
                 StatusCode::OK => {
-                    let df = parse_grid_response_to_df(response).await
-                        .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
+                    let df = parse_grid_response_to_df(response).await?;
                     Ok(df)
                 }
-                status => Err(ConnectorError::HttpError(
-                    status.to_string(),
-                    response.text().await.unwrap(),
-                    status,
-                )),
+                _ => Err(error_from_response(response).await),
             },
-            Err(e) => Err(ConnectorError::ReqwestError(e.to_string())),
+            Err(e) => Err(e),
         }
     }
 
     /// Download a ```polars``` DataFrame from the API for a grid of locations bounded by a bounding
     /// box object ```BBox``` and an arbitray number of parameters and a unique point in time.
+    /// With an ensemble selection ([`crate::options::QueryOptions::ens_select`]) the DataFrame holds
+    /// one row per member, statistic or quantile (see [`crate::ensemble`]).
     /// 
     /// # Arguments
     /// 
@@ -664,11 +1267,37 @@ impl APIClient {
     ///         .unwrap();
     /// }
     /// ```
-    pub async fn query_grid_unpivoted(&self,
+    pub async fn query_grid_unpivoted<P, O>(&self,
+        timestamp: &chrono::DateTime<chrono::Utc>,
+        parameters: &[P],
+        bbox: &BBox,
+        optionals: &O,
+    ) -> Result<polars::frame::DataFrame, ConnectorError>
+    where
+        P: Display,
+        O: ToQueryOptions + ?Sized,
+    {
+        let parameters = &parameter_names(parameters);
+        self.check_parameters(parameters)?;
+        let optionals = &optionals.to_query_options()?;
+        self.check_time_range(timestamp, timestamp, parameters, optionals).await?;
+        let df = match &self.tile_limits {
+            Some(limits) => {
+                self.query_tiled(limits, bbox, |tile| async move {
+                    self.fetch_grid_unpivoted(timestamp, parameters, &tile, optionals).await
+                }).await
+            }
+            None => self.fetch_grid_unpivoted(timestamp, parameters, bbox, optionals).await,
+        }?;
+        reshape_ensemble(df, optionals)
+    }
+
+    /// Downloads an unpivoted grid for a single point in time with a single request.
+    async fn fetch_grid_unpivoted(&self,
         timestamp: &chrono::DateTime<chrono::Utc>,
         parameters: &[String],
         bbox: &BBox,
-        optionals: &Option<Vec<String>>,
+        optionals: &QueryOptions,
     ) -> Result<polars::frame::DataFrame, ConnectorError> {
         // Create the bounding box string according to API specification.
         let coords_str = format!("{}", bbox);
@@ -679,10 +1308,10 @@ impl APIClient {
         // Create the query specifications (time, location, etc.)
         let query_specs = build_grid_query_specs(
             timestamp, &params, &coords_str, optionals, "csv"
-        ).await;
+        ).await?;
 
         // Create the complete URL
-        let full_url = build_url(&query_specs).await.map_err(|_| ConnectorError::ParseError)?;
+        let full_url = build_url_from(&self.base_url, &query_specs).await?;
 
         // Get the query result
         let result = self.do_http_get(full_url).await;
@@ -691,27 +1320,24 @@ impl APIClient {
         match result {
             Ok(response) => match response.status() {
                 StatusCode::OK => {
-                    let df = parse_response_to_df(response).await
-                        .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
+                    let df = parse_response_to_df(response).await?;
                     Ok(df)
                 }
-                status => Err(ConnectorError::HttpError(
-                    status.to_string(),
-                    response.text().await.unwrap(),
-                    status,
-                )),
+                _ => Err(error_from_response(response).await),
             },
-            Err(e) => Err(ConnectorError::ReqwestError(e.to_string())),
+            Err(e) => Err(e),
         }
     }
 
     /// Download a ```polars``` DataFrame from the API for a grid of locations bounded by a bounding
     /// box object ```BBox``` and an arbitray number of parameters and a time series. 
+    /// With an ensemble selection ([`crate::options::QueryOptions::ens_select`]) the DataFrame holds
+    /// one row per member, statistic or quantile (see [`crate::ensemble`]).
     /// 
     /// # Arguments
     /// 
     /// * `time_series` - Defines the temporal extent (time and date of start and a timedelta).
-    /// * `parameters` - Names of individual parameters (e.g. "t_2m:C" or "wind_speed_10m:ms"). 
+    /// * `parameters` - Names of individual parameters or [`crate::Parameter`]s (e.g. "t_2m:C" or "wind_speed_10m:ms"). 
     /// * `bbox` - Bounding box and resolution for the grid. (["crate::location::BBox"]) 
     /// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
     /// 
@@ -754,11 +1380,37 @@ impl APIClient {
     ///         .unwrap();
     /// }
     /// ```
-    pub async fn query_grid_unpivoted_time_series(&self,
+    pub async fn query_grid_unpivoted_time_series<P, O>(&self,
+        time_series: &TimeSeries,
+        parameters: &[P],
+        bbox: &BBox,
+        optionals: &O
+    ) -> Result<polars::frame::DataFrame, ConnectorError>
+    where
+        P: Display,
+        O: ToQueryOptions + ?Sized,
+    {
+        let parameters = &parameter_names(parameters);
+        self.check_parameters(parameters)?;
+        let optionals = &optionals.to_query_options()?;
+        self.check_time_range(&time_series.start, &time_series.end, parameters, optionals).await?;
+        let df = match &self.tile_limits {
+            Some(limits) => {
+                self.query_tiled(limits, bbox, |tile| async move {
+                    self.fetch_grid_unpivoted_time_series(time_series, parameters, &tile, optionals).await
+                }).await
+            }
+            None => self.fetch_grid_unpivoted_time_series(time_series, parameters, bbox, optionals).await,
+        }?;
+        reshape_ensemble(df, optionals)
+    }
+
+    /// Downloads an unpivoted grid for a time series with a single request.
+    async fn fetch_grid_unpivoted_time_series(&self,
         time_series: &TimeSeries,
         parameters: &[String],
         bbox: &BBox,
-        optionals: &Option<Vec<String>>
+        optionals: &QueryOptions
     ) -> Result<polars::frame::DataFrame, ConnectorError> {
         // Create the bounding box string according to API specification.
         let coords_str = format!("{}", bbox);
@@ -766,10 +1418,10 @@ impl APIClient {
         // Create the query specifications (time, location, etc.)
         let query_specs = build_ts_query_specs(
             time_series, parameters, &coords_str, optionals, "csv"
-        ).await;
+        ).await?;
 
         // Create the complete URL
-        let full_url = build_url(&query_specs).await.map_err(|_| ConnectorError::ParseError)?;
+        let full_url = build_url_from(&self.base_url, &query_specs).await?;
 
         // Get the query result
         let result = self.do_http_get(full_url).await;
@@ -778,17 +1430,12 @@ impl APIClient {
         match result {
             Ok(response) => match response.status() {
                 StatusCode::OK => {
-                    let df = parse_response_to_df(response).await
-                        .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
+                    let df = parse_response_to_df(response).await?;
                     Ok(df)
                 }
-                status => Err(ConnectorError::HttpError(
-                    status.to_string(),
-                    response.text().await.unwrap(),
-                    status,
-                )),
+                _ => Err(error_from_response(response).await),
             },
-            Err(e) => Err(ConnectorError::ReqwestError(e.to_string())),
+            Err(e) => Err(e),
         }
     }
 
@@ -798,7 +1445,7 @@ impl APIClient {
     /// # Arguments
     /// 
     /// * `time_series` - Defines the temporal extent (time and date of start and a timedelta).
-    /// * `parameters` - Names of individual parameters (e.g. "t_2m:C" or "wind_speed_10m:ms"). 
+    /// * `parameters` - Names of individual parameters or [`crate::Parameter`]s (e.g. "t_2m:C" or "wind_speed_10m:ms"). 
     /// * `bbox` - Bounding box and resolution for the grid. (["crate::location::BBox"]) 
     /// * `file_name` - The complete name and path for the NetCDF. Intermediate directories will be created.
     /// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
@@ -844,54 +1491,141 @@ impl APIClient {
     ///         .unwrap();
     /// }
     /// ```
-    pub async fn query_netcdf(&self,
+    pub async fn query_netcdf<P, O>(&self,
         time_series: &TimeSeries,
-        parameter: &String,
+        parameter: &P,
         bbox: &BBox,
         file_name: &String,
-        optionals: &Option<Vec<String>>
-    ) -> Result<(), ConnectorError> {
-
+        optionals: &O
+    ) -> Result<(), ConnectorError>
+    where
+        P: Display + ?Sized,
+        O: ToQueryOptions + ?Sized,
+    {
+        let parameter = &parameter.to_string();
+        self.check_parameters(std::slice::from_ref(parameter))?;
+        let optionals = &optionals.to_query_options()?;
         create_path(file_name).await?;
-
-        // Create the bounding box string according to API specification.
-        let coords_str = format!("{}", bbox);
-
-        // Create the query specifications (time, location, etc.)
-        let query_specs = build_grid_ts_query_specs(
-            time_series, parameter, &coords_str, "netcdf", optionals
-        ).await;
-
-        // Create the complete URL
-        let full_url = build_url(&query_specs).await.map_err(|_| ConnectorError::ParseError)?;
-
-        // Get the query result
-        let result = self.do_http_get(full_url).await;
-        
-        // Match the result
-        match result {
-            Ok(response) => match response.status() {
-                StatusCode::OK => {
-                    write_file(response, file_name).await?;
-                    Ok(())
-                }
-                status => Err(ConnectorError::HttpError(
-                    status.to_string(),
-                    response.text().await.unwrap(),
-                    status,
-                )),
-            },
-            Err(e) => Err(ConnectorError::ReqwestError(e.to_string())),
+        let mut file = AtomicFile::create(file_name).await?;
+        match self.netcdf_to_writer(time_series, parameter, bbox, file.writer(), optionals, Some(file_name)).await {
+            Ok(_) => file.commit().await,
+            Err(e) => {
+                file.abort().await;
+                Err(e)
+            }
         }
     }
 
-    /// Download a ```PNG``` from the API for a grid of locations bounded by a bounding box object 
-    /// ```BBox``` and an single parameter and a single point in time.
-    /// 
+    /// Download a ```NetCDF``` (see [`APIClient::query_netcdf`]) into a writer instead of a file
+    /// (e.g. an upload to an object storage). Returns the number of bytes written.
+    ///
     /// # Arguments
-    /// 
-    /// * `date` - Date and time for the request.
-    /// * `parameter` - The name of the parameter (e.g. "t_2m:C"). 
+    ///
+    /// * `time_series` - Defines the temporal extent (time and date of start and a timedelta).
+    /// * `parameter` - Name of the parameter (e.g. "t_2m:C").
+    /// * `bbox` - Bounding box and resolution for the grid. (["crate::location::BBox"])
+    /// * `writer` - The destination of the NetCDF.
+    /// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
+    pub async fn query_netcdf_to_writer<W, P, O>(&self,
+        time_series: &TimeSeries,
+        parameter: &P,
+        bbox: &BBox,
+        writer: &mut W,
+        optionals: &O
+    ) -> Result<u64, ConnectorError>
+    where
+        P: Display + ?Sized,
+        O: ToQueryOptions + ?Sized,
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let parameter = &parameter.to_string();
+        self.check_parameters(std::slice::from_ref(parameter))?;
+        let optionals = &optionals.to_query_options()?;
+        self.netcdf_to_writer(time_series, parameter, bbox, writer, optionals, None).await
+    }
+
+    /// Download a ```NetCDF``` (see [`APIClient::query_netcdf`]) into memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `time_series` - Defines the temporal extent (time and date of start and a timedelta).
+    /// * `parameter` - Name of the parameter (e.g. "t_2m:C").
+    /// * `bbox` - Bounding box and resolution for the grid. (["crate::location::BBox"])
+    /// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
+    pub async fn query_netcdf_bytes<P, O>(&self,
+        time_series: &TimeSeries,
+        parameter: &P,
+        bbox: &BBox,
+        optionals: &O
+    ) -> Result<Bytes, ConnectorError>
+    where
+        P: Display + ?Sized,
+        O: ToQueryOptions + ?Sized,
+    {
+        let mut buffer: Vec<u8> = Vec::new();
+        self.query_netcdf_to_writer(time_series, parameter, bbox, &mut buffer, optionals).await?;
+        Ok(Bytes::from(buffer))
+    }
+
+    // Streams the NetCDF into the writer. The progress and errors name the file, or the queried URL if
+    // the NetCDF is not written to a file.
+    async fn netcdf_to_writer<W>(&self,
+        time_series: &TimeSeries,
+        parameter: &String,
+        bbox: &BBox,
+        writer: &mut W,
+        optionals: &QueryOptions,
+        file_name: Option<&str>
+    ) -> Result<u64, ConnectorError>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        self.check_time_range(&time_series.start, &time_series.end, std::slice::from_ref(parameter), optionals).await?;
+        let (response, url) = self.fetch_netcdf(time_series, parameter, bbox, optionals).await?;
+        let file_name = file_name.unwrap_or(&url);
+        stream_to_writer(response, writer, file_name, Some(FileFormat::NetCdf), self.download_progress.as_ref()).await
+    }
+
+    // Sends the NetCDF query and returns the successful response together with the queried URL.
+    async fn fetch_netcdf(&self,
+        time_series: &TimeSeries,
+        parameter: &String,
+        bbox: &BBox,
+        optionals: &QueryOptions
+    ) -> Result<(Response, String), ConnectorError> {
+
+        // Create the bounding box string according to API specification.
+        let coords_str = format!("{}", bbox);
+
+        // Create the query specifications (time, location, etc.)
+        let query_specs = build_grid_ts_query_specs(
+            time_series, parameter, &coords_str, "netcdf", optionals
+        ).await?;
+
+        // Create the complete URL
+        let full_url = build_url_from(&self.base_url, &query_specs).await?;
+        let url = canonical_url(&full_url);
+
+        // Get the query result
+        let result = self.do_http_get(full_url).await;
+        
+        // Match the result
+        match result {
+            Ok(response) => match response.status() {
+                StatusCode::OK => Ok((response, url)),
+                _ => Err(error_from_response(response).await),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Download a ```PNG``` from the API for a grid of locations bounded by a bounding box object 
+    /// ```BBox``` and an single parameter and a single point in time.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `date` - Date and time for the request.
+    /// * `parameter` - The name of the parameter (e.g. "t_2m:C"). 
     /// * `bbox` - Bounding box and resolution for the grid. (["crate::location::BBox"]) 
     /// * `file_name` - The complete name and path for the PNG. Intermediate directories will be created.
     /// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
@@ -932,15 +1666,120 @@ impl APIClient {
     ///         .unwrap();
     /// }
     /// ```
-    pub async fn query_grid_png(&self,
+    pub async fn query_grid_png<P, O>(&self,
+        date: &chrono::DateTime<chrono::Utc>,
+        parameter: &P,
+        bbox: &BBox,
+        file_name: &String,
+        optionals: &O
+    ) -> Result<(), ConnectorError>
+    where
+        P: Display + ?Sized,
+        O: ToQueryOptions + ?Sized,
+    {
+        let parameter = &parameter.to_string();
+        self.check_parameters(std::slice::from_ref(parameter))?;
+        let optionals = &optionals.to_query_options()?;
+        self.check_time_range(date, date, std::slice::from_ref(parameter), optionals).await?;
+        self.grid_png_to_file(date, parameter, bbox, file_name, optionals).await
+    }
+
+    /// Download a ```PNG``` (see [`APIClient::query_grid_png`]) into a writer instead of a file.
+    /// Returns the number of bytes written.
+    ///
+    /// # Arguments
+    ///
+    /// * `date` - Date and time for the request.
+    /// * `parameter` - The name of the parameter (e.g. "t_2m:C").
+    /// * `bbox` - Bounding box and resolution for the grid. (["crate::location::BBox"])
+    /// * `writer` - The destination of the PNG.
+    /// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
+    pub async fn query_grid_png_to_writer<W, P, O>(&self,
+        date: &chrono::DateTime<chrono::Utc>,
+        parameter: &P,
+        bbox: &BBox,
+        writer: &mut W,
+        optionals: &O
+    ) -> Result<u64, ConnectorError>
+    where
+        P: Display + ?Sized,
+        O: ToQueryOptions + ?Sized,
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let parameter = &parameter.to_string();
+        self.check_parameters(std::slice::from_ref(parameter))?;
+        let optionals = &optionals.to_query_options()?;
+        self.check_time_range(date, date, std::slice::from_ref(parameter), optionals).await?;
+        self.grid_png_to_writer(date, parameter, bbox, writer, optionals, None).await
+    }
+
+    /// Download a ```PNG``` (see [`APIClient::query_grid_png`]) into memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `date` - Date and time for the request.
+    /// * `parameter` - The name of the parameter (e.g. "t_2m:C").
+    /// * `bbox` - Bounding box and resolution for the grid. (["crate::location::BBox"])
+    /// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
+    pub async fn query_grid_png_bytes<P, O>(&self,
+        date: &chrono::DateTime<chrono::Utc>,
+        parameter: &P,
+        bbox: &BBox,
+        optionals: &O
+    ) -> Result<Bytes, ConnectorError>
+    where
+        P: Display + ?Sized,
+        O: ToQueryOptions + ?Sized,
+    {
+        let mut buffer: Vec<u8> = Vec::new();
+        self.query_grid_png_to_writer(date, parameter, bbox, &mut buffer, optionals).await?;
+        Ok(Bytes::from(buffer))
+    }
+
+    // Writes the PNG to a file that only appears once the download is complete.
+    async fn grid_png_to_file(&self,
         date: &chrono::DateTime<chrono::Utc>,
         parameter: &String,
         bbox: &BBox,
         file_name: &String,
-        optionals: &Option<Vec<String>>
+        optionals: &QueryOptions
     ) -> Result<(), ConnectorError> {
-
         create_path(file_name).await?;
+        let mut file = AtomicFile::create(file_name).await?;
+        match self.grid_png_to_writer(date, parameter, bbox, file.writer(), optionals, Some(file_name)).await {
+            Ok(_) => file.commit().await,
+            Err(e) => {
+                file.abort().await;
+                Err(e)
+            }
+        }
+    }
+
+    // Streams the PNG into the writer. The progress and errors name the file, or the queried URL if the
+    // PNG is not written to a file.
+    async fn grid_png_to_writer<W>(&self,
+        date: &chrono::DateTime<chrono::Utc>,
+        parameter: &String,
+        bbox: &BBox,
+        writer: &mut W,
+        optionals: &QueryOptions,
+        file_name: Option<&str>
+    ) -> Result<u64, ConnectorError>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let (response, url) = self.fetch_grid_png(date, parameter, bbox, optionals).await?;
+        let file_name = file_name.unwrap_or(&url);
+        stream_to_writer(response, writer, file_name, Some(FileFormat::Png), self.download_progress.as_ref()).await
+    }
+
+    // Sends the PNG query and returns the successful response together with the queried URL.
+    async fn fetch_grid_png(&self,
+        date: &chrono::DateTime<chrono::Utc>,
+        parameter: &String,
+        bbox: &BBox,
+        optionals: &QueryOptions
+    ) -> Result<(Response, String), ConnectorError> {
 
         // Create the bounding box string according to API specification.
         let coords_str = format!("{}", bbox);
@@ -948,10 +1787,11 @@ impl APIClient {
         // Create the query specifications (time, location, etc.)
         let query_specs = build_grid_query_specs(
             date, parameter, &coords_str, optionals, &String::from("png")
-        ).await;
+        ).await?;
 
         // Create the complete URL
-        let full_url = build_url(&query_specs).await.map_err(|_| ConnectorError::ParseError)?;
+        let full_url = build_url_from(&self.base_url, &query_specs).await?;
+        let url = canonical_url(&full_url);
 
         // Get the query result
         let result = self.do_http_get(full_url).await;
@@ -959,17 +1799,10 @@ impl APIClient {
         // Match the result
         match result {
             Ok(response) => match response.status() {
-                StatusCode::OK => {
-                    write_file(response, file_name).await?;
-                    Ok(())
-                }
-                status => Err(ConnectorError::HttpError(
-                    status.to_string(),
-                    response.text().await.unwrap(),
-                    status,
-                )),
+                StatusCode::OK => Ok((response, url)),
+                _ => Err(error_from_response(response).await),
             },
-            Err(e) => Err(ConnectorError::ReqwestError(e.to_string())),
+            Err(e) => Err(e),
         }
     }
 
@@ -982,7 +1815,8 @@ impl APIClient {
     /// * `parameter` - Name of individual parameter (e.g. "t_2m:C"). 
     /// * `bbox` - Bounding box and resolution for the grid. (["crate::location::BBox"]) 
     /// * `prefix_path` - The complete name and path for the PNGs. Intermediate directories will be created.
-    /// And individual files will contain the specified `prefix_path` as well as a timestamp.
+    ///   And individual files will contain the specified `prefix_path` as well as a timestamp.
+    ///   Use [`APIClient::query_grid_png_frames`] for other file names or to resume a download.
     /// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
     /// 
     /// # Examples
@@ -1026,57 +1860,519 @@ impl APIClient {
     ///         .unwrap();
     /// }
     /// ```
-    pub async fn query_grid_png_timeseries(&self,
+    pub async fn query_grid_png_timeseries<P, O>(&self,
         time_series: &TimeSeries,
-        parameter: &String,
+        parameter: &P,
         bbox: &BBox,
-        prefixpath: &String,
-        optionals: &Option<Vec<String>>
-    ) -> Result<(), ConnectorError> {
+        prefixpath: &str,
+        optionals: &O
+    ) -> Result<(), ConnectorError>
+    where
+        P: Display + ?Sized,
+        O: ToQueryOptions + ?Sized,
+    {
+
+        let manifest = self.query_grid_png_frames(
+            time_series, parameter, bbox, prefixpath, optionals, &FrameOptions::default()
+        ).await?;
+        match manifest.frames.into_iter().find_map(|frame| frame.outcome.err()) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
 
-        // Iterate the time series
-        let mut dt_cur = time_series.start;
-        let fmt = "%Y%m%d_%H%M%S";
-        while dt_cur <= time_series.end {
-            let cur_file_name = format!("{}_{}.png", prefixpath, dt_cur.format(fmt));
-            self.query_grid_png(&dt_cur, parameter, bbox, &cur_file_name, optionals).await?;
-            dt_cur = dt_cur + time_series.timedelta.unwrap(); // panic when timedelta absent
-        };
-        Ok(())
+    /// Download a series of ```PNG``` files (see [`APIClient::query_grid_png_timeseries`]) with a
+    /// bounded number of requests in flight. Returns a manifest with the file or the error of every
+    /// frame, a failed frame does not affect the others (see [`crate::frames`]).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `time_series` - Defines the temporal extent (time and date of start and a timedelta).
+    /// * `parameter` - Name of individual parameter (e.g. "t_2m:C"). 
+    /// * `bbox` - Bounding box and resolution for the grid. (["crate::location::BBox"]) 
+    /// * `prefix_path` - The prefix for the file names (see [`FrameOptions::template`]).
+    /// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
+    /// * `options` - Concurrency, file name template and whether to skip existing files.
+    pub async fn query_grid_png_frames<P, O>(&self,
+        time_series: &TimeSeries,
+        parameter: &P,
+        bbox: &BBox,
+        prefixpath: &str,
+        optionals: &O,
+        options: &FrameOptions,
+    ) -> Result<FrameManifest, ConnectorError>
+    where
+        P: Display + ?Sized,
+        O: ToQueryOptions + ?Sized,
+    {
+        let parameter = &parameter.to_string();
+        self.check_parameters(std::slice::from_ref(parameter))?;
+        let optionals = &optionals.to_query_options()?;
+        self.check_time_range(&time_series.start, &time_series.end, std::slice::from_ref(parameter), optionals).await?;
+        let mut frames = Vec::new();
+        for date in expand_time_series(time_series)? {
+            let file_name = frame_file_name(&options.template, prefixpath, &date)?;
+            frames.push((date, file_name));
+        }
+
+        let frames = stream::iter(frames)
+            .map(|(date, file_name)| async move {
+                let outcome = if options.skip_existing && is_complete_png(Path::new(&file_name)).await {
+                    Ok(FrameStatus::Skipped)
+                } else {
+                    self.grid_png_to_file(&date, parameter, bbox, &file_name, optionals)
+                        .await
+                        .map(|_| FrameStatus::Downloaded)
+                };
+                Frame { date, path: PathBuf::from(file_name), outcome }
+            })
+            .buffered(options.concurrency.max(1))
+            .collect()
+            .await;
+        Ok(FrameManifest { frames })
+    }
+
+    /// Download a series of ```PNG``` files (see [`APIClient::query_grid_png_timeseries`]) into
+    /// memory. Returns the images together with their point in time.
+    ///
+    /// # Arguments
+    ///
+    /// * `time_series` - Defines the temporal extent (time and date of start and a timedelta).
+    /// * `parameter` - Name of individual parameter (e.g. "t_2m:C").
+    /// * `bbox` - Bounding box and resolution for the grid. (["crate::location::BBox"])
+    /// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
+    pub async fn query_grid_png_timeseries_bytes<P, O>(&self,
+        time_series: &TimeSeries,
+        parameter: &P,
+        bbox: &BBox,
+        optionals: &O
+    ) -> Result<Vec<(chrono::DateTime<chrono::Utc>, Bytes)>, ConnectorError>
+    where
+        P: Display + ?Sized,
+        O: ToQueryOptions + ?Sized,
+    {
+        let parameter = &parameter.to_string();
+        self.check_parameters(std::slice::from_ref(parameter))?;
+        let optionals = &optionals.to_query_options()?;
+        self.check_time_range(&time_series.start, &time_series.end, std::slice::from_ref(parameter), optionals).await?;
+        let mut images = Vec::new();
+        for date in expand_time_series(time_series)? {
+            let mut buffer: Vec<u8> = Vec::new();
+            self.grid_png_to_writer(&date, parameter, bbox, &mut buffer, optionals, None).await?;
+            images.push((date, Bytes::from(buffer)));
+        }
+        Ok(images)
     }
     
-    /// Handles the actual HTTP request using the ```reqwest``` crate. 
+    /// Runs many independent queries with a bounded number of queries in flight. The results are
+    /// returned in the order of the queries, a failed query does not affect the others.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `queries` - The queries to run (see [`BatchQuery`]).
+    /// * `options` - The concurrency and an optional cancellation token (see [`BatchOptions`]).
+    /// 
+    /// # Examples
+    /// 
+    /// ```rust, no_run
+    /// use chrono::{Utc, Duration};
+    /// use meteomatics::{APIClient, Point, TimeSeries};
+    /// use meteomatics::batch::{BatchOptions, BatchQuery, CancellationToken};
+    /// 
+    /// #[tokio::main] 
+    /// async fn main() {
+    ///     let client = APIClient::new("ferris_loves_rustaceans", "0123456789", 10);
+    ///     let start_date = Utc::now();
+    ///     let queries = (0..100).map(|i| BatchQuery::TimeSeries {
+    ///         time_series: TimeSeries {
+    ///             start: start_date,
+    ///             end: start_date + Duration::days(1),
+    ///             timedelta: Option::from(Duration::hours(1))
+    ///         },
+    ///         parameters: vec![String::from("t_2m:C")],
+    ///         coordinates: vec![Point { lat: 47.0 + 0.01 * i as f64, lon: 9.0 }],
+    ///         optionals: None,
+    ///     });
+    /// 
+    ///     // Cancel the batch on Ctrl-C.
+    ///     let cancel = CancellationToken::new();
+    ///     let token = cancel.clone();
+    ///     tokio::spawn(async move {
+    ///         tokio::signal::ctrl_c().await.unwrap();
+    ///         token.cancel();
+    ///     });
+    /// 
+    ///     let options = BatchOptions { concurrency: 8, cancel: Some(cancel) };
+    ///     let results = client.query_batch(queries, &options).await;
+    ///     println!("{} of {} queries succeeded", results.iter().filter(|r| r.is_ok()).count(), results.len());
+    /// }
+    /// ```
+    pub async fn query_batch<I>(
+        &self,
+        queries: I,
+        options: &BatchOptions,
+    ) -> Vec<Result<polars::frame::DataFrame, ConnectorError>>
+    where
+        I: IntoIterator<Item = BatchQuery>,
+    {
+        let cancel = options.cancel.clone().unwrap_or_default();
+        stream::iter(queries)
+            .map(|query| {
+                let cancel = cancel.clone();
+                async move {
+                    tokio::select! {
+                        biased;
+                        _ = cancel.cancelled() => Err(ConnectorError::Cancelled),
+                        result = query.run(self) => result,
+                    }
+                }
+            })
+            .buffered(options.concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Runs a time series query in chunks if it exceeds the [`SplitLimits`]. The chunks are fetched 
+    /// with the given function and combined into a single DataFrame: parameter chunks side by side,
+    /// location chunks and time windows below each other.
+    #[allow(clippy::too_many_arguments)]
+    async fn query_split<'a, L, F, Fut>(
+        &'a self,
+        limits: &SplitLimits,
+        time_series: &TimeSeries,
+        parameters: &[String],
+        locations: &'a [L],
+        optionals: &QueryOptions,
+        fetch: F,
+    ) -> Result<polars::frame::DataFrame, ConnectorError>
+    where
+        L: Display,
+        F: Fn(TimeSeries, Vec<String>, &'a [L]) -> Fut,
+        Fut: Future<Output = Result<polars::frame::DataFrame, ConnectorError>>,
+    {
+        let optionals_length = optionals.to_query_string().map_or(0, |query| query.len() + 1);
+        let sizes = limits.chunk_sizes(
+            self.base_url.as_str().len() + optionals_length, time_series, parameters, locations
+        );
+        if !limits.requires_split(&sizes, locations.len(), parameters.len(), time_series) {
+            return fetch(time_series.clone(), parameters.to_vec(), locations).await;
+        }
+
+        let parameter_chunks: Vec<Vec<String>> = parameters.chunks(sizes.parameters).map(|c| c.to_vec()).collect();
+        let windows = match sizes.time_steps {
+            Some(steps) => time_windows(time_series, steps),
+            None => vec![time_series.clone()],
+        };
+
+        // One request per location chunk, time window and parameter chunk.
+        let mut jobs = Vec::new();
+        for chunk in locations.chunks(sizes.points) {
+            for window in &windows {
+                for params in &parameter_chunks {
+                    jobs.push(fetch(window.clone(), params.clone(), chunk));
+                }
+            }
+        }
+        let results: Vec<Result<polars::frame::DataFrame, ConnectorError>> = stream::iter(jobs)
+            .buffered(limits.concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut results = results.into_iter();
+        let mut frames = Vec::new();
+        while results.len() > 0 {
+            let chunk_frames = results
+                .by_ref()
+                .take(parameter_chunks.len())
+                .collect::<Result<Vec<polars::frame::DataFrame>, ConnectorError>>()?;
+            frames.push(combine_parameter_chunks(chunk_frames, &parameter_chunks)?);
+        }
+        let df = concat_frames(frames)?;
+        if windows.len() > 1 {
+            group_by_location(df)
+        } else {
+            Ok(df)
+        }
+    }
+
+    /// Runs a grid query per tile of the bounding box (see [`TileLimits`]) and stitches the results.
+    async fn query_tiled<F, Fut>(
+        &self,
+        limits: &TileLimits,
+        bbox: &BBox,
+        fetch: F,
+    ) -> Result<polars::frame::DataFrame, ConnectorError>
+    where
+        F: Fn(BBox) -> Fut,
+        Fut: Future<Output = Result<polars::frame::DataFrame, ConnectorError>>,
+    {
+        let tiles = tiles(bbox, limits);
+        if tiles.len() == 1 {
+            return fetch(*bbox).await;
+        }
+        let frames = stream::iter(tiles.into_iter().map(fetch))
+            .buffered(limits.concurrency.max(1))
+            .collect::<Vec<Result<polars::frame::DataFrame, ConnectorError>>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<polars::frame::DataFrame>, ConnectorError>>()?;
+        stitch_tiles(frames)
+    }
+
+    /// Checks the parameters against the catalog of the client (if it has one).
+    fn check_parameters(&self, parameters: &[String]) -> Result<(), ConnectorError> {
+        match &self.catalog {
+            Some(catalog) if self.strict_parameter_check => {
+                parameters.iter().try_for_each(|p| catalog.check_name(p).map(|_| ()))
+            }
+            Some(catalog) => parameters.iter().try_for_each(|p| catalog.check_known(&p.parse()?)),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the dates of a query against the available time ranges if the client was built with
+    /// [`APIClientBuilder::time_range_check`]. Parameters the API returns no range for are left to the
+    /// API, parameters without data fail. Ranges are requested only if they are not kept yet.
+    async fn check_time_range(
+        &self,
+        start: &chrono::DateTime<chrono::Utc>,
+        end: &chrono::DateTime<chrono::Utc>,
+        parameters: &[String],
+        optionals: &QueryOptions,
+    ) -> Result<(), ConnectorError> {
+        let cache = match &self.time_ranges {
+            Some(cache) => cache,
+            None => return Ok(()),
+        };
+        let model = optionals.selected_model();
+        let ranges = match cache.get(model, parameters) {
+            Some(ranges) => ranges,
+            None => {
+                let ranges = self.query_available_time_range(model, parameters).await?;
+                cache.put(model, parameters, &ranges);
+                ranges
+            }
+        };
+        match ranges.iter().find(|range| !range.contains(start) || !range.contains(end)) {
+            Some(range) if !range.has_data() => Err(ConnectorError::UnavailableTimeRange(format!(
+                "{} (model {})", range, model
+            ))),
+            Some(range) => Err(ConnectorError::UnavailableTimeRange(format!(
+                "{}--{} is outside of {} (model {})",
+                format_query_date(start), format_query_date(end), range, model
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Handles the actual HTTP request using the ```reqwest``` crate. Successful responses are served
+    /// from and stored in the [`ResponseCache`] if the client has one.
     async fn do_http_get(&self, full_url: Url) -> Result<Response, ConnectorError> {
-        self.http_client
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return self.send_or_replay(full_url).await,
+        };
+        if let Some(cached) = cache.get(&full_url).await {
+            return Ok(cached.into_response());
+        }
+        if cache.config().mode == CacheMode::Offline {
+            return Err(ConnectorError::CacheMiss(canonical_url(&full_url)));
+        }
+
+        let response = self.send_or_replay(full_url.clone()).await?;
+        if response.status() != StatusCode::OK {
+            return Ok(response);
+        }
+        let content_type = response.headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        let body = match read_cacheable_body(response, cache.config().max_entry_size).await? {
+            Ok(body) => body,
+            Err(streamed) => return Ok(streamed),
+        };
+        // A cache that cannot be written (e.g. a full disk) must not fail the query.
+        let _ = cache.put(&full_url, content_type.as_deref(), body.clone()).await;
+        Ok(CachedResponse { content_type, body }.into_response())
+    }
+
+    /// Answers the request from the cassette in replay mode, otherwise sends it and records the
+    /// response if the client records its traffic.
+    async fn send_or_replay(&self, full_url: Url) -> Result<Response, ConnectorError> {
+        let cassette = match &self.cassette {
+            Some(cassette) => cassette,
+            None => return self.send_with_retries(full_url).await,
+        };
+        if cassette.mode() == CassetteMode::Replay {
+            return cassette.replay(&full_url)?.to_response();
+        }
+
+        let response = self.send_with_retries(full_url.clone()).await?;
+        let status = response.status().as_u16();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?.to_vec();
+        let interaction = Interaction::new(&full_url, status, &headers, body);
+        let response = interaction.to_response();
+        cassette.record(interaction);
+        response
+    }
+
+    /// Sends the request and retries transient failures according to the [`RetryPolicy`] of the client.
+    async fn send_with_retries(&self, full_url: Url) -> Result<Response, ConnectorError> {
+        let mut attempt: u32 = 1;
+        loop {
+            let result = match &self.rate_limiter {
+                Some(limiter) => {
+                    limiter.refresh_if_due(|| self.fetch_user_stats()).await;
+                    let permit = Arc::clone(limiter).acquire_owned().await;
+                    self.send_authenticated(full_url.clone()).await?
+                        .map(|response| hold_until_read(response, permit))
+                }
+                None => self.send_authenticated(full_url.clone()).await?,
+            };
+
+            // Decide if (and after which delay) the request is repeated.
+            let (reason, delay) = match &result {
+                Ok(response) if self.retry_policy.retries_status(attempt, response.status()) => (
+                    RetryReason::Status(response.status()),
+                    self.retry_policy.delay(attempt, Some(response.headers())),
+                ),
+                Err(e) if self.retry_policy.retries_error(attempt, e) => (
+                    RetryReason::Transport(e.to_string()),
+                    self.retry_policy.delay(attempt, None),
+                ),
+                _ => return Ok(result?),
+            };
+
+            self.retry_count.fetch_add(1, Ordering::Relaxed);
+            if let Some(callback) = &self.retry_policy.on_retry {
+                callback.call(&RetryEvent { url: full_url.to_string(), attempt, reason, delay });
+            }
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Fetches the account statistics for the rate limiter (bypassing the limiter and retries).
+    async fn fetch_user_stats(&self) -> Result<UserStats, ConnectorError> {
+        let full_url = build_url_from(&self.base_url, "user_stats_json").await?;
+        let response = self.send_authenticated(full_url).await??;
+        match response.status() {
+            StatusCode::OK => Ok(extract_user_statistics(response).await?.stats),
+            _ => Err(error_from_response(response).await),
+        }
+    }
+
+    /// Sends a single request with the credentials or the access token of the client. A rejected
+    /// token is renewed and the request is repeated once. Fails if no credentials or no token can be
+    /// obtained, the inner result is the outcome of the request.
+    async fn send_authenticated(&self, full_url: Url) -> Result<reqwest::Result<Response>, ConnectorError> {
+        let credentials = self.credentials.get().await?;
+        let manager = match &self.token {
+            Some(manager) => manager,
+            None => return Ok(self.send_request(full_url, RequestAuth::Basic(&credentials)).await),
+        };
+        let placement = manager.config().placement;
+
+        let token = manager.token(|| self.fetch_token(&credentials)).await?;
+        let result = self.send_request(full_url.clone(), RequestAuth::Token(&token, placement)).await;
+        match &result {
+            Ok(response) if response.status() == StatusCode::UNAUTHORIZED => {
+                manager.invalidate(&token).await;
+                let token = manager.token(|| self.fetch_token(&credentials)).await?;
+                Ok(self.send_request(full_url, RequestAuth::Token(&token, placement)).await)
+            }
+            _ => Ok(result),
+        }
+    }
+
+    /// Exchanges the credentials for an access token at the token endpoint.
+    async fn fetch_token(&self, credentials: &Credentials) -> Result<TokenResponse, ConnectorError> {
+        let token_url = match &self.token {
+            Some(manager) => Url::parse(&manager.config().token_url)?,
+            None => return Err(ConnectorError::LibraryError(String::from("Token authentication is not enabled"))),
+        };
+        let response = self.send_request(token_url, RequestAuth::Basic(credentials)).await?;
+        match response.status() {
+            StatusCode::OK => Ok(response.json::<TokenResponse>().await?),
+            _ => Err(error_from_response(response).await),
+        }
+    }
+
+    /// Sends a single GET request with the configured headers, timeout and authentication.
+    async fn send_request(&self, mut full_url: Url, auth: RequestAuth<'_>) -> reqwest::Result<Response> {
+        if let RequestAuth::Token(token, TokenPlacement::Query) = auth {
+            full_url.query_pairs_mut().append_pair("access_token", token);
+        }
+        let mut request = self.http_client
             .get(full_url)
-            .basic_auth(&self.username, Some(String::from(&self.password)))
-            .send()
-            .await
-            .map_err(|e| ConnectorError::ReqwestError(e.to_string()))
+            .headers(self.default_headers.clone());
+        request = match auth {
+            RequestAuth::Basic(credentials) => request.basic_auth(&credentials.username, Some(&credentials.password)),
+            RequestAuth::Token(token, TokenPlacement::Bearer) => request.bearer_auth(token),
+            RequestAuth::Token(_, TokenPlacement::Query) => request,
+        };
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        request.send().await
     }
 }
 
+// How a single request is authenticated.
+#[derive(Clone, Copy)]
+enum RequestAuth<'a> {
+    Basic(&'a Credentials),
+    Token(&'a str, TokenPlacement),
+}
+
 #[cfg(test)]
 mod tests {
 
     use crate::APIClient;
+    use crate::batch::{BatchOptions, BatchQuery, CancellationToken};
+    use crate::cache::{CacheConfig, CacheMode};
+    use crate::cassette::{CassetteConfig, CassetteMode};
+    use crate::catalog::Catalog;
+    use crate::errors::ConnectorError;
+    use crate::ratelimit::RateLimitConfig;
+    use crate::retry::RetryPolicy;
+    use crate::split::SplitLimits;
+    use crate::tiling::TileLimits;
+    use crate::auth::{AuthMode, TokenConfig, TokenPlacement};
+    use crate::credentials::{CredentialChain, CredentialProvider, Credentials};
+    use crate::frames::{FrameOptions, FrameStatus};
+    use crate::location::BBox;
+    use crate::options::{EnsembleSelection, QueryOptions};
+    use crate::parameter::{parameter_columns, Aggregation, Parameter};
+    use crate::testing::{Endpoint, FakeRequest, FakeResponse, FakeServer};
+    use crate::util::TimeSeries;
+    use crate::location::Point;
+    use chrono::{Duration, TimeZone, Utc};
+    use polars::prelude::TakeRandom;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
     
     #[tokio::test]
     async fn client_fires_get_request() {
 
-        // Query to the mockup server running at Postman
+        // Query to the status endpoint, answered from a recorded cassette
         let query = crate::client::build_url(
-            &"status".to_string()
+            "status"
         ).await.unwrap();
 
         // Credentials
         let api_key = "test_password".to_string();
         let api_user = "test_user".to_string();
-        let api_client = APIClient::new(
-            &api_user,
-            &api_key,
-            10,
-        );
+        let cassette = CassetteConfig {
+            path: concat!(env!("CARGO_MANIFEST_DIR"), "/tests/cassettes/client_fires_get_request.json").into(),
+            mode: CassetteMode::Replay,
+        };
+        let api_client = APIClient::builder(&api_user, &api_key)
+            .timeout_seconds(10)
+            .cassette(cassette)
+            .build()
+            .unwrap();
 
         let result = api_client.do_http_get(query).await;
 
@@ -1101,4 +2397,859 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn builder_routes_queries_to_base_url() {
+        let csv = "validdate;t_2m:C\n1989-11-09T18:00:00Z;6.8\n1989-11-10T06:00:00Z;1.4\n";
+        let server = FakeServer::start().await;
+        server.respond(Endpoint::TimeSeries, FakeResponse::csv(csv));
+
+        let api_client = APIClient::builder("test_user", "test_password")
+            .base_url(&format!("{}gateway/meteomatics", server.url()))
+            .timeout_seconds(5)
+            .user_agent("rust-connector-test")
+            .default_header("x-team", "forecasting")
+            .build()
+            .unwrap();
+        assert_eq!(api_client.base_url().path(), "/gateway/meteomatics/");
+
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let time_series = TimeSeries {
+            start: start_date,
+            end: start_date + Duration::hours(12),
+            timedelta: Option::from(Duration::hours(12))
+        };
+        let parameters = vec![String::from("t_2m:C")];
+        let coords = vec![Point { lat: 52.52, lon: 13.405 }];
+        let df = api_client
+            .query_time_series(&time_series, &parameters, &coords, &None)
+            .await
+            .unwrap();
+        assert_eq!(df.shape(), (2, 4));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].segments[..2], [String::from("gateway"), String::from("meteomatics")]);
+        assert!(requests[0].segments[2].starts_with("1989-11-09T18:00:00+00:00--"));
+        assert_eq!(requests[0].header("user-agent"), Some("rust-connector-test"));
+        assert_eq!(requests[0].header("x-team"), Some("forecasting"));
+        assert_eq!(requests[0].username().as_deref(), Some("test_user"));
+    }
+
+    #[tokio::test]
+    async fn builder_rejects_invalid_base_url() {
+        let result = APIClient::builder("test_user", "test_password")
+            .base_url("not a url")
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn client_retries_transient_failures() {
+        let server = FakeServer::start().await;
+        server.respond_once(Endpoint::Unknown, FakeResponse::error(503, "busy").with_header("retry-after", "0"));
+        server.respond_once(Endpoint::Unknown, FakeResponse::error(502, "bad gateway"));
+        server.respond(Endpoint::Unknown, FakeResponse::json("{}"));
+
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = std::sync::Arc::clone(&events);
+        let policy = RetryPolicy {
+            initial_backoff: std::time::Duration::from_millis(1),
+            ..RetryPolicy::default()
+        }
+        .on_retry(move |event| seen.lock().unwrap().push((event.attempt, event.reason.to_string())));
+
+        let api_client = APIClient::builder("test_user", "test_password")
+            .base_url(server.url())
+            .retry_policy(policy)
+            .build()
+            .unwrap();
+
+        let query = crate::util::build_url_from(api_client.base_url(), "status").await.unwrap();
+        let response = api_client.do_http_get(query).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(server.requests().len(), 3);
+        assert_eq!(api_client.retry_count(), 2);
+
+        let events = events.lock().unwrap();
+        assert_eq!(events[0].0, 1);
+        assert!(events[0].1.contains("503"));
+        assert_eq!(events[1].0, 2);
+    }
+
+    #[tokio::test]
+    async fn client_gives_up_after_max_attempts() {
+        let server = FakeServer::start().await;
+        server.respond(Endpoint::Unknown, FakeResponse::error(429, "slow down"));
+        let policy = RetryPolicy {
+            max_attempts: 2,
+            initial_backoff: std::time::Duration::from_millis(1),
+            ..RetryPolicy::default()
+        };
+        let api_client = APIClient::builder("test_user", "test_password")
+            .base_url(server.url())
+            .retry_policy(policy)
+            .build()
+            .unwrap();
+
+        let query = crate::util::build_url_from(api_client.base_url(), "status").await.unwrap();
+        let response = api_client.do_http_get(query).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(api_client.retry_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn client_fetches_rate_limits_before_first_request() {
+        let stats = r#"{"message": "", "user statistics": {"username": "rustythecrab",
+            "requests total": {"used": 0, "soft limit": 0, "hard limit": 0},
+            "requests since last UTC midnight": {"used": 0, "soft limit": 0, "hard limit": 0},
+            "requests since HH:00:00": {"used": 0, "soft limit": 0, "hard limit": 0},
+            "requests in the last 60 seconds": {"used": 0, "soft limit": 0, "hard limit": 6000},
+            "requests in parallel": {"used": 0, "soft limit": 20, "hard limit": 500},
+            "historic request option": "", "area request option": true, "model set": [],
+            "error message": "", "contact emails": []}}"#;
+        let server = FakeServer::start().await;
+        server.respond(Endpoint::UserStats, FakeResponse::json(stats));
+        let api_client = APIClient::builder("test_user", "test_password")
+            .base_url(server.url())
+            .rate_limit(RateLimitConfig::default())
+            .build()
+            .unwrap();
+
+        let ustats = api_client.query_user_features().await.unwrap();
+        assert_eq!(ustats.stats.username, "rustythecrab");
+        assert_eq!(api_client.rate_limits(), Some((Some(500), Some(6000))));
+
+        // One request for the limits, one for the actual query.
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|r| r.endpoint == Endpoint::UserStats));
+    }
+
+    #[tokio::test]
+    async fn batch_keeps_order_and_partial_failures() {
+        let csv = "validdate;t_2m:C\n1989-11-09T18:00:00Z;6.8\n";
+        let server = FakeServer::start().await;
+        server.respond_once(Endpoint::TimeSeries, FakeResponse::csv(csv));
+        server.respond_once(Endpoint::TimeSeries, FakeResponse::error(400, "invalid parameter"));
+        server.respond(Endpoint::TimeSeries, FakeResponse::csv(csv));
+        let api_client = APIClient::builder("test_user", "test_password")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let queries = (0..3).map(|i| BatchQuery::TimeSeries {
+            time_series: TimeSeries { start: start_date, end: start_date, timedelta: Option::from(Duration::hours(1)) },
+            parameters: vec![String::from("t_2m:C")],
+            coordinates: vec![Point { lat: 52.0 + i as f64, lon: 13.405 }],
+            optionals: None,
+        });
+
+        // One query at a time, such that the queued responses are served in order.
+        let options = BatchOptions { concurrency: 1, ..BatchOptions::default() };
+        let results = api_client.query_batch(queries, &options).await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().column("lat").unwrap().f64().unwrap().get(0), Some(52.0));
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap().column("lat").unwrap().f64().unwrap().get(0), Some(54.0));
+    }
+
+    #[tokio::test]
+    async fn batch_can_be_cancelled() {
+        let server = FakeServer::start().await;
+        let api_client = APIClient::builder("test_user", "test_password")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let queries = vec![BatchQuery::RoutePostal {
+            dates: vec![Utc::now()],
+            postals: vec![String::from("postal_CH9000")],
+            parameters: vec![String::from("t_2m:C")],
+        }];
+        let options = BatchOptions { concurrency: 2, cancel: Some(cancel) };
+        let results = api_client.query_batch(queries, &options).await;
+        assert!(matches!(results[0], Err(ConnectorError::Cancelled)));
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn split_time_series_is_combined() {
+        // The fake server answers every chunk with values for its parameter.
+        let server = FakeServer::start().await;
+        let limits = SplitLimits { max_points: 1, max_parameters: 1, concurrency: 1, ..SplitLimits::default() };
+        let api_client = APIClient::builder("test_user", "test_password")
+            .base_url(server.url())
+            .split_limits(limits)
+            .build()
+            .unwrap();
+
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let time_series = TimeSeries {
+            start: start_date,
+            end: start_date + Duration::hours(12),
+            timedelta: Option::from(Duration::hours(12))
+        };
+        let parameters = vec![String::from("t_2m:C"), String::from("precip_1h:mm")];
+        let coords = vec![Point { lat: 52.52, lon: 13.405 }, Point { lat: -52.52, lon: 13.405 }];
+        let df = api_client
+            .query_time_series(&time_series, &parameters, &coords, &None)
+            .await
+            .unwrap();
+
+        assert_eq!(server.requests().len(), 4);
+        assert_eq!(df.get_column_names(), &["lat", "lon", "validdate", "t_2m:C", "precip_1h:mm"]);
+        assert_eq!(df.height(), 4);
+        let lats: Vec<f64> = df.column("lat").unwrap().f64().unwrap().into_no_null_iter().collect();
+        assert_eq!(lats, vec![52.52, 52.52, -52.52, -52.52]);
+    }
+
+    #[tokio::test]
+    async fn tiled_grid_is_stitched() {
+        let north = "lat;lon;validdate;t_2m:C\n52.5;13.4;1989-11-09T18:00:00Z;6.8\n52.5;13.45;1989-11-09T18:00:00Z;6.9\n";
+        let south = "lat;lon;validdate;t_2m:C\n52.45;13.4;1989-11-09T18:00:00Z;6.5\n52.45;13.45;1989-11-09T18:00:00Z;6.6\n";
+        let server = FakeServer::start().await;
+        server.respond_once(Endpoint::GridPivoted, FakeResponse::csv(north));
+        server.respond_once(Endpoint::GridPivoted, FakeResponse::csv(south));
+        let limits = TileLimits { max_rows: 1, max_cols: 10, concurrency: 1 };
+        let api_client = APIClient::builder("test_user", "test_password")
+            .base_url(server.url())
+            .grid_tiling(limits)
+            .build()
+            .unwrap();
+
+        let date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let parameters = vec![String::from("t_2m:C")];
+        let bbox = BBox { lat_min: 52.45, lat_max: 52.50, lon_min: 13.40, lon_max: 13.45, lat_res: 0.05, lon_res: 0.05 };
+        let df = api_client.query_grid_unpivoted(&date, &parameters, &bbox, &None).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].segments.contains(&String::from("52.5,13.4_52.5,13.45:0.05,0.05")));
+        assert!(requests[1].segments.contains(&String::from("52.45,13.4_52.45,13.45:0.05,0.05")));
+        assert_eq!(df.shape(), (4, 4));
+    }
+
+    #[tokio::test]
+    async fn cached_queries_do_not_hit_the_api() {
+        let body = "validdate;t_2m:C\n1989-11-09T18:00:00Z;6.8\n";
+        let server = FakeServer::start().await;
+        server.respond(Endpoint::TimeSeries, FakeResponse::csv(body));
+        let directory = std::env::temp_dir().join(format!("meteomatics-client-cache-{}", rand::random::<u64>()));
+        let config = CacheConfig { directory, ..CacheConfig::default() };
+        let api_client = APIClient::builder("test_user", "test_password")
+            .base_url(server.url())
+            .cache(config.clone())
+            .build()
+            .unwrap();
+
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date, timedelta: Some(Duration::hours(1)) };
+        let parameters = vec![String::from("t_2m:C")];
+        let coordinates = vec![Point { lat: 52.52, lon: 13.405 }];
+        let first = api_client.query_time_series(&time_series, &parameters, &coordinates, &None).await.unwrap();
+        let second = api_client.query_time_series(&time_series, &parameters, &coordinates, &None).await.unwrap();
+        assert_eq!(server.requests().len(), 1);
+        assert!(first.frame_equal(&second));
+
+        // Offline, the cached query still works while a new one fails without a request.
+        let offline = APIClient::builder("test_user", "test_password")
+            .base_url(server.url())
+            .cache(CacheConfig { mode: CacheMode::Offline, ..config })
+            .build()
+            .unwrap();
+        assert!(offline.query_time_series(&time_series, &parameters, &coordinates, &None).await.is_ok());
+        let coordinates = vec![Point { lat: 47.42, lon: 9.37 }];
+        let result = offline.query_time_series(&time_series, &parameters, &coordinates, &None).await;
+        assert!(matches!(result, Err(ConnectorError::CacheMiss(_))));
+        assert_eq!(server.requests().len(), 1);
+        api_client.cache().unwrap().clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn time_ranges_are_not_served_from_the_cache() {
+        let server = FakeServer::start().await;
+        let range = |end: &str| FakeResponse::csv(&format!("parameter;min_date;max_date\nt_2m:C;2022-05-01T00:00:00Z;{}\n", end));
+        server.respond(Endpoint::TimeRange, range("2022-05-27T00:00:00Z"));
+        let directory = std::env::temp_dir().join(format!("meteomatics-client-cache-{}", rand::random::<u64>()));
+        let api_client = server
+            .client_builder()
+            .cache(CacheConfig { directory, ..CacheConfig::default() })
+            .time_range_check(true)
+            .time_range_ttl(std::time::Duration::ZERO)
+            .build()
+            .unwrap();
+
+        let parameters = vec![String::from("t_2m:C")];
+        let coordinates = vec![Point { lat: 47.42, lon: 9.37 }];
+        let start_date = Utc.with_ymd_and_hms(2022, 5, 26, 0, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date + Duration::days(1), timedelta: Some(Duration::hours(6)) };
+        api_client.query_time_series(&time_series, &parameters, &coordinates, &None).await.unwrap();
+
+        // A new model run extends the range, which the next check sees despite the cache.
+        server.respond(Endpoint::TimeRange, range("2022-05-28T00:00:00Z"));
+        let later = TimeSeries { start: start_date + Duration::days(1), end: start_date + Duration::days(2), ..time_series };
+        api_client.query_time_series(&later, &parameters, &coordinates, &None).await.unwrap();
+        assert_eq!(server.requests().iter().filter(|r| r.endpoint == Endpoint::TimeRange).count(), 2);
+        api_client.cache().unwrap().clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn recorded_queries_are_replayed_without_network() {
+        let body = "validdate;t_2m:C\n1989-11-09T18:00:00Z;6.8\n";
+        let server = FakeServer::start().await;
+        server.respond(Endpoint::TimeSeries, FakeResponse::csv(body));
+        let path = std::env::temp_dir().join(format!("meteomatics-client-cassette-{}.json", rand::random::<u64>()));
+
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date, timedelta: Some(Duration::hours(1)) };
+        let parameters = vec![String::from("t_2m:C")];
+        let coordinates = vec![Point { lat: 52.52, lon: 13.405 }];
+
+        let recorder = APIClient::builder("test_user", "test_password")
+            .base_url(server.url())
+            .cassette(CassetteConfig { path: path.clone(), mode: CassetteMode::Record })
+            .build()
+            .unwrap();
+        let recorded = recorder.query_time_series(&time_series, &parameters, &coordinates, &None).await.unwrap();
+        recorder.cassette().unwrap().save().unwrap();
+
+        let player = APIClient::builder("", "")
+            .base_url(server.url())
+            .cassette(CassetteConfig { path: path.clone(), mode: CassetteMode::Replay })
+            .build()
+            .unwrap();
+        let replayed = player.query_time_series(&time_series, &parameters, &coordinates, &None).await.unwrap();
+        assert!(recorded.frame_equal(&replayed));
+        assert_eq!(server.requests().len(), 1);
+
+        let coordinates = vec![Point { lat: 47.42, lon: 9.37 }];
+        let result = player.query_time_series(&time_series, &parameters, &coordinates, &None).await;
+        assert!(matches!(result, Err(ConnectorError::ReplayMiss(_))));
+        assert_eq!(server.requests().len(), 1);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn downloads_are_streamed_with_progress() {
+        let server = FakeServer::start().await;
+        let mut body = b"CDF\x01".to_vec();
+        body.extend((0..200_000u32).map(|i| (i % 251) as u8));
+        server.respond(Endpoint::NetCdf, FakeResponse::bytes("application/netcdf", body.clone()));
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&events);
+        let api_client = server.client_builder()
+            .on_download_progress(move |progress| {
+                seen.lock().unwrap().push((progress.file_name.clone(), progress.received, progress.total))
+            })
+            .build()
+            .unwrap();
+
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date + Duration::hours(1), timedelta: Some(Duration::hours(1)) };
+        let bbox = BBox { lat_min: 52.40, lat_max: 52.50, lon_min: 13.40, lon_max: 13.50, lat_res: 0.05, lon_res: 0.05 };
+        let file_name = std::env::temp_dir()
+            .join(format!("meteomatics-download-{}.nc", rand::random::<u64>()))
+            .to_string_lossy()
+            .to_string();
+        api_client.query_netcdf(&time_series, &String::from("t_2m:C"), &bbox, &file_name, &None).await.unwrap();
+        assert_eq!(std::fs::read(&file_name).unwrap(), body);
+
+        let events = events.lock().unwrap().clone();
+        assert!(events.len() > 1);
+        assert_eq!(events.last(), Some(&(file_name.clone(), body.len() as u64, Some(body.len() as u64))));
+
+        // A connection that breaks off is an error instead of a panic and leaves no file behind.
+        server.respond(Endpoint::NetCdf, FakeResponse::bytes("application/netcdf", body).truncated(1000));
+        let result = api_client.query_netcdf(&time_series, &String::from("t_2m:C"), &bbox, &file_name, &None).await;
+        assert!(matches!(result, Err(ConnectorError::CorruptDownload(_, _))));
+        assert!(!std::path::Path::new(&file_name).exists());
+    }
+
+    #[tokio::test]
+    async fn downloads_with_unexpected_content_are_rejected() {
+        let server = FakeServer::start().await;
+        server.respond(Endpoint::Png, FakeResponse::bytes("image/png", b"<html>Maintenance</html>".to_vec()));
+        let api_client = server.client_builder().build().unwrap();
+
+        let directory = std::env::temp_dir().join(format!("meteomatics-png-{}", rand::random::<u64>()));
+        let file_name = directory.join("t_2m.png").to_string_lossy().to_string();
+        let date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let bbox = BBox { lat_min: 52.40, lat_max: 52.50, lon_min: 13.40, lon_max: 13.50, lat_res: 0.05, lon_res: 0.05 };
+        let result = api_client.query_grid_png(&date, &String::from("t_2m:C"), &bbox, &file_name, &None).await;
+        assert!(matches!(result, Err(ConnectorError::CorruptDownload(_, _))));
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 0);
+
+        server.reset();
+        api_client.query_grid_png(&date, &String::from("t_2m:C"), &bbox, &file_name, &None).await.unwrap();
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 1);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn downloads_are_returned_in_memory_or_written_to_a_writer() {
+        let server = FakeServer::start().await;
+        let api_client = server.client_builder().build().unwrap();
+
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date + Duration::hours(2), timedelta: Some(Duration::hours(1)) };
+        let bbox = BBox { lat_min: 52.40, lat_max: 52.50, lon_min: 13.40, lon_max: 13.50, lat_res: 0.05, lon_res: 0.05 };
+        let parameter = String::from("t_2m:C");
+
+        let netcdf = api_client.query_netcdf_bytes(&time_series, &parameter, &bbox, &None).await.unwrap();
+        assert!(netcdf.starts_with(b"CDF\x01"));
+
+        let mut writer: Vec<u8> = Vec::new();
+        let written = api_client.query_grid_png_to_writer(&start_date, &parameter, &bbox, &mut writer, &None).await.unwrap();
+        assert_eq!(written, writer.len() as u64);
+        assert!(writer.starts_with(b"\x89PNG"));
+
+        let images = api_client.query_grid_png_timeseries_bytes(&time_series, &parameter, &bbox, &None).await.unwrap();
+        assert_eq!(images.iter().map(|(date, _)| *date).collect::<Vec<_>>(), vec![
+            start_date, start_date + Duration::hours(1), start_date + Duration::hours(2)
+        ]);
+        assert!(images.iter().all(|(_, image)| image[..] == writer[..]));
+
+        // A time series without timedelta is an error instead of a panic.
+        let time_series = TimeSeries { timedelta: None, ..time_series };
+        let result = api_client.query_grid_png_timeseries_bytes(&time_series, &parameter, &bbox, &None).await;
+        assert!(matches!(result, Err(ConnectorError::LibraryError(_))));
+    }
+
+    #[tokio::test]
+    async fn png_frames_are_resumed() {
+        let server = FakeServer::start().await;
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let broken = start_date + Duration::hours(1);
+        server.respond_with(Endpoint::Png, move |request| {
+            if request.segments[0].parse::<chrono::DateTime<Utc>>() == Ok(broken) {
+                FakeResponse::error(500, "Internal Server Error")
+            } else {
+                FakeResponse::bytes("image/png", b"\x89PNG\r\n\x1a\nframe".to_vec())
+            }
+        });
+        let api_client = server.client_builder().build().unwrap();
+
+        let time_series = TimeSeries { start: start_date, end: start_date + Duration::hours(3), timedelta: Some(Duration::hours(1)) };
+        let bbox = BBox { lat_min: 52.40, lat_max: 52.50, lon_min: 13.40, lon_max: 13.50, lat_res: 0.05, lon_res: 0.05 };
+        let directory = std::env::temp_dir().join(format!("meteomatics-frames-{}", rand::random::<u64>()));
+        let prefix = directory.join("t_2m").to_string_lossy().to_string();
+        let options = FrameOptions { template: String::from("{prefix}/%H%M.png"), skip_existing: true, ..FrameOptions::default() };
+
+        // A failed frame does not affect the others.
+        let manifest = api_client
+            .query_grid_png_frames(&time_series, &String::from("t_2m:C"), &bbox, &prefix, &None, &options)
+            .await
+            .unwrap();
+        assert_eq!(manifest.frames.len(), 4);
+        assert_eq!(manifest.failed().map(|frame| frame.date).collect::<Vec<_>>(), vec![broken]);
+        assert!(manifest.path(&start_date).unwrap().ends_with("t_2m/1800.png"));
+        assert!(manifest.path(&broken).is_none());
+
+        // The second run only fetches the missing frame.
+        server.reset();
+        let manifest = api_client
+            .query_grid_png_frames(&time_series, &String::from("t_2m:C"), &bbox, &prefix, &None, &options)
+            .await
+            .unwrap();
+        assert!(manifest.is_complete());
+        let statuses: Vec<FrameStatus> = manifest.frames.iter().map(|frame| *frame.outcome.as_ref().unwrap()).collect();
+        assert_eq!(statuses, vec![FrameStatus::Skipped, FrameStatus::Downloaded, FrameStatus::Skipped, FrameStatus::Skipped]);
+        assert_eq!(server.requests().len(), 5);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn api_errors_are_structured() {
+        let server = FakeServer::start().await;
+        let api_client = server.client_builder().build().unwrap();
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date + Duration::hours(1), timedelta: Some(Duration::hours(1)) };
+        let coordinates = vec![Point { lat: 52.52, lon: 13.405 }];
+
+        server.respond_once(Endpoint::TimeSeries, FakeResponse::error(400, "Parameter t_2m:X not found."));
+        let result = api_client.query_time_series(&time_series, &[String::from("t_2m:X")], &coordinates, &None).await;
+        match result {
+            Err(ConnectorError::InvalidParameter(e)) => {
+                assert_eq!(e.status, reqwest::StatusCode::BAD_REQUEST);
+                assert_eq!(e.message, "Parameter t_2m:X not found.");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        server.respond_once(Endpoint::UserStats, FakeResponse::error(401, "Unauthorized"));
+        let error = api_client.query_user_features().await.unwrap_err();
+        assert!(matches!(error, ConnectorError::AuthenticationFailed(_)));
+        assert_eq!(error.status(), Some(reqwest::StatusCode::UNAUTHORIZED));
+    }
+
+    #[tokio::test]
+    async fn credentials_are_resolved_through_the_provider() {
+        let server = FakeServer::start().await;
+        let api_client = APIClient::builder_with(Credentials::new("profile_user", "s3cr3t"))
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        api_client.query_user_features().await.unwrap();
+        assert_eq!(server.requests()[0].username().as_deref(), Some("profile_user"));
+        assert!(!format!("{:?}", api_client).contains("s3cr3t"));
+
+        let missing = APIClient::builder_with(CredentialChain::new())
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        let result = missing.query_user_features().await;
+        assert!(matches!(result, Err(ConnectorError::MissingCredentials(_))));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[derive(Debug, Default)]
+    struct CountingProvider {
+        calls: Arc<AtomicU64>,
+    }
+
+    impl CredentialProvider for CountingProvider {
+        fn credentials(&self) -> Result<Credentials, ConnectorError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            Ok(Credentials::new("counted_user", "s3cr3t"))
+        }
+    }
+
+    #[tokio::test]
+    async fn credentials_are_kept_until_refreshed() {
+        let server = FakeServer::start().await;
+        let provider = CountingProvider::default();
+        let calls = Arc::clone(&provider.calls);
+        let api_client = APIClient::builder_with(provider).base_url(server.url()).build().unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+
+        api_client.query_user_features().await.unwrap();
+        api_client.query_user_features().await.unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        api_client.refresh_credentials().await;
+        api_client.query_user_features().await.unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        assert!(server.requests().iter().all(|r| r.username().as_deref() == Some("counted_user")));
+
+        let expiring = CountingProvider::default();
+        let calls = Arc::clone(&expiring.calls);
+        let api_client = APIClient::builder_with(expiring)
+            .base_url(server.url())
+            .credentials_ttl(std::time::Duration::ZERO)
+            .build()
+            .unwrap();
+        api_client.query_user_features().await.unwrap();
+        api_client.query_user_features().await.unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn access_tokens_are_shared_and_renewed() {
+        let server = FakeServer::start().await;
+        let config = TokenConfig { token_url: server.token_url(), ..TokenConfig::default() };
+        let api_client = server.client_builder().auth(AuthMode::Token(config)).build().unwrap();
+
+        // Concurrent queries wait for a single token.
+        let (a, b, c) = tokio::join!(
+            api_client.query_user_features(),
+            api_client.query_user_features(),
+            api_client.query_user_features()
+        );
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+        let requests = server.requests();
+        assert_eq!(requests.iter().filter(|r| r.endpoint == Endpoint::Token).count(), 1);
+        assert_eq!(requests[0].username().as_deref(), Some("fake_user"));
+        let stats: Vec<&FakeRequest> = requests.iter().filter(|r| r.endpoint == Endpoint::UserStats).collect();
+        assert!(stats.iter().all(|r| r.username().is_none() && r.access_token() == stats[0].access_token()));
+
+        // A rejected token is renewed and the query repeated.
+        server.respond_once(Endpoint::UserStats, FakeResponse::error(401, "Token expired"));
+        api_client.query_user_features().await.unwrap();
+        let requests = server.requests();
+        assert_eq!(requests.iter().filter(|r| r.endpoint == Endpoint::Token).count(), 2);
+        let last = requests.last().unwrap();
+        assert!(last.access_token().is_some() && last.access_token() != stats[0].access_token());
+
+        // Query tokens are sent as parameter instead of a header.
+        let config = TokenConfig { token_url: server.token_url(), placement: TokenPlacement::Query, ..TokenConfig::default() };
+        let api_client = server.client_builder().auth(AuthMode::Token(config)).build().unwrap();
+        api_client.query_user_features().await.unwrap();
+        let last = server.requests().pop().unwrap();
+        assert!(last.query_param("access_token").is_some() && last.header("authorization").is_none());
+    }
+
+    #[tokio::test]
+    async fn query_options_are_encoded_and_checked_before_sending() {
+        let server = FakeServer::start().await;
+        let api_client = server.client_builder().build().unwrap();
+
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date, timedelta: Some(Duration::hours(1)) };
+        let parameters = vec![String::from("t_2m:C")];
+        let coordinates = vec![Point { lat: 52.52, lon: 13.405 }];
+        let options = QueryOptions::new()
+            .model("ecmwf-ens")
+            .ens_select(&[EnsembleSelection::Member(0), EnsembleSelection::Median])
+            .raw("custom", "a b&c");
+        api_client.query_time_series(&time_series, &parameters, &coordinates, &options).await.unwrap();
+
+        let request = server.requests().pop().unwrap();
+        assert_eq!(request.query_param("model"), Some("ecmwf-ens"));
+        assert_eq!(request.query_param("ens_select"), Some("member:0,median"));
+        assert_eq!(request.query_param("custom"), Some("a b&c"));
+
+        let invalid = QueryOptions::new().ens_select(&[EnsembleSelection::Quantile(1.5)]);
+        let result = api_client.query_time_series(&time_series, &parameters, &coordinates, &invalid).await;
+        assert!(matches!(result, Err(ConnectorError::InvalidOption(_))));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn ensemble_selection_returns_long_format() {
+        let csv = "validdate;t_2m:C-m1;t_2m:C-m2;t_2m:C-median\n1989-11-09T18:00:00Z;6;7.5;6.8\n";
+        let server = FakeServer::start().await;
+        server.respond(Endpoint::TimeSeries, FakeResponse::csv(csv));
+        let api_client = APIClient::builder("test_user", "test_password")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date, timedelta: Some(Duration::hours(1)) };
+        let options = QueryOptions::new().ens_select(&[EnsembleSelection::Members(1, 2), EnsembleSelection::Median]);
+        let df = api_client
+            .query_time_series(&time_series, &[String::from("t_2m:C")], &[Point { lat: 52.52, lon: 13.405 }], &options)
+            .await
+            .unwrap();
+
+        assert_eq!(df.get_column_names(), &["lat", "lon", "validdate", "member", "quantile", "t_2m:C"]);
+        let members: Vec<&str> = df.column("member").unwrap().utf8().unwrap().into_no_null_iter().collect();
+        assert_eq!(members, vec!["1", "2", "median"]);
+        let values: Vec<f64> = df.column("t_2m:C").unwrap().f64().unwrap().into_no_null_iter().collect();
+        assert_eq!(values, vec![6.0, 7.5, 6.8]);
+
+        // A verbatim selection is reshaped as well.
+        let verbatim = Some(vec![String::from("ens_select=member:1-2,median")]);
+        let df = api_client
+            .query_time_series(&time_series, &[String::from("t_2m:C")], &[Point { lat: 52.52, lon: 13.405 }], &verbatim)
+            .await
+            .unwrap();
+        assert_eq!(df.get_column_names(), &["lat", "lon", "validdate", "member", "quantile", "t_2m:C"]);
+    }
+
+    #[tokio::test]
+    async fn pivoted_grids_reject_ensemble_selections() {
+        let server = FakeServer::start().await;
+        let api_client = server.client_builder().build().unwrap();
+        let date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let bbox = BBox { lat_min: 52.40, lat_max: 52.50, lon_min: 13.40, lon_max: 13.50, lat_res: 0.05, lon_res: 0.05 };
+
+        let options = QueryOptions::new().ens_select(&[EnsembleSelection::Members(1, 2)]);
+        let result = api_client.query_grid_pivoted(&date, "t_2m:C", &bbox, &options).await;
+        assert!(matches!(result, Err(ConnectorError::InvalidOption(_))));
+        let verbatim = Some(vec![String::from("ens_select=member:1-2")]);
+        let result = api_client.query_grid_pivoted(&date, "t_2m:C", &bbox, &verbatim).await;
+        assert!(matches!(result, Err(ConnectorError::InvalidOption(_))));
+        assert!(server.requests().is_empty());
+
+        assert!(api_client.query_grid_pivoted(&date, "t_2m:C", &bbox, &None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn models_are_checked_and_compared() {
+        let server = FakeServer::start().await;
+        let limit = serde_json::json!({"used": 0, "soft limit": 0, "hard limit": 0});
+        let stats = serde_json::json!({"message": "", "user statistics": {
+            "username": "test_user", "requests total": limit, "requests since last UTC midnight": limit,
+            "requests since HH:00:00": limit, "requests in the last 60 seconds": limit, "requests in parallel": limit,
+            "historic request option": "", "area request option": true, "model set": ["all_minus_euro1k"],
+            "error message": "", "contact emails": []
+        }});
+        server.respond(Endpoint::UserStats, FakeResponse::json(&stats.to_string()));
+        let api_client = server.client_builder().build().unwrap();
+
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date, timedelta: Some(Duration::hours(1)) };
+        let parameters = vec![String::from("t_2m:C")];
+        let coordinates = vec![Point { lat: 52.52, lon: 13.405 }];
+        let models = vec![String::from("mix"), String::from("ecmwf-ifs")];
+        let verbatim = Some(vec![String::from("model=dwd-icon-eu"), String::from("source=dwd-icon-eu")]);
+        let df = api_client
+            .query_model_comparison(&time_series, &parameters, &coordinates, &models, &verbatim)
+            .await
+            .unwrap();
+        assert_eq!(df.get_column_names(), &["lat", "lon", "validdate", "model", "t_2m:C"]);
+        let compared: Vec<&str> = df.column("model").unwrap().utf8().unwrap().into_no_null_iter().collect();
+        assert_eq!(compared, vec!["mix", "ecmwf-ifs"]);
+        let mut requested: Vec<String> = server
+            .requests()
+            .iter()
+            .filter_map(|request| request.query_param("model").map(String::from))
+            .collect();
+        requested.sort();
+        assert_eq!(requested, vec!["ecmwf-ifs", "mix"]);
+        assert!(server.requests().iter().all(|request| request.query_param("source").is_none()));
+
+        let result = api_client
+            .query_model_comparison(&time_series, &parameters, &coordinates, &[String::from("mm-euro1k")], &None)
+            .await;
+        assert!(matches!(result, Err(ConnectorError::InvalidOption(_))));
+    }
+
+    #[tokio::test]
+    async fn typed_parameters_are_queried_and_mapped_back() {
+        let csv = "validdate;t_max_2m_24h:C;precip_24h:mm\n1989-11-09T00:00:00Z;9.5;0.2\n";
+        let server = FakeServer::start().await;
+        server.respond(Endpoint::TimeSeries, FakeResponse::csv(csv));
+        let api_client = APIClient::builder("test_user", "test_password")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 0, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date, timedelta: Some(Duration::days(1)) };
+        let t_max = Parameter::new("t", "C").aggregation(Aggregation::Max).level("2m").interval("24h");
+        let parameters = vec![t_max.clone(), "precip_24h:mm".parse().unwrap()];
+        let df = api_client
+            .query_time_series(&time_series, &parameters, &[Point { lat: 52.52, lon: 13.405 }], &None)
+            .await
+            .unwrap();
+
+        assert!(server.requests()[0].segments.contains(&String::from("t_max_2m_24h:C,precip_24h:mm")));
+        let columns = parameter_columns(&df);
+        assert_eq!(columns[0], ("t_max_2m_24h:C", t_max));
+        assert_eq!(columns.iter().map(|(_, p)| p.clone()).collect::<Vec<Parameter>>(), parameters);
+    }
+
+    #[tokio::test]
+    async fn station_list_accepts_typed_parameters() {
+        let server = FakeServer::start().await;
+        let api_client = server.client_builder().build().unwrap();
+        let parameters = Some(vec![Parameter::new("t", "C").level("2m")]);
+        api_client.query_station_list(&Some("50.7,10.4"), &parameters, &None, &None, &None).await.unwrap();
+        let request = &server.requests()[0];
+        assert_eq!(request.endpoint, Endpoint::FindStation);
+        assert_eq!(request.query_param("parameters"), Some("t_2m:C"));
+    }
+
+    #[tokio::test]
+    async fn unknown_parameters_are_rejected_before_sending() {
+        let server = FakeServer::start().await;
+        let api_client = APIClient::builder("test_user", "test_password")
+            .base_url(server.url())
+            .parameter_check(Catalog::bundled().clone())
+            .build()
+            .unwrap();
+
+        let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date, timedelta: Some(Duration::hours(1)) };
+        let parameters = vec![String::from("t_2m:C"), String::from("wind_sped_10m:ms")];
+        let result = api_client
+            .query_time_series(&time_series, &parameters, &[Point { lat: 52.52, lon: 13.405 }], &None)
+            .await;
+        match result {
+            Err(ConnectorError::UnknownParameter(message)) => assert!(message.contains("did you mean wind_speed?")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(server.requests().is_empty());
+
+        // Parameters that are missing from the catalog are only rejected by the strict check.
+        let parameters = vec![String::from("t_2m:C"), String::from("frost_depth:cm")];
+        let point = [Point { lat: 52.52, lon: 13.405 }];
+        api_client.query_time_series(&time_series, &parameters, &point, &None).await.unwrap();
+        let strict = server.client_builder().strict_parameter_check(Catalog::bundled().clone()).build().unwrap();
+        let result = strict.query_time_series(&time_series, &parameters, &point, &None).await;
+        assert!(matches!(result, Err(ConnectorError::UnknownParameter(_))));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn init_dates_are_returned_as_datetimes() {
+        let server = FakeServer::start().await;
+        let api_client = server.client_builder().build().unwrap();
+
+        let start_date = Utc.with_ymd_and_hms(2022, 5, 17, 0, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date + Duration::hours(30), timedelta: Some(Duration::hours(6)) };
+        let df = api_client
+            .query_init_dates("ecmwf-ifs", &time_series, &[String::from("t_2m:C"), String::from("precip_1h:mm")])
+            .await
+            .unwrap();
+
+        let request = &server.requests()[0];
+        assert_eq!(request.endpoint, Endpoint::InitDate);
+        assert_eq!(request.query_param("model"), Some("ecmwf-ifs"));
+        assert_eq!(request.query_param("valid_date"), Some("2022-05-17T00:00:00Z--2022-05-18T06:00:00Z:PT21600S"));
+        assert_eq!(df.get_column_names(), &["validdate", "t_2m:C", "precip_1h:mm"]);
+        assert_eq!(df.height(), 6);
+        let init_dates = df.column("t_2m:C").unwrap().datetime().unwrap();
+        assert_eq!(init_dates.get(0), Some(start_date.timestamp_millis()));
+        assert_eq!(init_dates.get(5), Some((start_date + Duration::days(1)).timestamp_millis()));
+    }
+
+    #[tokio::test]
+    async fn queries_outside_the_available_time_range_are_rejected() {
+        let server = FakeServer::start().await;
+        server.respond(Endpoint::TimeRange, FakeResponse::csv(
+            "parameter;min_date;max_date\nt_2m:C;2022-05-01T00:00:00Z;2022-05-27T00:00:00Z\n"
+        ));
+        let api_client = server.client_builder().time_range_check(true).build().unwrap();
+
+        let ranges = api_client.query_available_time_range("ecmwf-ifs", &[String::from("t_2m:C")]).await.unwrap();
+        assert_eq!(ranges[0].end, Some(Utc.with_ymd_and_hms(2022, 5, 27, 0, 0, 0).unwrap()));
+
+        let parameters = vec![String::from("t_2m:C")];
+        let start_date = Utc.with_ymd_and_hms(2022, 5, 26, 0, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date + Duration::days(1), timedelta: Some(Duration::hours(6)) };
+        let optionals = QueryOptions::new().model("ecmwf-ifs");
+        let coordinates = vec![Point { lat: 47.42, lon: 9.37 }];
+        assert!(api_client.query_time_series(&time_series, &parameters, &coordinates, &optionals).await.is_ok());
+
+        let late = TimeSeries { end: start_date + Duration::days(2), ..time_series };
+        let result = api_client.query_time_series(&late, &parameters, &coordinates, &optionals).await;
+        match result {
+            Err(ConnectorError::UnavailableTimeRange(message)) => assert!(message.contains("(model ecmwf-ifs)")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let bbox = BBox { lat_min: 47.0, lat_max: 47.5, lon_min: 8.0, lon_max: 9.0, lat_res: 0.5, lon_res: 0.5 };
+        let result = api_client.query_grid_pivoted(&late.end, &parameters[0], &bbox, &Some(vec![String::from("model=mix")])).await;
+        assert!(matches!(result, Err(ConnectorError::UnavailableTimeRange(_))));
+
+        // The ranges are kept per model, the second ecmwf-ifs query is checked without a request.
+        let requests = server.requests();
+        let endpoints: Vec<Endpoint> = requests.iter().map(|r| r.endpoint).collect();
+        assert_eq!(endpoints, vec![Endpoint::TimeRange, Endpoint::TimeRange, Endpoint::TimeSeries, Endpoint::TimeRange]);
+        assert_eq!(requests[1].query_param("model"), Some("ecmwf-ifs"));
+        assert_eq!(requests[3].query_param("model"), Some("mix"));
+
+        // The source option is an alias of the model.
+        let source = Some(vec![String::from("source=dwd-icon-eu")]);
+        let result = api_client.query_time_series(&late, &parameters, &coordinates, &source).await;
+        match result {
+            Err(ConnectorError::UnavailableTimeRange(message)) => assert!(message.contains("(model dwd-icon-eu)")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(server.requests().last().unwrap().query_param("model"), Some("dwd-icon-eu"));
+
+        // A series of PNGs is checked once, not per frame.
+        let images = api_client.query_grid_png_timeseries_bytes(&time_series, "t_2m:C", &bbox, &optionals).await.unwrap();
+        assert_eq!(images.len(), 5);
+        let ranges = server.requests().iter().filter(|r| r.endpoint == Endpoint::TimeRange).count();
+        assert_eq!(ranges, 4);
+
+        // Parameters without data are rejected.
+        server.respond(Endpoint::TimeRange, FakeResponse::csv(
+            "parameter;min_date;max_date\nsst:K;0000-00-00T00:00:00Z;0000-00-00T00:00:00Z\n"
+        ));
+        let result = api_client.query_time_series(&time_series, &["sst:K"], &coordinates, &optionals).await;
+        match result {
+            Err(ConnectorError::UnavailableTimeRange(message)) => assert_eq!(message, "sst:K: no data (model ecmwf-ifs)"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,99 @@
+//! # Comparison
+//! Forecasts of several models for the same locations (see
+//! [`crate::APIClient::query_model_comparison`]). The models are queried concurrently and their
+//! results are combined into a single [`DataFrame`] with a [`MODEL_COLUMN`]. The rows of all models
+//! for the same location and point in time follow each other, such that the models can be compared
+//! row by row.
+//!
+//! ```rust, no_run
+//! use chrono::{Duration, Utc};
+//! use meteomatics::{APIClient, Point, TimeSeries};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = APIClient::new("ferris_loves_rustaceans", "0123456789", 10);
+//!     let time_series = TimeSeries {
+//!         start: Utc::now(),
+//!         end: Utc::now() + Duration::days(2),
+//!         timedelta: Option::from(Duration::hours(1))
+//!     };
+//!     let models = vec![String::from("mix"), String::from("ecmwf-ifs"), String::from("dwd-icon-eu")];
+//!     let df = client
+//!         .query_model_comparison(&time_series, &[String::from("t_2m:C")], &[Point { lat: 47.4, lon: 9.4 }], &models, &None)
+//!         .await
+//!         .unwrap();
+//!     println!("{:?}", df);
+//! }
+//! ```
+
+use crate::ensemble::is_parameter_column;
+use crate::errors::ConnectorError;
+use crate::split::concat_frames;
+use polars::prelude::*;
+use std::collections::HashMap;
+
+/// Name of the column with the model of a row.
+pub const MODEL_COLUMN: &str = "model";
+
+/// Combines the results of several models. The [`MODEL_COLUMN`] is inserted before the first
+/// parameter column, the columns before it (e.g. ```lat```, ```lon``` and ```validdate```) identify
+/// the rows that are compared. The rows are ordered by these columns (in the order in which they
+/// first appear) and then by model.
+///
+/// # Arguments
+///
+/// * `frames` - The model and its result, in the order of the models.
+/// * `parameters` - The queried parameters.
+pub fn combine_models(
+    frames: Vec<(String, DataFrame)>,
+    parameters: &[String],
+) -> std::result::Result<DataFrame, ConnectorError> {
+    let polars_error = |e: PolarsError| ConnectorError::PolarsError(e.to_string());
+    let mut with_model = Vec::with_capacity(frames.len());
+    for (model, mut df) in frames {
+        let position = df
+            .get_columns()
+            .iter()
+            .position(|s| parameters.iter().any(|p| is_parameter_column(s.name(), p)))
+            .unwrap_or(df.width());
+        let column = Series::new(MODEL_COLUMN, vec![model.as_str(); df.height()]);
+        df.insert_at_idx(position, column).map_err(polars_error)?;
+        with_model.push(df);
+    }
+    let df = concat_frames(with_model)?;
+
+    let n_keys = df.get_column_names().iter().position(|name| *name == MODEL_COLUMN).unwrap_or(0);
+    let keys: Vec<&Series> = df.get_columns()[..n_keys].iter().collect();
+    let mut groups: HashMap<String, usize> = HashMap::new();
+    let mut rows: Vec<(usize, usize)> = Vec::with_capacity(df.height());
+    for row in 0..df.height() {
+        let key = keys.iter().map(|s| s.get(row).to_string()).collect::<Vec<String>>().join(";");
+        let next = groups.len();
+        let group = *groups.entry(key).or_insert(next);
+        rows.push((group, row));
+    }
+    rows.sort();
+    let indices: Vec<IdxSize> = rows.into_iter().map(|(_, row)| row as IdxSize).collect();
+    df.take(&IdxCa::from_vec("", indices)).map_err(polars_error)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::comparison::combine_models;
+    use polars::prelude::*;
+
+    #[tokio::test]
+    async fn models_are_aligned_on_location_and_time() {
+        let mix = df!("lat" => &[47.0, 47.0], "lon" => &[9.0, 9.0], "validdate" => &["t1", "t2"], "t_2m:C" => &[10.0, 11.0]).unwrap();
+        let ecmwf = df!("lat" => &[47.0, 47.0], "lon" => &[9.0, 9.0], "validdate" => &["t1", "t2"], "t_2m:C" => &[9, 12]).unwrap();
+        let parameters = vec![String::from("t_2m:C")];
+        let df = combine_models(vec![(String::from("mix"), mix), (String::from("ecmwf-ifs"), ecmwf)], &parameters).unwrap();
+
+        assert_eq!(df.get_column_names(), &["lat", "lon", "validdate", "model", "t_2m:C"]);
+        let models: Vec<&str> = df.column("model").unwrap().utf8().unwrap().into_no_null_iter().collect();
+        assert_eq!(models, vec!["mix", "ecmwf-ifs", "mix", "ecmwf-ifs"]);
+        let values: Vec<f64> = df.column("t_2m:C").unwrap().f64().unwrap().into_no_null_iter().collect();
+        assert_eq!(values, vec![10.0, 9.0, 11.0, 12.0]);
+    }
+}
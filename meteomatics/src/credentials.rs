@@ -0,0 +1,369 @@
+//! # Credentials
+//! The [`crate::APIClient`] resolves the username and password of the Meteomatics API account through
+//! a [`CredentialProvider`]. This keeps passwords out of the source code and allows to rotate them
+//! without restarting the program: the resolved credentials are kept for a limited time (see
+//! [`crate::APIClientBuilder::credentials_ttl`]) and can be reloaded with
+//! [`crate::APIClient::refresh_credentials`]. The following providers are available:
+//!
+//! * [`Credentials`] - Explicit credentials (e.g. from a command line argument).
+//! * [`EnvCredentials`] - The environment variables ```METEOMATICS_USER``` and ```METEOMATICS_PW```.
+//! * [`DotEnvCredentials`] - The same variables in a ```.env``` file.
+//! * [`ProfileCredentials`] - A named profile in ```~/.config/meteomatics/credentials.toml```.
+//! * [`CredentialChain`] - The first of several providers that has credentials (see
+//!   [`CredentialChain::default`]).
+//!
+//! Secret stores (e.g. a vault or the keychain of the operating system) are supported by implementing
+//! the [`CredentialProvider`] trait.
+//!
+//! The profiles file contains one table per profile:
+//!
+//! ```toml
+//! [default]
+//! username = "ferris_loves_rustaceans"
+//! password = "0123456789"
+//!
+//! [production]
+//! username = "ferris_in_production"
+//! password = "9876543210"
+//! ```
+//!
+//! ```rust, no_run
+//! use meteomatics::APIClient;
+//! use meteomatics::credentials::{CredentialChain, ProfileCredentials};
+//!
+//! // Environment, .env file or the "default" profile (in this order).
+//! let client = APIClient::builder_with(CredentialChain::default()).build().unwrap();
+//!
+//! // A specific profile.
+//! let client = APIClient::builder_with(ProfileCredentials::new("production")).build().unwrap();
+//! ```
+
+use crate::errors::ConnectorError;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Environment variable with the username.
+pub const USER_VARIABLE: &str = "METEOMATICS_USER";
+/// Environment variable with the password.
+pub const PASSWORD_VARIABLE: &str = "METEOMATICS_PW";
+/// Environment variable with the name of the profile (see [`ProfileCredentials::from_env`]).
+pub const PROFILE_VARIABLE: &str = "METEOMATICS_PROFILE";
+/// How long the client keeps resolved credentials by default.
+pub const DEFAULT_CREDENTIALS_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Source of the credentials for the Meteomatics API account.
+pub trait CredentialProvider: fmt::Debug + Send + Sync {
+    /// Returns the current credentials. Fails with [`ConnectorError::MissingCredentials`] if the
+    /// provider has none.
+    fn credentials(&self) -> Result<Credentials, ConnectorError>;
+}
+
+/// Username and password of a Meteomatics API account. The password is not printed by ```Debug```.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+pub struct Credentials {
+    /// Username of the account.
+    pub username: String,
+    /// Password of the account.
+    pub password: String,
+}
+
+impl Credentials {
+    /// Creates explicit credentials.
+    pub fn new(username: &str, password: &str) -> Self {
+        Self { username: username.to_string(), password: password.to_string() }
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+impl CredentialProvider for Credentials {
+    fn credentials(&self) -> Result<Credentials, ConnectorError> {
+        Ok(self.clone())
+    }
+}
+
+/// Reads the credentials from environment variables.
+#[derive(Clone, Debug)]
+pub struct EnvCredentials {
+    /// Name of the variable with the username.
+    pub user_variable: String,
+    /// Name of the variable with the password.
+    pub password_variable: String,
+}
+
+impl Default for EnvCredentials {
+    fn default() -> Self {
+        Self {
+            user_variable: String::from(USER_VARIABLE),
+            password_variable: String::from(PASSWORD_VARIABLE),
+        }
+    }
+}
+
+impl CredentialProvider for EnvCredentials {
+    fn credentials(&self) -> Result<Credentials, ConnectorError> {
+        let variable = |name: &str| {
+            std::env::var(name).map_err(|_| {
+                ConnectorError::MissingCredentials(format!("environment variable {} is not set", name))
+            })
+        };
+        Ok(Credentials {
+            username: variable(&self.user_variable)?,
+            password: variable(&self.password_variable)?,
+        })
+    }
+}
+
+/// Reads the credentials from the variables ```METEOMATICS_USER``` and ```METEOMATICS_PW``` in a
+/// ```.env``` file. The file is not loaded into the environment of the process.
+#[derive(Clone, Debug)]
+pub struct DotEnvCredentials {
+    /// Path of the file.
+    pub path: PathBuf,
+}
+
+impl DotEnvCredentials {
+    /// Reads the given file.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl Default for DotEnvCredentials {
+    /// Reads the ```.env``` file in the current working directory.
+    fn default() -> Self {
+        Self::new(".env")
+    }
+}
+
+impl CredentialProvider for DotEnvCredentials {
+    fn credentials(&self) -> Result<Credentials, ConnectorError> {
+        let content = std::fs::read_to_string(&self.path).map_err(|e| {
+            ConnectorError::MissingCredentials(format!("cannot read {}: {}", self.path.display(), e))
+        })?;
+        let variables = parse_dotenv(&content);
+        let variable = |name: &str| {
+            variables.get(name).cloned().ok_or_else(|| {
+                ConnectorError::MissingCredentials(format!("{} does not define {}", self.path.display(), name))
+            })
+        };
+        Ok(Credentials {
+            username: variable(USER_VARIABLE)?,
+            password: variable(PASSWORD_VARIABLE)?,
+        })
+    }
+}
+
+// Parses the lines "KEY=VALUE" of a .env file (with optional "export" and quotes).
+fn parse_dotenv(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = match (value.chars().next(), value.chars().last()) {
+                (Some(first @ ('"' | '\'')), Some(last)) if value.len() >= 2 && first == last => {
+                    &value[1..value.len() - 1]
+                }
+                // Unquoted values end at a comment.
+                _ => value.split(" #").next().unwrap_or_default().trim_end(),
+            };
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Reads the credentials from a named profile in a TOML file (see the module documentation).
+#[derive(Clone, Debug)]
+pub struct ProfileCredentials {
+    /// Path of the profiles file.
+    pub path: PathBuf,
+    /// Name of the profile.
+    pub profile: String,
+}
+
+impl ProfileCredentials {
+    /// Reads the profile from the default file (see [`ProfileCredentials::default_path`]).
+    pub fn new(profile: &str) -> Self {
+        Self { path: Self::default_path(), profile: profile.to_string() }
+    }
+
+    /// Reads the profile named by ```METEOMATICS_PROFILE``` (or ```default```) from the default file.
+    pub fn from_env() -> Self {
+        Self::new(&std::env::var(PROFILE_VARIABLE).unwrap_or_else(|_| String::from("default")))
+    }
+
+    /// Reads the profile from the given file.
+    pub fn with_path(path: impl Into<PathBuf>, profile: &str) -> Self {
+        Self { path: path.into(), profile: profile.to_string() }
+    }
+
+    /// Returns ```$XDG_CONFIG_HOME/meteomatics/credentials.toml``` or
+    /// ```~/.config/meteomatics/credentials.toml```.
+    pub fn default_path() -> PathBuf {
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .or_else(|| std::env::var_os("USERPROFILE"))
+                    .map(|home| PathBuf::from(home).join(".config"))
+            })
+            .unwrap_or_else(|| PathBuf::from(".config"));
+        config.join("meteomatics").join("credentials.toml")
+    }
+}
+
+impl CredentialProvider for ProfileCredentials {
+    fn credentials(&self) -> Result<Credentials, ConnectorError> {
+        let content = std::fs::read_to_string(&self.path).map_err(|e| {
+            ConnectorError::MissingCredentials(format!("cannot read {}: {}", self.path.display(), e))
+        })?;
+        let mut profiles: HashMap<String, Credentials> = toml::from_str(&content).map_err(|e| {
+            ConnectorError::MissingCredentials(format!("invalid profiles file {}: {}", self.path.display(), e))
+        })?;
+        profiles.remove(&self.profile).ok_or_else(|| {
+            ConnectorError::MissingCredentials(format!(
+                "profile {} not found in {}", self.profile, self.path.display()
+            ))
+        })
+    }
+}
+
+/// Asks several providers in order and returns the first credentials that are found.
+#[derive(Clone, Debug)]
+pub struct CredentialChain {
+    providers: Vec<Arc<dyn CredentialProvider>>,
+}
+
+impl CredentialChain {
+    /// Creates an empty chain.
+    pub fn new() -> Self {
+        Self { providers: Vec::new() }
+    }
+
+    /// Appends a provider to the chain.
+    pub fn with<P: CredentialProvider + 'static>(mut self, provider: P) -> Self {
+        self.providers.push(Arc::new(provider));
+        self
+    }
+}
+
+impl Default for CredentialChain {
+    /// Environment variables, the ```.env``` file in the working directory and the profile named by
+    /// ```METEOMATICS_PROFILE``` (or ```default```), in this order.
+    fn default() -> Self {
+        Self::new()
+            .with(EnvCredentials::default())
+            .with(DotEnvCredentials::default())
+            .with(ProfileCredentials::from_env())
+    }
+}
+
+impl CredentialProvider for CredentialChain {
+    fn credentials(&self) -> Result<Credentials, ConnectorError> {
+        let mut reasons = Vec::new();
+        for provider in &self.providers {
+            match provider.credentials() {
+                Ok(credentials) => return Ok(credentials),
+                Err(ConnectorError::MissingCredentials(reason)) => reasons.push(reason),
+                Err(e) => return Err(e),
+            }
+        }
+        Err(ConnectorError::MissingCredentials(reasons.join("; ")))
+    }
+}
+
+/// Credentials resolved through a provider and kept for a limited time, such that the provider (which
+/// may read files) is not asked on every request. The provider runs on the blocking thread pool.
+#[derive(Debug)]
+pub(crate) struct CachedCredentials {
+    provider: Arc<dyn CredentialProvider>,
+    ttl: Duration,
+    resolved: Mutex<Option<(Credentials, Instant)>>,
+}
+
+impl CachedCredentials {
+    pub(crate) fn new(provider: Arc<dyn CredentialProvider>, ttl: Duration) -> Self {
+        Self { provider, ttl, resolved: Mutex::new(None) }
+    }
+
+    /// Returns the kept credentials or resolves them again once they are older than the TTL.
+    pub(crate) async fn get(&self) -> Result<Credentials, ConnectorError> {
+        let mut resolved = self.resolved.lock().await;
+        if let Some((credentials, at)) = resolved.as_ref() {
+            if at.elapsed() < self.ttl {
+                return Ok(credentials.clone());
+            }
+        }
+        let provider = Arc::clone(&self.provider);
+        let credentials = tokio::task::spawn_blocking(move || provider.credentials())
+            .await
+            .map_err(|e| ConnectorError::LibraryError(e.to_string()))??;
+        *resolved = Some((credentials.clone(), Instant::now()));
+        Ok(credentials)
+    }
+
+    /// Drops the kept credentials, such that the next request resolves them again.
+    pub(crate) async fn refresh(&self) {
+        *self.resolved.lock().await = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::credentials::{
+        CredentialChain, CredentialProvider, Credentials, DotEnvCredentials, EnvCredentials, ProfileCredentials
+    };
+    use crate::errors::ConnectorError;
+
+    #[tokio::test]
+    async fn credentials_are_resolved_from_files_and_environment() {
+        let directory = std::env::temp_dir().join(format!("meteomatics-credentials-{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let profiles = directory.join("credentials.toml");
+        std::fs::write(&profiles, "[default]\nusername = \"ferris\"\npassword = \"crab\"\n\n\
+            [production]\nusername = \"ferris_prod\"\npassword = \"lobster\"\n").unwrap();
+        let credentials = ProfileCredentials::with_path(&profiles, "production").credentials().unwrap();
+        assert_eq!(credentials, Credentials::new("ferris_prod", "lobster"));
+        assert!(matches!(
+            ProfileCredentials::with_path(&profiles, "staging").credentials(),
+            Err(ConnectorError::MissingCredentials(_))
+        ));
+
+        let dotenv = directory.join(".env");
+        std::fs::write(&dotenv, "# account\nexport METEOMATICS_USER=ferris\nMETEOMATICS_PW=\"p#ss word\"\n").unwrap();
+        assert_eq!(DotEnvCredentials::new(&dotenv).credentials().unwrap(), Credentials::new("ferris", "p#ss word"));
+
+        // The chain falls through providers without credentials.
+        let env = EnvCredentials {
+            user_variable: String::from("METEOMATICS_TEST_UNSET_USER"),
+            password_variable: String::from("METEOMATICS_TEST_UNSET_PW"),
+        };
+        let chain = CredentialChain::new().with(env).with(DotEnvCredentials::new(&dotenv));
+        assert_eq!(chain.credentials().unwrap().username, "ferris");
+        assert!(matches!(
+            CredentialChain::new().with(DotEnvCredentials::new(directory.join("missing"))).credentials(),
+            Err(ConnectorError::MissingCredentials(_))
+        ));
+
+        assert!(!format!("{:?}", Credentials::new("ferris", "crab")).contains("crab"));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
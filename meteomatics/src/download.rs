@@ -0,0 +1,258 @@
+//! # Download
+//! File downloads (NetCDF and PNG) are streamed chunk by chunk to disk or to any [`AsyncWrite`] (e.g.
+//! an upload to an object storage), such that even very large grids never have to fit into memory.
+//! The progress of a download can be observed with a callback
+//! that is registered on the client (see [`crate::APIClientBuilder::on_download_progress`]). Note that
+//! responses that pass through the [`crate::cache`] or a [`crate::cassette`] are held in memory.
+//!
+//! The first bytes of a download are checked against the signature of the expected [`FileFormat`]
+//! before anything is written. Downloads to a file are first written to a temporary file next to the
+//! target ([`AtomicFile`]), which is only renamed to the target once the download is complete.
+//! Interrupted or corrupt downloads fail with [`ConnectorError::CorruptDownload`] and leave no file
+//! behind.
+//!
+//! ```rust, no_run
+//! use meteomatics::APIClient;
+//!
+//! let client = APIClient::builder("ferris_loves_rustaceans", "0123456789")
+//!     .on_download_progress(|progress| match progress.total {
+//!         Some(total) => println!("{}: {} of {} bytes", progress.file_name, progress.received, total),
+//!         None => println!("{}: {} bytes", progress.file_name, progress.received),
+//!     })
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use crate::errors::ConnectorError;
+use reqwest::Response;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Progress of a download that is passed to the progress callback after every chunk.
+#[derive(Clone, Debug)]
+pub struct DownloadProgress {
+    /// The file the response is written to (the URL of the query for downloads into a writer or
+    /// into memory).
+    pub file_name: String,
+    /// Number of bytes received so far.
+    pub received: u64,
+    /// Total number of bytes as announced by the ```Content-Length``` header (if present).
+    pub total: Option<u64>,
+}
+
+/// Wrapper for the progress callback (so that the client can still be cloned and debug-printed).
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(&DownloadProgress) + Send + Sync>);
+
+impl ProgressCallback {
+    /// Wraps the given function.
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: Fn(&DownloadProgress) + Send + Sync + 'static
+    {
+        Self(Arc::new(callback))
+    }
+
+    /// Invokes the callback.
+    pub fn call(&self, progress: &DownloadProgress) {
+        (self.0)(progress)
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ProgressCallback")
+    }
+}
+
+/// The file formats whose content is validated before a download is declared successful.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileFormat {
+    /// PNG images.
+    Png,
+    /// NetCDF files (classic, 64-bit offset, CDF-5 or NetCDF-4/HDF5).
+    NetCdf,
+}
+
+impl FileFormat {
+    /// Checks if the file starts with the signature ("magic bytes") of the format.
+    pub fn matches(&self, head: &[u8]) -> bool {
+        match self {
+            FileFormat::Png => head.starts_with(b"\x89PNG\r\n\x1a\n"),
+            FileFormat::NetCdf => {
+                head.starts_with(b"CDF\x01")
+                    || head.starts_with(b"CDF\x02")
+                    || head.starts_with(b"CDF\x05")
+                    || head.starts_with(b"\x89HDF\r\n\x1a\n")
+            }
+        }
+    }
+}
+
+// Length of the longest signature.
+const SIGNATURE_LENGTH: usize = 8;
+
+/// Streams the body of the HTTP response into the writer and reports the progress after every
+/// chunk. The first bytes are held back until they are validated against the signature of the
+/// format, such that nothing is written for a response with unexpected content. Returns the number
+/// of bytes written.
+///
+/// # Arguments
+///
+/// * `response` - The HTTP response object.
+/// * `writer` - The destination of the body.
+/// * `file_name` - The name of the destination (for the progress and errors).
+/// * `format` - Optional format whose signature the body must start with.
+/// * `progress` - Optional callback for the progress of the download.
+pub async fn stream_to_writer<W>(
+    mut response: Response,
+    writer: &mut W,
+    file_name: &str,
+    format: Option<FileFormat>,
+    progress: Option<&ProgressCallback>,
+) -> Result<u64, ConnectorError>
+where
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    let corrupt = |reason: String| ConnectorError::CorruptDownload(file_name.to_string(), reason);
+    let validate = |head: &[u8]| match format {
+        Some(format) if !format.matches(head) => Err(corrupt(format!("the content is not a valid {:?} file", format))),
+        _ => Ok(()),
+    };
+
+    // Responses rebuilt around a stream (e.g. by the rate limiter) only have the header.
+    let total = response.content_length().or_else(|| {
+        response.headers()
+            .get(reqwest::header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+    });
+    let mut head: Vec<u8> = Vec::with_capacity(SIGNATURE_LENGTH);
+    let mut validated = false;
+    let mut received: u64 = 0;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| corrupt(format!("download interrupted after {} bytes ({})", received, e)))?
+    {
+        received += chunk.len() as u64;
+        if validated {
+            writer.write_all(&chunk).await?;
+        } else {
+            head.extend_from_slice(&chunk);
+            if head.len() >= SIGNATURE_LENGTH {
+                validate(&head)?;
+                writer.write_all(&head).await?;
+                validated = true;
+            }
+        }
+        if let Some(callback) = progress {
+            callback.call(&DownloadProgress { file_name: file_name.to_string(), received, total });
+        }
+    }
+    if !validated {
+        validate(&head)?;
+        writer.write_all(&head).await?;
+    }
+    writer.flush().await?;
+
+    match total {
+        Some(total) if received != total => Err(corrupt(format!("received {} of {} bytes", received, total))),
+        _ => Ok(received),
+    }
+}
+
+/// A file that only appears at its target path once it is committed. The content is written to a
+/// temporary file next to the target, which is removed again if the file is dropped without commit.
+#[derive(Debug)]
+pub struct AtomicFile {
+    file: tokio::fs::File,
+    tmp_path: PathBuf,
+    target: PathBuf,
+    committed: bool,
+}
+
+impl AtomicFile {
+    /// Creates the temporary file for the given target path.
+    pub async fn create(file_name: &str) -> Result<Self, ConnectorError> {
+        let target = PathBuf::from(file_name);
+        let tmp_name = format!(
+            ".{}.{:08x}.part",
+            target.file_name().map(|n| n.to_string_lossy()).unwrap_or_default(),
+            rand::random::<u32>()
+        );
+        let tmp_path = target.with_file_name(tmp_name);
+        let file = tokio::fs::File::create(&tmp_path).await?;
+        Ok(Self { file, tmp_path, target, committed: false })
+    }
+
+    /// Returns the writer for the content.
+    pub fn writer(&mut self) -> &mut tokio::fs::File {
+        &mut self.file
+    }
+
+    /// Moves the file to its target path.
+    pub async fn commit(mut self) -> Result<(), ConnectorError> {
+        self.file.sync_all().await?;
+        tokio::fs::rename(&self.tmp_path, &self.target).await?;
+        self.committed = true;
+        Ok(())
+    }
+
+    /// Discards the content and removes an existing file at the target path, such that no outdated
+    /// file of an earlier download is left behind.
+    pub async fn abort(self) {
+        let _ = tokio::fs::remove_file(&self.target).await;
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = std::fs::remove_file(&self.tmp_path);
+        }
+    }
+}
+
+/// Streams the body of the HTTP response to a file (see [`stream_to_writer`] and [`AtomicFile`]).
+/// On failure no file is left at the target path.
+///
+/// # Arguments
+///
+/// * `response` - The HTTP response object.
+/// * `file_name` - The name for the file to be written (complete with path).
+/// * `format` - Optional format whose signature the body must start with.
+/// * `progress` - Optional callback for the progress of the download.
+pub async fn stream_to_file(
+    response: Response,
+    file_name: &str,
+    format: Option<FileFormat>,
+    progress: Option<&ProgressCallback>,
+) -> Result<(), ConnectorError> {
+    let mut file = AtomicFile::create(file_name).await?;
+    match stream_to_writer(response, file.writer(), file_name, format, progress).await {
+        Ok(_) => file.commit().await,
+        Err(e) => {
+            file.abort().await;
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::download::FileFormat;
+
+    #[tokio::test]
+    async fn file_signatures() {
+        assert!(FileFormat::Png.matches(b"\x89PNG\r\n\x1a\n\x00\x00"));
+        assert!(FileFormat::NetCdf.matches(b"CDF\x01\x00\x00\x00\x00"));
+        assert!(FileFormat::NetCdf.matches(b"\x89HDF\r\n\x1a\n"));
+        assert!(!FileFormat::NetCdf.matches(b"\x89PNG\r\n\x1a\n"));
+        assert!(!FileFormat::Png.matches(b"<html>Service Unavailable</html>"));
+        assert!(!FileFormat::Png.matches(b""));
+    }
+}
@@ -0,0 +1,284 @@
+//! # Ensemble
+//! Ensemble models (e.g. ```ecmwf-ens```) provide many possible outcomes ("members") of the same
+//! forecast. With [`crate::options::QueryOptions::ens_select`] the API returns one column per parameter
+//! and selection, e.g. ```t_2m:C-m1```, ```t_2m:C-mean``` or ```t_2m:C-quantile0.9```. The queries of
+//! the [`crate::APIClient`] turn such responses into a long format (see [`ensemble_to_long`]): one
+//! column per parameter and a [`MEMBER_COLUMN`] or [`QUANTILE_COLUMN`] that tells which member,
+//! statistic or quantile a row belongs to.
+//!
+//! The members can then be summarized locally with [`ensemble_spread`] and
+//! [`exceedance_probability`].
+//!
+//! ```rust, no_run
+//! use chrono::{Duration, Utc};
+//! use meteomatics::{APIClient, Point, TimeSeries};
+//! use meteomatics::ensemble::exceedance_probability;
+//! use meteomatics::options::{EnsembleSelection, QueryOptions};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = APIClient::new("ferris_loves_rustaceans", "0123456789", 10);
+//!     let time_series = TimeSeries {
+//!         start: Utc::now(),
+//!         end: Utc::now() + Duration::days(5),
+//!         timedelta: Option::from(Duration::hours(6))
+//!     };
+//!     let options = QueryOptions::new()
+//!         .model("ecmwf-ens")
+//!         .ens_select(&[EnsembleSelection::Members(1, 50)]);
+//!     let members = client
+//!         .query_time_series(&time_series, &[String::from("t_2m:C")], &[Point { lat: 47.4, lon: 9.4 }], &options)
+//!         .await
+//!         .unwrap();
+//!
+//!     // Probability of more than 25 °C at each point in time.
+//!     let hot = exceedance_probability(&members, "t_2m:C", 25.0).unwrap();
+//!     println!("{:?}", hot);
+//! }
+//! ```
+
+use crate::errors::ConnectorError;
+use crate::options::QueryOptions;
+use crate::split::concat_frames;
+use polars::prelude::*;
+use std::collections::HashMap;
+
+/// Name of the column with the member (e.g. "1") or statistic ("mean" or "median") of a row.
+pub const MEMBER_COLUMN: &str = "member";
+/// Name of the column with the quantile (e.g. 0.9) of a row.
+pub const QUANTILE_COLUMN: &str = "quantile";
+
+/// The member, statistic or quantile that an ensemble column belongs to.
+#[derive(Clone, Debug, PartialEq)]
+enum Selection {
+    Member(String),
+    Quantile(f64),
+}
+
+/// Splits the name of an ensemble column (e.g. ```t_2m:C-m1```) into the parameter and the selection.
+/// Columns of other values (e.g. ```validdate``` or ```t_2m:C```) return ```None```.
+fn split_column_name(name: &str) -> Option<(&str, Selection)> {
+    let (parameter, suffix) = name.rsplit_once('-')?;
+    // Parameters always have a unit (e.g. "t_2m:C"), which keeps key columns out.
+    if !parameter.contains(':') {
+        return None;
+    }
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    let selection = match suffix {
+        "mean" | "median" => Selection::Member(suffix.to_string()),
+        s if is_number(s) => Selection::Member(s.to_string()),
+        s if s.starts_with('m') && is_number(&s[1..]) => Selection::Member(s[1..].to_string()),
+        s => Selection::Quantile(s.strip_prefix("quantile")?.parse().ok()?),
+    };
+    Some((parameter, selection))
+}
+
+/// Checks if a column holds the values of a parameter, including its ensemble columns (e.g.
+/// ```t_2m:C-m1``` for ```t_2m:C```).
+pub(crate) fn is_parameter_column(name: &str, parameter: &str) -> bool {
+    name == parameter || split_column_name(name).is_some_and(|(p, _)| p == parameter)
+}
+
+/// Turns a response with ensemble columns (e.g. ```t_2m:C-m1```, ```t_2m:C-m2```,
+/// ```t_2m:C-quantile0.9```) into a long format: the key columns (e.g. ```lat```, ```lon``` and
+/// ```validdate```), the [`MEMBER_COLUMN`] (Utf8, e.g. "1", "mean" or "median"), the
+/// [`QUANTILE_COLUMN`] (f64) and one column per parameter. A row either has a member or a quantile,
+/// the other column is null. The rows of each selection follow each other in the order of the
+/// columns in the response. Frames without ensemble columns are returned unchanged.
+///
+/// # Arguments
+///
+/// * `df` - The response with one column per parameter and selection.
+pub fn ensemble_to_long(df: DataFrame) -> std::result::Result<DataFrame, ConnectorError> {
+    let mut keys: Vec<&Series> = Vec::new();
+    let mut parameters: Vec<&str> = Vec::new();
+    let mut selections: Vec<(Selection, Vec<(&str, &Series)>)> = Vec::new();
+    for series in df.get_columns() {
+        match split_column_name(series.name()) {
+            None => keys.push(series),
+            Some((parameter, selection)) => {
+                if !parameters.contains(&parameter) {
+                    parameters.push(parameter);
+                }
+                match selections.iter_mut().find(|(s, _)| *s == selection) {
+                    Some((_, columns)) => columns.push((parameter, series)),
+                    None => selections.push((selection, vec![(parameter, series)])),
+                }
+            }
+        }
+    }
+    if selections.is_empty() {
+        return Ok(df);
+    }
+
+    let height = df.height();
+    let mut frames = Vec::with_capacity(selections.len());
+    for (selection, columns) in &selections {
+        let mut frame: Vec<Series> = keys.iter().map(|s| (*s).clone()).collect();
+        let (member, quantile) = match selection {
+            Selection::Member(member) => (Some(member.as_str()), None),
+            Selection::Quantile(quantile) => (None, Some(*quantile)),
+        };
+        frame.push(Series::new(MEMBER_COLUMN, vec![member; height]));
+        frame.push(Series::new(QUANTILE_COLUMN, vec![quantile; height]));
+        for parameter in &parameters {
+            // The members of a parameter may be inferred as different types (e.g. i64 and f64).
+            let mut values = match columns.iter().find(|(p, _)| p == parameter) {
+                Some((_, series)) => series
+                    .cast(&DataType::Float64)
+                    .map_err(|e| ConnectorError::PolarsError(e.to_string()))?,
+                None => Series::full_null(parameter, height, &DataType::Float64),
+            };
+            values.rename(parameter);
+            frame.push(values);
+        }
+        frames.push(DataFrame::new(frame).map_err(|e| ConnectorError::PolarsError(e.to_string()))?);
+    }
+    concat_frames(frames)
+}
+
+/// Reshapes the result of a query if the options select ensemble members (see [`ensemble_to_long`]).
+pub(crate) fn reshape_ensemble(
+    df: DataFrame,
+    options: &QueryOptions,
+) -> std::result::Result<DataFrame, ConnectorError> {
+    match options.lookup("ens_select") {
+        Some(_) => ensemble_to_long(df),
+        None => Ok(df),
+    }
+}
+
+/// Groups the values of the numbered members (not the statistics or quantiles) by the key columns
+/// (all columns before the [`MEMBER_COLUMN`]). Returns the key columns of each group and its values.
+fn member_values(
+    df: &DataFrame,
+    parameter: &str,
+) -> std::result::Result<(DataFrame, Vec<Vec<f64>>), ConnectorError> {
+    let polars_error = |e: PolarsError| ConnectorError::PolarsError(e.to_string());
+    let names = df.get_column_names();
+    let n_keys = names.iter().position(|name| *name == MEMBER_COLUMN).ok_or_else(|| {
+        ConnectorError::LibraryError(String::from("No member column (see ensemble::ensemble_to_long)"))
+    })?;
+    let keys: Vec<&Series> = df.get_columns()[..n_keys].iter().collect();
+    let members = df.column(MEMBER_COLUMN).and_then(|s| s.utf8().cloned()).map_err(polars_error)?;
+    let values = df.column(parameter).and_then(|s| s.cast(&DataType::Float64)).map_err(polars_error)?;
+    let values = values.f64().map_err(polars_error)?;
+
+    let mut groups: HashMap<String, usize> = HashMap::new();
+    let mut first_rows: Vec<IdxSize> = Vec::new();
+    let mut group_values: Vec<Vec<f64>> = Vec::new();
+    for (row, member) in members.into_iter().enumerate() {
+        // Only numbered members, not "mean", "median" or quantiles.
+        if member.and_then(|m| m.parse::<u32>().ok()).is_none() {
+            continue;
+        }
+        let key = keys.iter().map(|s| s.get(row).to_string()).collect::<Vec<String>>().join(";");
+        let group = *groups.entry(key).or_insert_with(|| {
+            first_rows.push(row as IdxSize);
+            group_values.push(Vec::new());
+            group_values.len() - 1
+        });
+        if let Some(value) = values.get(row).filter(|v| !v.is_nan()) {
+            group_values[group].push(value);
+        }
+    }
+    let keys = df
+        .select(&names[..n_keys])
+        .and_then(|keys| keys.take(&IdxCa::from_vec("", first_rows)))
+        .map_err(polars_error)?;
+    Ok((keys, group_values))
+}
+
+/// Summarizes the members of a parameter (in the long format of [`ensemble_to_long`]) per location
+/// and point in time. Returns the key columns and the columns ```{parameter}_mean```,
+/// ```{parameter}_std``` (standard deviation of the members, the spread), ```{parameter}_min``` and
+/// ```{parameter}_max```. Statistics and quantiles of the API are not taken into account.
+///
+/// # Arguments
+///
+/// * `df` - The members in long format.
+/// * `parameter` - The name of the parameter column (e.g. "t_2m:C").
+pub fn ensemble_spread(df: &DataFrame, parameter: &str) -> std::result::Result<DataFrame, ConnectorError> {
+    let (keys, groups) = member_values(df, parameter)?;
+    let statistic = |f: &dyn Fn(&[f64]) -> f64| -> Vec<Option<f64>> {
+        groups.iter().map(|v| (!v.is_empty()).then(|| f(v))).collect()
+    };
+    let mean = |v: &[f64]| v.iter().sum::<f64>() / v.len() as f64;
+    let std = |v: &[f64]| {
+        let m = mean(v);
+        (v.iter().map(|x| (x - m).powi(2)).sum::<f64>() / v.len() as f64).sqrt()
+    };
+    let columns = [
+        Series::new(&format!("{}_mean", parameter), statistic(&mean)),
+        Series::new(&format!("{}_std", parameter), statistic(&std)),
+        Series::new(&format!("{}_min", parameter), statistic(&|v| v.iter().cloned().fold(f64::INFINITY, f64::min))),
+        Series::new(&format!("{}_max", parameter), statistic(&|v| v.iter().cloned().fold(f64::NEG_INFINITY, f64::max))),
+    ];
+    keys.hstack(&columns).map_err(|e| ConnectorError::PolarsError(e.to_string()))
+}
+
+/// Computes the share of members (between 0 and 1) whose value of a parameter exceeds a threshold per
+/// location and point in time. Returns the key columns and the column ```{parameter}_exceedance```.
+///
+/// # Arguments
+///
+/// * `df` - The members in the long format of [`ensemble_to_long`].
+/// * `parameter` - The name of the parameter column (e.g. "precip_24h:mm").
+/// * `threshold` - Values above the threshold count as exceedance.
+pub fn exceedance_probability(
+    df: &DataFrame,
+    parameter: &str,
+    threshold: f64,
+) -> std::result::Result<DataFrame, ConnectorError> {
+    let (keys, groups) = member_values(df, parameter)?;
+    let probabilities: Vec<Option<f64>> = groups
+        .iter()
+        .map(|v| (!v.is_empty()).then(|| v.iter().filter(|x| **x > threshold).count() as f64 / v.len() as f64))
+        .collect();
+    let column = Series::new(&format!("{}_exceedance", parameter), probabilities);
+    keys.hstack(&[column]).map_err(|e| ConnectorError::PolarsError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::ensemble::{ensemble_spread, ensemble_to_long, exceedance_probability, is_parameter_column};
+    use polars::prelude::*;
+
+    #[tokio::test]
+    async fn members_are_turned_into_rows_and_summarized() {
+        let df = df!(
+            "lat" => &[47.0, 47.0],
+            "lon" => &[9.0, 9.0],
+            "validdate" => &["t1", "t2"],
+            "t_2m:C-m1" => &[10.0, 20.0],
+            "t_2m:C-m2" => &[14.0, 22.0],
+            "precip_1h:mm-m1" => &[0.0, 1.0],
+            "precip_1h:mm-m2" => &[2.0, 3.0],
+            "t_2m:C-quantile0.9" => &[13.6, 21.8]
+        ).unwrap();
+        let long = ensemble_to_long(df).unwrap();
+        assert_eq!(
+            long.get_column_names(),
+            &["lat", "lon", "validdate", "member", "quantile", "t_2m:C", "precip_1h:mm"]
+        );
+        assert_eq!(long.height(), 6);
+        let members: Vec<Option<&str>> = long.column("member").unwrap().utf8().unwrap().into_iter().collect();
+        assert_eq!(members, vec![Some("1"), Some("1"), Some("2"), Some("2"), None, None]);
+        let quantiles: Vec<Option<f64>> = long.column("quantile").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(quantiles[4], Some(0.9));
+        assert_eq!(long.column("precip_1h:mm").unwrap().null_count(), 2);
+
+        let spread = ensemble_spread(&long, "t_2m:C").unwrap();
+        assert_eq!(spread.get_column_names(), &["lat", "lon", "validdate", "t_2m:C_mean", "t_2m:C_std", "t_2m:C_min", "t_2m:C_max"]);
+        let std: Vec<f64> = spread.column("t_2m:C_std").unwrap().f64().unwrap().into_no_null_iter().collect();
+        assert_eq!(std, vec![2.0, 1.0]);
+
+        let exceedance = exceedance_probability(&long, "t_2m:C", 12.0).unwrap();
+        let p: Vec<f64> = exceedance.column("t_2m:C_exceedance").unwrap().f64().unwrap().into_no_null_iter().collect();
+        assert_eq!(p, vec![0.5, 1.0]);
+
+        assert!(is_parameter_column("t_2m:C-median", "t_2m:C"));
+        assert!(!is_parameter_column("t_2m:C-m1", "t_2m:F"));
+    }
+}
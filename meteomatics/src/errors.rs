@@ -1,15 +1,71 @@
+use reqwest::StatusCode;
+use std::fmt;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ConnectorError {
     /// ReqwestError.
     #[error("ReqwestError error: {0}")]
-    ReqwestError(String),
+    ReqwestError(#[from] reqwest::Error),
 
-    /// HTTP response error.
+    /// HTTP response error that does not match any of the more specific API errors.
     #[error("HTTP error: `{0}`, `{1}`, {2}`")]
     HttpError(String, String, reqwest::StatusCode),
 
+    /// The API does not know the parameter or it is not available for the query.
+    #[error("Invalid parameter: {0}")]
+    InvalidParameter(ApiError),
+
+    /// The API does not know the model (e.g. ```model=mix```) or it is not available for the query.
+    #[error("Unknown model: {0}")]
+    UnknownModel(ApiError),
+
+    /// The account has no access to data of the requested period.
+    #[error("No access to historic data: {0}")]
+    NoHistoricAccess(ApiError),
+
+    /// The account has no access to data of the requested area.
+    #[error("No access to area: {0}")]
+    NoAreaAccess(ApiError),
+
+    /// The request limit of the account is exceeded.
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(ApiError),
+
+    /// The username or password is wrong.
+    #[error("Authentication failed: {0}")]
+    AuthenticationFailed(ApiError),
+
+    /// The request exceeds the size limits of the API (e.g. too many parameters or grid points).
+    #[error("Request too large: {0}")]
+    RequestTooLarge(ApiError),
+
+    /// No credentials were found (see [`crate::credentials`]).
+    #[error("Missing credentials: {0}")]
+    MissingCredentials(String),
+
+    /// An optional parameter of the query is invalid (see [`crate::options::QueryOptions`]).
+    #[error("Invalid option: {0}")]
+    InvalidOption(String),
+
+    /// A parameter does not follow the naming rules of the API (see [`crate::parameter::Parameter`]).
+    #[error("Malformed parameter: {0}")]
+    MalformedParameter(String),
+
+    /// The parameter is not in the parameter catalog or its unit, level, aggregation or interval is
+    /// not supported (see [`crate::catalog`]).
+    #[error("Unknown parameter: {0}")]
+    UnknownParameter(String),
+
+    /// There is no conversion between the units (see [`crate::units`]).
+    #[error("Cannot convert unit `{0}` to `{1}`")]
+    UnsupportedConversion(String, String),
+
+    /// The dates of a query are outside the time range for which the model has data (see
+    /// [`crate::APIClientBuilder::time_range_check`]).
+    #[error("Unavailable time range: {0}")]
+    UnavailableTimeRange(String),
+
     /// Library error.
     #[error("Library error: `{0}`")]
     LibraryError(String),
@@ -20,26 +76,227 @@ pub enum ConnectorError {
 
     /// Generic error.
     #[error(transparent)]
-    GenericError(#[from] Box<dyn std::error::Error>),
+    GenericError(#[from] Box<dyn std::error::Error + Send + Sync>),
 
     /// Parse error.
-    #[error("Parsing error")]
-    ParseError,
+    #[error("Parsing error: {0}")]
+    ParseError(#[from] url::ParseError),
 
     /// File i/o error
-    #[error("File i/o error")]
-    FileIOError
+    #[error("File i/o error: {0}")]
+    FileIOError(#[from] std::io::Error),
+
+    /// The query was cancelled before it finished.
+    #[error("Query cancelled")]
+    Cancelled,
+
+    /// The client is offline and the query is not in the cache.
+    #[error("No cached response for `{0}` (offline mode)")]
+    CacheMiss(String),
+
+    /// The client replays a cassette that does not contain the query.
+    #[error("No recorded response for `{0}` (replay mode)")]
+    ReplayMiss(String),
+
+    /// A downloaded file is incomplete or does not have the expected format.
+    #[error("Corrupt download `{0}`: {1}")]
+    CorruptDownload(String, String)
 }
 
+impl ConnectorError {
+    /// Returns the HTTP status of errors that were returned by the API.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ConnectorError::HttpError(_, _, status) => Some(*status),
+            ConnectorError::InvalidParameter(e)
+            | ConnectorError::UnknownModel(e)
+            | ConnectorError::NoHistoricAccess(e)
+            | ConnectorError::NoAreaAccess(e)
+            | ConnectorError::QuotaExceeded(e)
+            | ConnectorError::AuthenticationFailed(e)
+            | ConnectorError::RequestTooLarge(e) => Some(e.status),
+            ConnectorError::ReqwestError(e) => e.status(),
+            _ => None,
+        }
+    }
+}
 
-impl From<url::ParseError> for ConnectorError {
-    fn from(_: url::ParseError) -> Self {
-        ConnectorError::ParseError
+/// An error response of the API.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApiError {
+    /// The HTTP status of the response.
+    pub status: StatusCode,
+    /// The error message of the API (without markup).
+    pub message: String,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.status)
+    }
+}
+
+// Words of the API for a subject (parameter, model or area) that is unknown or not accessible.
+const REJECTED: [&str; 10] = [
+    "unknown", "not found", "not available", "invalid", "not supported", "does not exist", "access", "allowed",
+    "permit", "restricted",
+];
+
+// Phrases of the API that name the requested area as the subject of a message.
+const AREA_SUBJECTS: [&str; 7] = [
+    "requested area", "this area", "the area", "area request", "requested region", "this region", "the region",
+];
+
+/// Converts an error response of the API into the matching [`ConnectorError`]. The message is taken
+/// from JSON (```{"message": "..."}```), HTML or plain text bodies. Responses that do not match any of
+/// the known failures become a [`ConnectorError::HttpError`].
+///
+/// Messages often name several subjects (e.g. "Parameter t_2m:X not available for model
+/// ecmwf-ifs"). The most specific subject wins: a parameter before a model, a model before the
+/// period or the area of the query.
+///
+/// # Arguments
+///
+/// * `status` - The HTTP status of the response.
+/// * `body` - The body of the response.
+pub fn parse_api_error(status: StatusCode, body: &str) -> ConnectorError {
+    let message = error_message(body);
+    let text = message.to_lowercase();
+    let has = |words: &[&str]| words.iter().any(|word| text.contains(word));
+
+    let variant: fn(ApiError) -> ConnectorError = if status == StatusCode::UNAUTHORIZED
+        || has(&["authentication failed", "invalid credentials", "wrong username", "wrong password"])
+    {
+        ConnectorError::AuthenticationFailed
+    } else if status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::PAYMENT_REQUIRED
+        || has(&["quota", "limit exceeded", "limit reached", "limit is exceeded", "limit is reached"])
+    {
+        ConnectorError::QuotaExceeded
+    } else if status == StatusCode::PAYLOAD_TOO_LARGE
+        || status == StatusCode::URI_TOO_LONG
+        || has(&["too large", "too long", "too many", "exceeds the maximum", "maximum number"])
+    {
+        ConnectorError::RequestTooLarge
+    } else if text.contains("parameter") && has(&REJECTED) {
+        ConnectorError::InvalidParameter
+    } else if text.contains("model") && has(&REJECTED) {
+        ConnectorError::UnknownModel
+    } else if text.contains("historic") {
+        ConnectorError::NoHistoricAccess
+    } else if has(&AREA_SUBJECTS) && has(&["access", "allowed", "permit", "restricted"]) {
+        ConnectorError::NoAreaAccess
+    } else if text.contains("parameter") {
+        ConnectorError::InvalidParameter
+    } else {
+        return ConnectorError::HttpError(status.to_string(), body.to_string(), status);
+    };
+    variant(ApiError { status, message })
+}
+
+// Extracts the message from the body of an error response.
+fn error_message(body: &str) -> String {
+    if let Ok(serde_json::Value::Object(json)) = serde_json::from_str::<serde_json::Value>(body) {
+        for key in ["message", "error message", "error"] {
+            if let Some(serde_json::Value::String(message)) = json.get(key) {
+                return message.trim().to_string();
+            }
+        }
+    }
+    // Drop the markup (and the title of the page) of HTML bodies.
+    let body = match (body.find("<title>"), body.find("</title>")) {
+        (Some(start), Some(end)) if start < end => format!("{}{}", &body[..start], &body[end + 8..]),
+        _ => body.to_string(),
+    };
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in body.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => (),
+        }
     }
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
 }
 
-impl From<std::io::Error> for ConnectorError {
-    fn from(_: std::io::Error) -> Self {
-        ConnectorError::FileIOError
+#[cfg(test)]
+mod tests {
+
+    use crate::errors::{parse_api_error, ApiError, ConnectorError};
+    use reqwest::StatusCode;
+
+    #[tokio::test]
+    async fn api_errors_are_parsed() {
+        let html = "<html><head><title>Meteomatics API</title></head>\n<body><h3>Bad Request</h3>\n\
+            <p>Parameter t_2m:X not found.</p></body></html>";
+        match parse_api_error(StatusCode::BAD_REQUEST, html) {
+            ConnectorError::InvalidParameter(e) => assert_eq!(e, ApiError {
+                status: StatusCode::BAD_REQUEST,
+                message: String::from("Bad Request Parameter t_2m:X not found."),
+            }),
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let json = r#"{"status": "Error", "message": "Model ecmwf-xyz is unknown."}"#;
+        assert!(matches!(parse_api_error(StatusCode::BAD_REQUEST, json), ConnectorError::UnknownModel(_)));
+        assert!(matches!(
+            parse_api_error(StatusCode::FORBIDDEN, "No access to historic data before 2020-01-01."),
+            ConnectorError::NoHistoricAccess(_)
+        ));
+        assert!(matches!(
+            parse_api_error(StatusCode::FORBIDDEN, "Your account has no access to the requested area."),
+            ConnectorError::NoAreaAccess(_)
+        ));
+        assert!(matches!(
+            parse_api_error(StatusCode::FORBIDDEN, "Request limit exceeded."),
+            ConnectorError::QuotaExceeded(_)
+        ));
+        assert!(matches!(
+            parse_api_error(StatusCode::UNAUTHORIZED, "Unauthorized"),
+            ConnectorError::AuthenticationFailed(_)
+        ));
+        assert!(matches!(
+            parse_api_error(StatusCode::BAD_REQUEST, "The request contains too many grid points."),
+            ConnectorError::RequestTooLarge(_)
+        ));
+
+        let error = parse_api_error(StatusCode::SERVICE_UNAVAILABLE, "Maintenance");
+        assert!(matches!(error, ConnectorError::HttpError(_, ref body, _) if body == "Maintenance"));
+        assert_eq!(error.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+    }
+
+    #[tokio::test]
+    async fn most_specific_subject_wins() {
+        let error = |status: StatusCode, message: &str| parse_api_error(status, message);
+        assert!(matches!(
+            error(StatusCode::BAD_REQUEST, "Parameter t_2m:X not available for model ecmwf-ifs"),
+            ConnectorError::InvalidParameter(_)
+        ));
+        assert!(matches!(
+            error(StatusCode::FORBIDDEN, "No access to parameter lightning_strikes in the requested area."),
+            ConnectorError::InvalidParameter(_)
+        ));
+        assert!(matches!(
+            error(StatusCode::BAD_REQUEST, "Model mm-euro1k is not available for the requested area."),
+            ConnectorError::UnknownModel(_)
+        ));
+        assert!(matches!(
+            error(StatusCode::FORBIDDEN, "Access to historic data of the requested area is restricted."),
+            ConnectorError::NoHistoricAccess(_)
+        ));
+        assert!(matches!(
+            error(StatusCode::FORBIDDEN, "Area requests are not allowed for your account."),
+            ConnectorError::NoAreaAccess(_)
+        ));
+        // Neither the area nor the region is the subject of the message.
+        assert!(matches!(
+            error(StatusCode::FORBIDDEN, "Access denied for requests from your region."),
+            ConnectorError::HttpError(_, _, _)
+        ));
     }
-}
\ No newline at end of file
+}
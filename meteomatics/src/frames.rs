@@ -0,0 +1,187 @@
+//! # Frames
+//! A series of PNG images ("frames") of a grid, one per point in time of a [`crate::TimeSeries`] (see
+//! [`crate::APIClient::query_grid_png_frames`]). The frames are fetched with a bounded number of
+//! requests in flight and written to file names that are created from a template. Frames that were
+//! already downloaded by an earlier run can be skipped, such that an interrupted download of a long
+//! series is resumed instead of restarted.
+//!
+//! The result is a [`FrameManifest`] that maps each point in time to its file or to the error of the
+//! frame. A failed frame does not affect the others.
+//!
+//! ```rust, no_run
+//! use chrono::{Duration, Utc};
+//! use meteomatics::{APIClient, BBox, TimeSeries};
+//! use meteomatics::frames::FrameOptions;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = APIClient::new("ferris_loves_rustaceans", "0123456789", 10);
+//!     let time_series = TimeSeries {
+//!         start: Utc::now(),
+//!         end: Utc::now() + Duration::days(2),
+//!         timedelta: Option::from(Duration::hours(1))
+//!     };
+//!     let bbox = BBox {
+//!         lat_min: 45.8179716,
+//!         lat_max: 47.8084648,
+//!         lon_min: 5.9559113,
+//!         lon_max: 10.4922941,
+//!         lat_res: 0.01,
+//!         lon_res: 0.01
+//!     };
+//!
+//!     let options = FrameOptions {
+//!         template: String::from("{prefix}/%Y/%m/%d/%H%M.png"),
+//!         skip_existing: true,
+//!         ..FrameOptions::default()
+//!     };
+//!     let manifest = client
+//!         .query_grid_png_frames(&time_series, &String::from("t_2m:C"), &bbox, "frames/t_2m", &None, &options)
+//!         .await
+//!         .unwrap();
+//!     for frame in manifest.failed() {
+//!         println!("{}: {}", frame.date, frame.outcome.as_ref().unwrap_err());
+//!     }
+//! }
+//! ```
+
+use crate::download::FileFormat;
+use crate::errors::ConnectorError;
+use chrono::format::{Item, StrftimeItems};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+/// Default template of the file names, which corresponds to the file names of
+/// [`crate::APIClient::query_grid_png_timeseries`].
+pub const DEFAULT_TEMPLATE: &str = "{prefix}_%Y%m%d_%H%M%S.png";
+
+/// Options for [`crate::APIClient::query_grid_png_frames`].
+#[derive(Clone, Debug)]
+pub struct FrameOptions {
+    /// Maximum number of frames in flight at the same time.
+    pub concurrency: usize,
+    /// Template of the file names. The placeholder ```{prefix}``` is replaced by the prefix path of
+    /// the query, the remainder is formatted with the point in time of the frame (see
+    /// [`chrono::format::strftime`]). Intermediate directories will be created.
+    pub template: String,
+    /// Keep existing (valid) PNG files instead of downloading them again.
+    pub skip_existing: bool,
+}
+
+impl Default for FrameOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            template: String::from(DEFAULT_TEMPLATE),
+            skip_existing: false,
+        }
+    }
+}
+
+/// How a frame was obtained.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameStatus {
+    /// The frame was downloaded.
+    Downloaded,
+    /// The file already existed and was kept (see [`FrameOptions::skip_existing`]).
+    Skipped,
+}
+
+/// A single frame of the series.
+#[derive(Debug)]
+pub struct Frame {
+    /// The point in time of the frame.
+    pub date: chrono::DateTime<chrono::Utc>,
+    /// The file of the frame.
+    pub path: PathBuf,
+    /// Whether the frame was downloaded or skipped, or why it failed.
+    pub outcome: Result<FrameStatus, ConnectorError>,
+}
+
+/// The frames of a series in chronological order.
+#[derive(Debug, Default)]
+pub struct FrameManifest {
+    /// All frames of the series.
+    pub frames: Vec<Frame>,
+}
+
+impl FrameManifest {
+    /// Returns the frames that are available on disk.
+    pub fn succeeded(&self) -> impl Iterator<Item = &Frame> {
+        self.frames.iter().filter(|frame| frame.outcome.is_ok())
+    }
+
+    /// Returns the frames that failed.
+    pub fn failed(&self) -> impl Iterator<Item = &Frame> {
+        self.frames.iter().filter(|frame| frame.outcome.is_err())
+    }
+
+    /// Checks if all frames are available on disk.
+    pub fn is_complete(&self) -> bool {
+        self.failed().next().is_none()
+    }
+
+    /// Returns the file of the frame at the given point in time (if it is available).
+    pub fn path(&self, date: &chrono::DateTime<chrono::Utc>) -> Option<&Path> {
+        self.succeeded()
+            .find(|frame| &frame.date == date)
+            .map(|frame| frame.path.as_path())
+    }
+}
+
+/// Creates the file name of a frame from the template. Fails for templates with invalid format
+/// specifiers.
+///
+/// # Arguments
+///
+/// * `template` - The template of the file names (see [`FrameOptions::template`]).
+/// * `prefix` - The prefix path that replaces the ```{prefix}``` placeholder.
+/// * `date` - The point in time of the frame.
+pub fn frame_file_name(
+    template: &str,
+    prefix: &str,
+    date: &chrono::DateTime<chrono::Utc>,
+) -> Result<String, ConnectorError> {
+    // Escape the prefix, such that a '%' in a path is not mistaken for a format specifier.
+    let template = template.replace("{prefix}", &prefix.replace('%', "%%"));
+    let items: Vec<Item> = StrftimeItems::new(&template).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(ConnectorError::LibraryError(format!("Invalid file name template: {}", template)));
+    }
+    Ok(date.format_with_items(items.into_iter()).to_string())
+}
+
+/// Checks if a file exists and starts with the PNG signature. Incomplete downloads never end up at
+/// their target path (see [`crate::download::AtomicFile`]), so this is sufficient to skip a frame.
+pub(crate) async fn is_complete_png(path: &Path) -> bool {
+    let mut head = [0u8; 8];
+    match tokio::fs::File::open(path).await {
+        Ok(mut file) => file.read_exact(&mut head).await.is_ok() && FileFormat::Png.matches(&head),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::errors::ConnectorError;
+    use crate::frames::{frame_file_name, DEFAULT_TEMPLATE};
+    use chrono::{TimeZone, Utc};
+
+    #[tokio::test]
+    async fn file_names_are_created_from_the_template() {
+        let date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
+        assert_eq!(
+            frame_file_name(DEFAULT_TEMPLATE, "tests/png/t_2m", &date).unwrap(),
+            "tests/png/t_2m_19891109_180000.png"
+        );
+        assert_eq!(
+            frame_file_name("{prefix}/%Y/%j/%H%M.png", "100%/t_2m", &date).unwrap(),
+            "100%/t_2m/1989/313/1800.png"
+        );
+        assert!(matches!(
+            frame_file_name("{prefix}_%Q.png", "t_2m", &date),
+            Err(ConnectorError::LibraryError(_))
+        ));
+    }
+}
@@ -107,12 +107,46 @@
 //! ```
 
 pub mod errors;
+pub mod auth;
+pub mod availability;
+pub mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cache;
+pub mod cassette;
+pub mod catalog;
 pub mod client;
+pub mod comparison;
+pub mod credentials;
+pub mod download;
+pub mod ensemble;
+pub mod frames;
 pub mod location;
+pub mod options;
+pub mod parameter;
+pub mod ratelimit;
+pub mod retry;
+pub mod split;
+pub mod tiling;
+pub mod units;
+#[cfg(any(test, feature = "test-support"))]
+pub mod testing;
 pub mod util;
 pub use client::APIClient;
+pub use client::APIClientBuilder;
+#[cfg(feature = "blocking")]
+pub use blocking::BlockingAPIClient;
+pub use cache::{CacheConfig, CacheMode, CacheTtl};
+pub use cassette::{CassetteConfig, CassetteMode};
+pub use credentials::{CredentialProvider, Credentials};
+pub use ratelimit::RateLimitConfig;
+pub use retry::RetryPolicy;
+pub use split::SplitLimits;
+pub use tiling::TileLimits;
 pub use location::Point;
 pub use location::BBox;
+pub use options::QueryOptions;
+pub use parameter::Parameter;
 pub use util::TimeSeries;
 pub use chrono::{Duration, DateTime, Local, Utc};
 pub use polars::frame::DataFrame;
\ No newline at end of file
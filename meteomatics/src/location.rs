@@ -29,6 +29,7 @@ use std::fmt;
 
 /// Define a location using its latitude and longitude coordinates. This is used in the generation of 
 /// the query in ```query_time_series()```.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Point {
     pub lat: f64,
     pub lon: f64,
@@ -37,6 +38,7 @@ pub struct Point {
 /// Define an area of interest by specifying a bounding box with coordinates at the upper left (lat_max, 
 /// lon_min) and lower right locations (lat_min, lon_max). This is used in the generation of the query
 /// in ```query_grid()``` and ```query_grid_time_series()```. 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct BBox {
     pub lat_min: f64,
     pub lat_max: f64,
@@ -0,0 +1,353 @@
+//! # Options
+//! Optional parameters of a query (e.g. the model or the calibration). The query methods of the
+//! [`crate::APIClient`] accept any type that implements [`ToQueryOptions`]:
+//!
+//! * [`QueryOptions`] - A typed builder that checks the values and URL-encodes them.
+//! * ```Option<Vec<String>>``` - Raw options (e.g. ```"calibrated=true"```) that are appended to the
+//!   query verbatim, such that typos only fail at the API.
+//!
+//! ```rust, no_run
+//! use chrono::{Duration, Utc};
+//! use meteomatics::{APIClient, Point, TimeSeries};
+//! use meteomatics::options::{EnsembleSelection, Mask, QueryOptions};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = APIClient::new("ferris_loves_rustaceans", "0123456789", 10);
+//!     let time_series = TimeSeries {
+//!         start: Utc::now(),
+//!         end: Utc::now() + Duration::days(1),
+//!         timedelta: Option::from(Duration::hours(1))
+//!     };
+//!     let options = QueryOptions::new()
+//!         .model("ecmwf-ens")
+//!         .ens_select(&[EnsembleSelection::Median, EnsembleSelection::Quantile(0.9)])
+//!         .mask(Mask::Land)
+//!         .raw("custom_option", "42");
+//!
+//!     let df = client
+//!         .query_time_series(&time_series, &[String::from("t_2m:C")], &[Point { lat: 47.4, lon: 9.4 }], &options)
+//!         .await
+//!         .unwrap();
+//!     println!("{:?}", df);
+//! }
+//! ```
+
+use crate::errors::ConnectorError;
+use std::fmt;
+
+/// Types that can be turned into the optional parameters of a query.
+pub trait ToQueryOptions {
+    /// Returns the checked options.
+    fn to_query_options(&self) -> Result<QueryOptions, ConnectorError>;
+}
+
+impl ToQueryOptions for QueryOptions {
+    fn to_query_options(&self) -> Result<QueryOptions, ConnectorError> {
+        match self.errors.is_empty() {
+            true => Ok(self.clone()),
+            false => {
+                let errors: Vec<&str> = self.errors.iter().map(|(_, error)| error.as_str()).collect();
+                Err(ConnectorError::InvalidOption(errors.join("; ")))
+            }
+        }
+    }
+}
+
+impl ToQueryOptions for Option<Vec<String>> {
+    fn to_query_options(&self) -> Result<QueryOptions, ConnectorError> {
+        let mut options = QueryOptions::new();
+        for option in self.iter().flatten() {
+            options.options.push(QueryOption::Verbatim(option.clone()));
+        }
+        Ok(options)
+    }
+}
+
+/// Selection of ensemble members or statistics of an ensemble model (```ens_select```).
+#[derive(Clone, Debug, PartialEq)]
+pub enum EnsembleSelection {
+    /// A single member (0 is the control run).
+    Member(u32),
+    /// A range of members (both ends included).
+    Members(u32, u32),
+    /// The median of all members.
+    Median,
+    /// The mean of all members.
+    Mean,
+    /// A quantile between 0 and 1 (e.g. 0.9).
+    Quantile(f64),
+}
+
+impl fmt::Display for EnsembleSelection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EnsembleSelection::Member(member) => write!(f, "member:{}", member),
+            EnsembleSelection::Members(first, last) => write!(f, "member:{}-{}", first, last),
+            EnsembleSelection::Median => write!(f, "median"),
+            EnsembleSelection::Mean => write!(f, "mean"),
+            EnsembleSelection::Quantile(quantile) => write!(f, "quantile{}", quantile),
+        }
+    }
+}
+
+/// Restricts grid values to land or sea (```mask```).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mask {
+    /// Only values over land.
+    Land,
+    /// Only values over sea.
+    Sea,
+}
+
+/// How values between the time steps of a model are derived (```temporal_interpolation```).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TemporalInterpolation {
+    /// The best method for the parameter (default of the API).
+    Best,
+    /// Linear interpolation.
+    Linear,
+    /// No interpolation, points in time without model data are invalid.
+    None,
+}
+
+/// How the API handles invalid values (```on_invalid```).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnInvalid {
+    /// The query fails.
+    Fail,
+    /// Invalid values are returned as -999.
+    FillWithInvalid,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum QueryOption {
+    Pair(String, String),
+    Verbatim(String),
+}
+
+/// Builder for the optional parameters of a query. Setting an option twice replaces the first
+/// value. Invalid values are reported by the query (see [`ConnectorError::InvalidOption`]).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QueryOptions {
+    options: Vec<QueryOption>,
+    /// Validation errors by option name, replaced together with the value.
+    errors: Vec<(String, String)>,
+}
+
+impl QueryOptions {
+    /// Creates options without any parameter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selects the model or data source (```model```, e.g. "mix" or "ecmwf-ifs").
+    pub fn model(self, model: &str) -> Self {
+        let error = model.trim().is_empty().then(|| String::from("model must not be empty"));
+        self.set("model", model, error)
+    }
+
+    /// Requests values that are calibrated with station measurements (```calibrated```).
+    pub fn calibrated(self, calibrated: bool) -> Self {
+        self.set("calibrated", &calibrated.to_string(), None)
+    }
+
+    /// Selects members or statistics of an ensemble model (```ens_select```).
+    pub fn ens_select(self, selection: &[EnsembleSelection]) -> Self {
+        let error = match selection {
+            [] => Some(String::from("ens_select requires at least one selection")),
+            _ => selection.iter().find_map(|s| match s {
+                EnsembleSelection::Quantile(q) if !(0.0..=1.0).contains(q) => {
+                    Some(format!("ens_select quantile {} is not between 0 and 1", q))
+                }
+                EnsembleSelection::Members(first, last) if first > last => {
+                    Some(format!("ens_select member range {}-{} is empty", first, last))
+                }
+                _ => None,
+            }),
+        };
+        let value = selection.iter().map(|s| s.to_string()).collect::<Vec<String>>().join(",");
+        self.set("ens_select", &value, error)
+    }
+
+    /// Selects clusters of an ensemble model (```cluster_select```, e.g. ```cluster:1```).
+    pub fn cluster_select(self, clusters: &[u32]) -> Self {
+        let error = clusters.is_empty().then(|| String::from("cluster_select requires at least one cluster"));
+        let value = clusters.iter().map(|c| format!("cluster:{}", c)).collect::<Vec<String>>().join(",");
+        self.set("cluster_select", &value, error)
+    }
+
+    /// Selects the interval of parameters that are available for several intervals
+    /// (```interval_select```).
+    pub fn interval_select(self, interval: &str) -> Self {
+        let error = interval.trim().is_empty().then(|| String::from("interval_select must not be empty"));
+        self.set("interval_select", interval, error)
+    }
+
+    /// Restricts grid values to land or sea (```mask```).
+    pub fn mask(self, mask: Mask) -> Self {
+        let value = match mask {
+            Mask::Land => "land",
+            Mask::Sea => "sea",
+        };
+        self.set("mask", value, None)
+    }
+
+    /// Sets how values between the time steps of a model are derived (```temporal_interpolation```).
+    pub fn temporal_interpolation(self, interpolation: TemporalInterpolation) -> Self {
+        let value = match interpolation {
+            TemporalInterpolation::Best => "best",
+            TemporalInterpolation::Linear => "linear",
+            TemporalInterpolation::None => "none",
+        };
+        self.set("temporal_interpolation", value, None)
+    }
+
+    /// Sets how the API handles invalid values (```on_invalid```).
+    pub fn on_invalid(self, on_invalid: OnInvalid) -> Self {
+        let value = match on_invalid {
+            OnInvalid::Fail => "fail",
+            OnInvalid::FillWithInvalid => "fill_with_invalid",
+        };
+        self.set("on_invalid", value, None)
+    }
+
+    /// Sets the time in seconds after which the API aborts the query (```timeout```).
+    pub fn timeout(self, seconds: u64) -> Self {
+        let error = (seconds == 0).then(|| String::from("timeout must be at least one second"));
+        self.set("timeout", &seconds.to_string(), error)
+    }
+
+    /// Requests the values along a route instead of for every combination of point in time and
+    /// location (```route```).
+    pub fn route(self, route: bool) -> Self {
+        self.set("route", &route.to_string(), None)
+    }
+
+    /// Adds an option that is not covered by the builder. The value is URL-encoded.
+    pub fn raw(self, name: &str, value: &str) -> Self {
+        let error = match name {
+            "" => Some(String::from("option name must not be empty")),
+            _ if name.contains(['&', '=', '?', '#']) => Some(format!("invalid option name {}", name)),
+            _ => None,
+        };
+        self.set(name, value, error)
+    }
+
+    /// Checks if no option is set.
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+
+    /// Returns the value of an option set by name (not for verbatim options).
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.options.iter().find_map(|option| match option {
+            QueryOption::Pair(n, value) if n == name => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Returns the value of an option by name, including verbatim options (e.g. "model=mix").
+    pub(crate) fn lookup(&self, name: &str) -> Option<&str> {
+        self.get(name).or_else(|| {
+            self.options.iter().find_map(|option| match option {
+                QueryOption::Verbatim(option) => option.split_once('=').filter(|(n, _)| *n == name).map(|(_, value)| value),
+                QueryOption::Pair(_, _) => None,
+            })
+        })
+    }
+
+    /// Returns the model the query is sent to: the ```model``` option or its alias ```source```
+    /// (including verbatim options), "mix" by default.
+    pub(crate) fn selected_model(&self) -> &str {
+        self.lookup("model").or_else(|| self.lookup("source")).unwrap_or("mix")
+    }
+
+    /// Removes an option by name, including verbatim options (e.g. "model=mix").
+    pub(crate) fn without(mut self, name: &str) -> Self {
+        self.options.retain(|option| match option {
+            QueryOption::Pair(n, _) => n != name,
+            QueryOption::Verbatim(option) => option.split_once('=').map_or(option.as_str(), |(n, _)| n) != name,
+        });
+        self.errors.retain(|(n, _)| n != name);
+        self
+    }
+
+    /// Returns the encoded query string (without the leading '?'), ```None``` without options.
+    pub fn to_query_string(&self) -> Option<String> {
+        if self.options.is_empty() {
+            return None;
+        }
+        let parts: Vec<String> = self
+            .options
+            .iter()
+            .map(|option| match option {
+                // Literal '+' is encoded as %2B, so the remaining ones are spaces, which not every
+                // server decodes from '+'.
+                QueryOption::Pair(name, value) => url::form_urlencoded::Serializer::new(String::new())
+                    .append_pair(name, value)
+                    .finish()
+                    .replace('+', "%20"),
+                QueryOption::Verbatim(option) => option.clone(),
+            })
+            .collect();
+        Some(parts.join("&"))
+    }
+
+    fn set(mut self, name: &str, value: &str, error: Option<String>) -> Self {
+        self.errors.retain(|(n, _)| n != name);
+        self.errors.extend(error.map(|error| (name.to_string(), error)));
+        let option = QueryOption::Pair(name.to_string(), value.to_string());
+        match self.options.iter_mut().find(|o| matches!(o, QueryOption::Pair(n, _) if n == name)) {
+            Some(existing) => *existing = option,
+            None => self.options.push(option),
+        }
+        self
+    }
+}
+
+/// Appends the options to the query specifications.
+pub(crate) fn append_options(query_specs: String, options: &QueryOptions) -> String {
+    match options.to_query_string() {
+        Some(query) => format!("{}?{}", query_specs, query),
+        None => query_specs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::errors::ConnectorError;
+    use crate::options::{EnsembleSelection, Mask, OnInvalid, QueryOptions, ToQueryOptions};
+
+    #[tokio::test]
+    async fn options_are_encoded_and_validated() {
+        let options = QueryOptions::new()
+            .model("mix")
+            .calibrated(true)
+            .ens_select(&[EnsembleSelection::Members(1, 10), EnsembleSelection::Quantile(0.9)])
+            .mask(Mask::Sea)
+            .on_invalid(OnInvalid::FillWithInvalid)
+            .raw("custom", "a b&c")
+            .calibrated(false);
+        assert_eq!(
+            options.to_query_options().unwrap().to_query_string().unwrap(),
+            "model=mix&calibrated=false&ens_select=member%3A1-10%2Cquantile0.9&mask=sea\
+             &on_invalid=fill_with_invalid&custom=a%20b%26c"
+        );
+        assert_eq!(options.get("calibrated"), Some("false"));
+
+        let legacy: Option<Vec<String>> = Some(vec![String::from("model=mix"), String::from("calibrated=true")]);
+        assert_eq!(legacy.to_query_options().unwrap().to_query_string().unwrap(), "model=mix&calibrated=true");
+        assert_eq!(None::<Vec<String>>.to_query_options().unwrap().to_query_string(), None);
+
+        let invalid = QueryOptions::new().timeout(0).ens_select(&[EnsembleSelection::Quantile(90.0)]);
+        assert!(matches!(invalid.to_query_options(), Err(ConnectorError::InvalidOption(_))));
+
+        // A replaced value takes its error along.
+        let corrected = QueryOptions::new().timeout(0).timeout(30);
+        assert_eq!(corrected.to_query_options().unwrap().to_query_string().unwrap(), "timeout=30");
+        let broken = QueryOptions::new().timeout(30).timeout(0);
+        assert!(matches!(broken.to_query_options(), Err(ConnectorError::InvalidOption(_))));
+    }
+}
@@ -0,0 +1,263 @@
+//! # Parameter
+//! The API names parameters by a short grammar: the name of the quantity, an optional aggregation,
+//! level and interval, and the unit (e.g. ```t_2m:C```, ```t_max_2m_24h:C```, ```precip_1h:mm``` or
+//! ```wind_speed_850hPa:ms```). A [`Parameter`] parses and renders these names, such that they can be
+//! built and checked in code instead of being assembled as strings.
+//!
+//! The query methods of the [`crate::APIClient`] accept anything that can be displayed, so a
+//! [`Parameter`] can be passed in place of a ```String``` (or ```&str```). The columns of the returned
+//! DataFrames are named after the displayed parameters and can be mapped back with
+//! [`parameter_columns`].
+//!
+//! ```rust, no_run
+//! use chrono::{Duration, Utc};
+//! use meteomatics::{APIClient, Point, TimeSeries};
+//! use meteomatics::parameter::{parameter_columns, Aggregation, Parameter};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = APIClient::new("ferris_loves_rustaceans", "0123456789", 10);
+//!     let time_series = TimeSeries {
+//!         start: Utc::now(),
+//!         end: Utc::now() + Duration::days(7),
+//!         timedelta: Option::from(Duration::days(1))
+//!     };
+//!     let parameters = vec![
+//!         Parameter::new("t", "C").aggregation(Aggregation::Max).level("2m").interval("24h"),
+//!         "precip_24h:mm".parse::<Parameter>().unwrap(),
+//!     ];
+//!     let df = client
+//!         .query_time_series(&time_series, &parameters, &[Point { lat: 47.4, lon: 9.4 }], &None)
+//!         .await
+//!         .unwrap();
+//!     for (column, parameter) in parameter_columns(&df) {
+//!         println!("{}: {:?} in {}", column, parameter.name, parameter.unit);
+//!     }
+//! }
+//! ```
+
+use crate::errors::ConnectorError;
+use polars::frame::DataFrame;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Aggregation of a parameter over its interval (e.g. the maximum temperature of a day).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregation {
+    /// Maximum (```max```).
+    Max,
+    /// Minimum (```min```).
+    Min,
+    /// Mean (```mean```).
+    Mean,
+    /// Sum (```sum```).
+    Sum,
+}
+
+impl Aggregation {
+    fn parse(token: &str) -> Option<Self> {
+        match token {
+            "max" => Some(Aggregation::Max),
+            "min" => Some(Aggregation::Min),
+            "mean" => Some(Aggregation::Mean),
+            "sum" => Some(Aggregation::Sum),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Aggregation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Aggregation::Max => write!(f, "max"),
+            Aggregation::Min => write!(f, "min"),
+            Aggregation::Mean => write!(f, "mean"),
+            Aggregation::Sum => write!(f, "sum"),
+        }
+    }
+}
+
+/// A parameter of the API, rendered as
+/// ```{name}[_{aggregation}][_{level}][_{interval}]:{unit}``` (e.g. ```t_max_2m_24h:C```).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Parameter {
+    /// Name of the quantity (e.g. "t" or "wind_speed").
+    pub name: String,
+    /// Aggregation over the interval.
+    pub aggregation: Option<Aggregation>,
+    /// Level above (or below) the ground or pressure level (e.g. "2m", "-50cm" or "850hPa").
+    pub level: Option<String>,
+    /// Interval of accumulated or aggregated values (e.g. "1h", "24h" or "PT3H").
+    pub interval: Option<String>,
+    /// Unit of the values (e.g. "C", "mm" or "ms").
+    pub unit: String,
+}
+
+impl Parameter {
+    /// Creates a parameter without aggregation, level and interval.
+    pub fn new(name: &str, unit: &str) -> Self {
+        Self { name: name.to_string(), aggregation: None, level: None, interval: None, unit: unit.to_string() }
+    }
+
+    /// Sets the aggregation over the interval.
+    pub fn aggregation(mut self, aggregation: Aggregation) -> Self {
+        self.aggregation = Some(aggregation);
+        self
+    }
+
+    /// Sets the level (e.g. "2m" or "850hPa").
+    pub fn level(mut self, level: &str) -> Self {
+        self.level = Some(level.to_string());
+        self
+    }
+
+    /// Sets the interval (e.g. "1h" or "24h").
+    pub fn interval(mut self, interval: &str) -> Self {
+        self.interval = Some(interval.to_string());
+        self
+    }
+
+    /// Returns the same parameter in another unit (e.g. "F" instead of "C").
+    pub fn with_unit(&self, unit: &str) -> Self {
+        Self { unit: unit.to_string(), ..self.clone() }
+    }
+
+    /// Parses the name of a DataFrame column. Besides parameters this accepts the columns of ensemble
+    /// queries (e.g. ```t_2m:C-m1```, see [`crate::ensemble`]). Other columns (e.g. ```validdate```)
+    /// return ```None```.
+    pub fn from_column(column: &str) -> Option<Self> {
+        column.parse().ok().or_else(|| {
+            let (parameter, _) = column.rsplit_once('-')?;
+            parameter.parse().ok()
+        })
+    }
+}
+
+// Intervals: "1h", "24h", "10min", "1d" or ISO 8601 durations like "PT3H".
+fn is_interval(token: &str) -> bool {
+    if token.len() > 1 && token.starts_with('P') {
+        return token[1..].chars().all(|c| c.is_ascii_alphanumeric() || c == '.');
+    }
+    let digits = token.chars().take_while(|c| c.is_ascii_digit()).count();
+    digits > 0 && matches!(&token[digits..], "min" | "h" | "d")
+}
+
+// Levels: "2m", "-50cm" or "850hPa".
+fn is_level(token: &str) -> bool {
+    let number = token.strip_prefix('-').unwrap_or(token);
+    let digits = number.chars().take_while(|c| c.is_ascii_digit()).count();
+    digits > 0 && matches!(&number[digits..], "m" | "cm" | "hPa")
+}
+
+impl FromStr for Parameter {
+    type Err = ConnectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = |reason: &str| ConnectorError::MalformedParameter(format!("{}: {}", s, reason));
+        if s.chars().any(|c| c.is_whitespace() || matches!(c, ',' | '/' | '?' | '&' | '+' | '=')) {
+            return Err(malformed("invalid character"));
+        }
+        let (body, unit) = s.rsplit_once(':').ok_or_else(|| malformed("missing unit"))?;
+        if unit.is_empty() || unit.contains('-') {
+            return Err(malformed("invalid unit"));
+        }
+
+        let mut tokens: Vec<&str> = body.split('_').collect();
+        if tokens.iter().any(|t| t.is_empty()) {
+            return Err(malformed("empty name"));
+        }
+        let interval = match tokens.last() {
+            Some(t) if tokens.len() > 1 && is_interval(t) => tokens.pop().map(String::from),
+            _ => None,
+        };
+        let level = match tokens.last() {
+            Some(t) if tokens.len() > 1 && is_level(t) => tokens.pop().map(String::from),
+            _ => None,
+        };
+        let aggregation = match tokens.last().and_then(|t| Aggregation::parse(t)) {
+            Some(aggregation) if tokens.len() > 1 => {
+                tokens.pop();
+                Some(aggregation)
+            }
+            _ => None,
+        };
+        Ok(Self { name: tokens.join("_"), aggregation, level, interval, unit: unit.to_string() })
+    }
+}
+
+impl TryFrom<&str> for Parameter {
+    type Error = ConnectorError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Parameter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(aggregation) = &self.aggregation {
+            write!(f, "_{}", aggregation)?;
+        }
+        if let Some(level) = &self.level {
+            write!(f, "_{}", level)?;
+        }
+        if let Some(interval) = &self.interval {
+            write!(f, "_{}", interval)?;
+        }
+        write!(f, ":{}", self.unit)
+    }
+}
+
+/// Returns the parameter columns of a DataFrame together with their parameters (see
+/// [`Parameter::from_column`]), in the order of the columns.
+///
+/// # Arguments
+///
+/// * `df` - The result of a query.
+pub fn parameter_columns(df: &DataFrame) -> Vec<(&str, Parameter)> {
+    df.get_columns()
+        .iter()
+        .filter_map(|s| Parameter::from_column(s.name()).map(|parameter| (s.name(), parameter)))
+        .collect()
+}
+
+/// Renders the parameters of a query.
+pub(crate) fn parameter_names<P: fmt::Display>(parameters: &[P]) -> Vec<String> {
+    parameters.iter().map(|p| p.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::errors::ConnectorError;
+    use crate::parameter::{parameter_columns, Aggregation, Parameter};
+    use polars::prelude::*;
+
+    #[tokio::test]
+    async fn parameters_are_parsed_and_rendered() {
+        let t_max: Parameter = "t_max_2m_24h:C".parse().unwrap();
+        assert_eq!(t_max, Parameter::new("t", "C").aggregation(Aggregation::Max).level("2m").interval("24h"));
+        assert_eq!(t_max.to_string(), "t_max_2m_24h:C");
+
+        let wind: Parameter = "wind_speed_850hPa:kmh".parse().unwrap();
+        assert_eq!((wind.name.as_str(), wind.level.as_deref()), ("wind_speed", Some("850hPa")));
+        let precip: Parameter = "precip_1h:mm".parse().unwrap();
+        assert_eq!((precip.name.as_str(), precip.interval.as_deref()), ("precip", Some("1h")));
+        let sunshine: Parameter = "sunshine_duration_PT3H:min".parse().unwrap();
+        assert_eq!(sunshine.interval.as_deref(), Some("PT3H"));
+        assert_eq!("msl_pressure:hPa".parse::<Parameter>().unwrap(), Parameter::new("msl_pressure", "hPa"));
+        assert_eq!(t_max.with_unit("F").to_string(), "t_max_2m_24h:F");
+
+        for invalid in ["t_2m", "t_2m:", "t__2m:C", "t_2m:C,precip_1h:mm"] {
+            assert!(matches!(invalid.parse::<Parameter>(), Err(ConnectorError::MalformedParameter(_))), "{}", invalid);
+        }
+
+        let df = df!("lat" => &[47.0], "validdate" => &["t1"], "t_2m:C" => &[5.0], "precip_1h:mm-m1" => &[0.2]).unwrap();
+        let columns = parameter_columns(&df);
+        assert_eq!(columns.len(), 2);
+        assert_eq!(columns[1], ("precip_1h:mm-m1", precip));
+    }
+}
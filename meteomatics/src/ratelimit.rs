@@ -0,0 +1,352 @@
+//! # Rate limiting
+//! The Meteomatics API enforces limits on the number of requests per account (see
+//! [`UserStats`](crate::util::UserStats)). The [`RateLimiter`] keeps an [`crate::APIClient`] below
+//! the limit for requests in parallel and the limit for requests in the last 60 seconds. The limits
+//! are read from <https://api.meteomatics.com/user_stats_json> and refreshed periodically. Requests
+//! of other services using the same account are taken into account at every refresh, since the API
+//! reports how many requests are currently used.
+//!
+//! ```rust, no_run
+//! use meteomatics::{APIClient, RateLimitConfig};
+//! use std::time::Duration;
+//!
+//! let client = APIClient::builder("ferris_loves_rustaceans", "0123456789")
+//!     .rate_limit(RateLimitConfig { refresh_interval: Duration::from_secs(60), ..RateLimitConfig::default() })
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use crate::errors::ConnectorError;
+use crate::util::{map_body_stream, Limit, UserStats};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::StreamExt;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+// Length of the sliding window for the per-minute limit.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Configuration of the client-side rate limiting.
+#[derive(Clone, Debug)]
+pub struct RateLimitConfig {
+    /// How often the limits are refreshed from ```user_stats_json```.
+    pub refresh_interval: Duration,
+    /// Use the soft limits of the account instead of the hard limits (if they are set).
+    pub prefer_soft_limits: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            refresh_interval: Duration::from_secs(300),
+            prefer_soft_limits: false,
+        }
+    }
+}
+
+/// Caps the requests in flight and paces the requests per minute of an [`crate::APIClient`]. The
+/// limiter is shared between clones of a client.
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    state: Mutex<LimiterState>,
+    notify: Notify,
+    refresh_lock: tokio::sync::Mutex<()>,
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    parallel: Option<u32>,
+    per_minute: Option<u32>,
+    in_flight: u32,
+    window: VecDeque<Instant>,
+    last_refresh: Option<Instant>,
+}
+
+impl LimiterState {
+    // Removes the requests that are older than the sliding window.
+    fn prune(&mut self, now: Instant) {
+        while let Some(start) = self.window.front() {
+            if now.duration_since(*start) >= WINDOW {
+                self.window.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+/// Marks a request in flight. The slot is released when the permit is dropped.
+#[derive(Debug)]
+pub struct RateLimitPermit<'a> {
+    limiter: &'a RateLimiter,
+}
+
+impl Drop for RateLimitPermit<'_> {
+    fn drop(&mut self) {
+        self.limiter.release();
+    }
+}
+
+/// Like [`RateLimitPermit`], but keeps the limiter alive itself, such that the permit can be moved
+/// into the body of a response and is released only when the body has been read (or dropped).
+#[derive(Debug)]
+pub struct OwnedRateLimitPermit {
+    limiter: Arc<RateLimiter>,
+}
+
+impl Drop for OwnedRateLimitPermit {
+    fn drop(&mut self) {
+        self.limiter.release();
+    }
+}
+
+/// Holds the permit until the body of the response has been read (or the response is dropped).
+pub(crate) fn hold_until_read(response: reqwest::Response, permit: OwnedRateLimitPermit) -> reqwest::Response {
+    map_body_stream(response, move |body| {
+        body.map(move |chunk| {
+            let _permit = &permit;
+            chunk
+        })
+    })
+}
+
+impl RateLimiter {
+    /// Creates a new limiter without known limits. The limits are fetched before the first request.
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(LimiterState::default()),
+            notify: Notify::new(),
+            refresh_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Returns the current (parallel, per minute) limits. ```None``` means that no limit is applied.
+    pub fn limits(&self) -> (Option<u32>, Option<u32>) {
+        let state = self.state.lock().unwrap();
+        (state.parallel, state.per_minute)
+    }
+
+    /// Updates the limits from the account statistics. The usage reported by the API that does not
+    /// stem from this limiter (e.g. other services using the same account) reduces the budget.
+    pub fn update(&self, stats: &UserStats) {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state.prune(now);
+
+        let own_in_flight = state.in_flight;
+        let own_in_window = state.window.len() as u32;
+        state.parallel = self.budget(&stats.parallel, own_in_flight);
+        state.per_minute = self.budget(&stats.since_60s, own_in_window);
+        state.last_refresh = Some(now);
+        drop(state);
+        self.notify.notify_waiters();
+    }
+
+    // Selects the applicable limit (a value of 0 means the limit is not set) and subtracts the
+    // foreign usage. At least one request is always allowed.
+    fn budget(&self, limit: &Limit, own_usage: u32) -> Option<u32> {
+        let cap = match (self.config.prefer_soft_limits, limit.soft_lim, limit.hard_lim) {
+            (_, 0, 0) => return None,
+            (true, soft, _) if soft > 0 => soft,
+            (_, soft, 0) => soft,
+            (_, _, hard) => hard,
+        };
+        let foreign_usage = limit.used.saturating_sub(own_usage);
+        Some(cap.saturating_sub(foreign_usage).max(1))
+    }
+
+    /// Checks if the limits have to be (re-)fetched.
+    pub fn needs_refresh(&self) -> bool {
+        match self.state.lock().unwrap().last_refresh {
+            None => true,
+            Some(last) => last.elapsed() >= self.config.refresh_interval,
+        }
+    }
+
+    /// Refreshes the limits with the given fetch function if the refresh interval elapsed. Only one
+    /// caller refreshes at a time. Before the limits are known for the first time, all callers wait
+    /// for the refresh, afterwards they continue with the old limits. A failed refresh keeps the old
+    /// limits until the next interval.
+    pub async fn refresh_if_due<F, Fut>(&self, fetch: F)
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<UserStats, ConnectorError>>,
+    {
+        if !self.needs_refresh() {
+            return;
+        }
+        let initialized = self.state.lock().unwrap().last_refresh.is_some();
+        let _guard = if initialized {
+            match self.refresh_lock.try_lock() {
+                Ok(guard) => guard,
+                Err(_) => return,
+            }
+        } else {
+            self.refresh_lock.lock().await
+        };
+        // Somebody else may have refreshed while we were waiting for the lock.
+        if !self.needs_refresh() {
+            return;
+        }
+        match fetch().await {
+            Ok(stats) => self.update(&stats),
+            Err(_) => self.state.lock().unwrap().last_refresh = Some(Instant::now()),
+        }
+    }
+
+    /// Waits until a request is allowed by both the parallel and the per minute limit.
+    pub async fn acquire(&self) -> RateLimitPermit<'_> {
+        self.wait_for_slot().await;
+        RateLimitPermit { limiter: self }
+    }
+
+    /// Same as [`RateLimiter::acquire`], but the permit owns a reference to the limiter.
+    pub async fn acquire_owned(self: Arc<Self>) -> OwnedRateLimitPermit {
+        self.wait_for_slot().await;
+        OwnedRateLimitPermit { limiter: self }
+    }
+
+    // Releases the slot of a request in flight.
+    fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.in_flight = state.in_flight.saturating_sub(1);
+        drop(state);
+        self.notify.notify_waiters();
+    }
+
+    // Waits for a free slot and occupies it.
+    async fn wait_for_slot(&self) {
+        loop {
+            // Register for notifications before checking the state to not miss a released slot.
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                state.prune(now);
+                let parallel_ok = !matches!(state.parallel, Some(limit) if state.in_flight >= limit);
+                let minute_ok = !matches!(state.per_minute, Some(limit) if state.window.len() as u32 >= limit);
+                if parallel_ok && minute_ok {
+                    state.in_flight += 1;
+                    state.window.push_back(now);
+                    return;
+                }
+                match (minute_ok, state.window.front()) {
+                    (false, Some(oldest)) => Some(*oldest + WINDOW - now),
+                    _ => None,
+                }
+            };
+
+            match wait {
+                Some(delay) => {
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {},
+                        _ = &mut notified => {},
+                    }
+                }
+                None => notified.await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::errors::ConnectorError;
+    use crate::ratelimit::{hold_until_read, RateLimitConfig, RateLimiter};
+    use crate::util::{Limit, UserStats};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn stats(parallel: (u32, u32, u32), since_60s: (u32, u32, u32)) -> UserStats {
+        let limit = |(used, soft_lim, hard_lim)| Limit { used, soft_lim, hard_lim };
+        UserStats {
+            username: String::from("rustythecrab"),
+            total: limit((0, 0, 0)),
+            since_midnight: limit((0, 0, 0)),
+            since_0: limit((0, 0, 0)),
+            since_60s: limit(since_60s),
+            parallel: limit(parallel),
+            hist: String::new(),
+            area: true,
+            models: Vec::new(),
+            error: String::new(),
+            contact: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn limits_from_user_stats() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        limiter.update(&stats((0, 20, 500), (0, 0, 6000)));
+        assert_eq!(limiter.limits(), (Some(500), Some(6000)));
+
+        // Foreign usage reduces the budget, soft limits are used on request.
+        let limiter = RateLimiter::new(RateLimitConfig { prefer_soft_limits: true, ..RateLimitConfig::default() });
+        limiter.update(&stats((5, 20, 500), (0, 0, 0)));
+        assert_eq!(limiter.limits(), (Some(15), None));
+    }
+
+    #[tokio::test]
+    async fn caps_requests_in_flight() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        limiter.update(&stats((0, 0, 2), (0, 0, 0)));
+
+        let first = limiter.acquire().await;
+        let _second = limiter.acquire().await;
+        let blocked = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+        assert!(blocked.is_err());
+
+        drop(first);
+        let third = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+        assert!(third.is_ok());
+    }
+
+    #[tokio::test]
+    async fn paces_requests_per_minute() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        limiter.update(&stats((0, 0, 0), (0, 0, 2)));
+
+        drop(limiter.acquire().await);
+        drop(limiter.acquire().await);
+        let blocked = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+        assert!(blocked.is_err());
+    }
+
+    #[tokio::test]
+    async fn refreshes_only_when_due() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        assert!(limiter.needs_refresh());
+        limiter.refresh_if_due(|| async { Ok(stats((0, 0, 3), (0, 0, 0))) }).await;
+        assert_eq!(limiter.limits(), (Some(3), None));
+
+        // Not due yet: the fetch function is not called.
+        limiter.refresh_if_due(|| async { Err(ConnectorError::LibraryError(String::from("offline"))) }).await;
+        assert_eq!(limiter.limits(), (Some(3), None));
+        assert!(!limiter.needs_refresh());
+    }
+
+    #[tokio::test]
+    async fn holds_permit_until_body_is_read() {
+        let limiter = Arc::new(RateLimiter::new(RateLimitConfig::default()));
+        limiter.update(&stats((0, 0, 1), (0, 0, 0)));
+
+        let permit = Arc::clone(&limiter).acquire_owned().await;
+        let response = reqwest::Response::from(http::Response::new("validdate;t_2m:C"));
+        let response = hold_until_read(response, permit);
+        let blocked = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+        assert!(blocked.is_err());
+
+        assert_eq!(response.text().await.unwrap(), "validdate;t_2m:C");
+        let next = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+        assert!(next.is_ok());
+    }
+}
@@ -0,0 +1,252 @@
+//! # Retry
+//! This module contains the [`RetryPolicy`] that decides whether a failed HTTP request to the API is
+//! repeated. Transient failures (e.g. HTTP 429 "Too Many Requests", 502 "Bad Gateway" or 503 "Service
+//! Unavailable" as well as timeouts and connection errors) are retried with an exponential backoff.
+//! The backoff is randomized (jitter) to avoid that many clients retry at the same moment and the
+//! ```Retry-After``` header sent by the API is honored.
+//!
+//! ```rust, no_run
+//! use meteomatics::{APIClient, RetryPolicy};
+//! use std::time::Duration;
+//!
+//! let policy = RetryPolicy {
+//!     max_attempts: 5,
+//!     initial_backoff: Duration::from_secs(1),
+//!     ..RetryPolicy::default()
+//! }
+//! .on_retry(|event| println!("retrying {} (attempt {}): {}", event.url, event.attempt, event.reason));
+//!
+//! let client = APIClient::builder("ferris_loves_rustaceans", "0123456789")
+//!     .retry_policy(policy)
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Describes which failures are retried and how long to wait between the attempts.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts per request (including the first one). A value of 1 disables retries.
+    pub max_attempts: u32,
+    /// Backoff before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for the backoff between two attempts (also for the ```Retry-After``` header).
+    pub max_backoff: Duration,
+    /// Factor by which the backoff grows after each attempt. Invalid factors (negative or NaN) make
+    /// the client wait for ```max_backoff```.
+    pub multiplier: f64,
+    /// Fraction (between 0 and 1) of the backoff that is randomized.
+    pub jitter: f64,
+    /// HTTP status codes that are considered transient.
+    pub retry_statuses: Vec<StatusCode>,
+    /// Retry requests that timed out.
+    pub retry_on_timeout: bool,
+    /// Retry requests that failed to connect.
+    pub retry_on_connect: bool,
+    /// Wait for the duration given in the ```Retry-After``` header (if present, at most ```max_backoff```)
+    /// instead of the backoff.
+    pub respect_retry_after: bool,
+    /// Callback that is invoked before every retry.
+    pub on_retry: Option<RetryCallback>,
+}
+
+impl Default for RetryPolicy {
+    /// Up to three attempts for 429, 500, 502, 503 and 504 responses as well as timeouts and connection
+    /// errors, starting with a backoff of 500 ms.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+            retry_statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            retry_on_timeout: true,
+            retry_on_connect: true,
+            respect_retry_after: true,
+            on_retry: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries (this is what an [`crate::APIClient`] uses by default).
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    /// Registers a callback that is invoked before every retry.
+    pub fn on_retry<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&RetryEvent) + Send + Sync + 'static
+    {
+        self.on_retry = Some(RetryCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Checks if a response with the given status should be retried after the given attempt.
+    pub fn retries_status(&self, attempt: u32, status: StatusCode) -> bool {
+        attempt < self.max_attempts && self.retry_statuses.contains(&status)
+    }
+
+    /// Checks if a transport error should be retried after the given attempt.
+    pub fn retries_error(&self, attempt: u32, error: &reqwest::Error) -> bool {
+        attempt < self.max_attempts
+            && ((self.retry_on_timeout && error.is_timeout()) || (self.retry_on_connect && error.is_connect()))
+    }
+
+    /// Computes the (randomized) backoff after the given attempt (starting at 1).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(63) as i32;
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        let backoff = backoff.min(self.max_backoff.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = 1.0 - jitter * rand::thread_rng().gen::<f64>();
+        Duration::try_from_secs_f64(backoff * factor).map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+
+    /// Computes the delay before the next attempt, preferring the ```Retry-After``` header if it is
+    /// present and the policy respects it. The delay never exceeds ```max_backoff```.
+    pub fn delay(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        if self.respect_retry_after {
+            if let Some(retry_after) = headers.and_then(parse_retry_after) {
+                return retry_after.min(self.max_backoff);
+            }
+        }
+        self.backoff(attempt)
+    }
+}
+
+/// Reason for a retry.
+#[derive(Clone, Debug)]
+pub enum RetryReason {
+    /// The API answered with a transient HTTP status.
+    Status(StatusCode),
+    /// The request failed before a response was received.
+    Transport(String),
+}
+
+impl fmt::Display for RetryReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RetryReason::Status(status) => write!(f, "HTTP status {}", status),
+            RetryReason::Transport(error) => write!(f, "transport error: {}", error),
+        }
+    }
+}
+
+/// Information about a retry that is passed to the [`RetryPolicy::on_retry`] callback.
+#[derive(Clone, Debug)]
+pub struct RetryEvent {
+    /// The URL of the request that is retried.
+    pub url: String,
+    /// The attempt that failed (starting at 1).
+    pub attempt: u32,
+    /// Why the attempt failed.
+    pub reason: RetryReason,
+    /// How long the client waits before the next attempt.
+    pub delay: Duration,
+}
+
+/// Wrapper for the retry callback (so that the policy can still be cloned and debug-printed).
+#[derive(Clone)]
+pub struct RetryCallback(Arc<dyn Fn(&RetryEvent) + Send + Sync>);
+
+impl RetryCallback {
+    /// Invokes the callback.
+    pub fn call(&self, event: &RetryEvent) {
+        (self.0)(event)
+    }
+}
+
+impl fmt::Debug for RetryCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RetryCallback")
+    }
+}
+
+/// Parses the ```Retry-After``` header, which is either a number of seconds or a HTTP date.
+pub fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::retry::{parse_retry_after, RetryPolicy};
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn backoff_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(350),
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+
+        // With jitter the backoff is never larger than without.
+        let policy = RetryPolicy { jitter: 0.5, ..policy };
+        for _ in 0..20 {
+            let backoff = policy.backoff(2);
+            assert!(backoff <= Duration::from_millis(200) && backoff >= Duration::from_millis(100));
+        }
+
+        // Invalid multipliers do not panic.
+        for multiplier in [-2.0, f64::NAN, f64::INFINITY] {
+            let policy = RetryPolicy { multiplier, ..policy.clone() };
+            assert!(policy.backoff(2) <= Duration::from_millis(350));
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_only_transient_statuses() {
+        let policy = RetryPolicy::default();
+        assert!(policy.retries_status(1, StatusCode::TOO_MANY_REQUESTS));
+        assert!(policy.retries_status(2, StatusCode::BAD_GATEWAY));
+        assert!(!policy.retries_status(3, StatusCode::BAD_GATEWAY));
+        assert!(!policy.retries_status(1, StatusCode::UNAUTHORIZED));
+        assert!(!RetryPolicy::none().retries_status(1, StatusCode::SERVICE_UNAVAILABLE));
+    }
+
+    #[tokio::test]
+    async fn retry_after_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(7)));
+
+        // Dates in the past mean "retry now".
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+
+        let policy = RetryPolicy { respect_retry_after: false, jitter: 0.0, ..RetryPolicy::default() };
+        assert_eq!(policy.delay(1, Some(&headers)), Duration::from_millis(500));
+
+        // A long Retry-After is capped by the maximum backoff.
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("86400"));
+        assert_eq!(RetryPolicy::default().delay(1, Some(&headers)), Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,308 @@
+//! # Split
+//! A time series query puts all locations, parameters and the complete time range into the URL of a
+//! single request. Large queries therefore run into the maximum URL length or the limits of the API
+//! per request (e.g. at most 10 parameters). With [`SplitLimits`] configured on the client (see
+//! [`crate::APIClientBuilder::split_limits`]) such queries are split across locations, parameters and
+//! time windows. The chunks are requested concurrently and their results are combined into a single
+//! [`DataFrame`] with the usual ```lat```/```lon```/```validdate``` columns.
+//!
+//! ```rust, no_run
+//! use meteomatics::{APIClient, SplitLimits};
+//!
+//! let client = APIClient::builder("ferris_loves_rustaceans", "0123456789")
+//!     .split_limits(SplitLimits { max_points: 50, ..SplitLimits::default() })
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use crate::ensemble::is_parameter_column;
+use crate::errors::ConnectorError;
+use crate::util::TimeSeries;
+use polars::prelude::*;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Limits per request above which a time series query is split.
+#[derive(Clone, Debug)]
+pub struct SplitLimits {
+    /// Maximum number of locations per request.
+    pub max_points: usize,
+    /// Maximum number of parameters per request.
+    pub max_parameters: usize,
+    /// Maximum number of time steps per request (only applies to time series with a time step).
+    pub max_time_steps: usize,
+    /// Maximum length of the request URL.
+    pub max_url_length: usize,
+    /// Maximum number of chunks requested at the same time.
+    pub concurrency: usize,
+}
+
+impl Default for SplitLimits {
+    /// At most 10 parameters (the limit of the API), 100 locations, 1000 time steps and 2000 characters
+    /// per request.
+    fn default() -> Self {
+        Self {
+            max_points: 100,
+            max_parameters: 10,
+            max_time_steps: 1000,
+            max_url_length: 2000,
+            concurrency: 4,
+        }
+    }
+}
+
+/// Number of locations, parameters and time steps per chunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkSizes {
+    pub points: usize,
+    pub parameters: usize,
+    pub time_steps: Option<usize>,
+}
+
+impl SplitLimits {
+    /// Computes the chunk sizes for a query. The sizes are first limited by the counts and then halved
+    /// (locations first, then parameters) until the longest URL fits.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_length` - Length of the base URL plus the optionals of the query.
+    /// * `time_series` - The temporal extent of the query.
+    /// * `parameters` - Names of the parameters.
+    /// * `locations` - The locations (e.g. [`crate::Point`] or postal codes).
+    pub fn chunk_sizes<L: Display>(
+        &self,
+        base_length: usize,
+        time_series: &TimeSeries,
+        parameters: &[String],
+        locations: &[L],
+    ) -> ChunkSizes {
+        let mut sizes = ChunkSizes {
+            points: locations.len().min(self.max_points).max(1),
+            parameters: parameters.len().min(self.max_parameters).max(1),
+            time_steps: count_time_steps(time_series).map(|n| n.min(self.max_time_steps).max(1)),
+        };
+
+        let location_lengths: Vec<usize> = locations.iter().map(|l| l.to_string().len()).collect();
+        let parameter_lengths: Vec<usize> = parameters.iter().map(|p| p.len()).collect();
+        // "<start>--<end>:<step>/<params>/<locations>/csv"
+        let time_length = time_series.to_string().len();
+        loop {
+            let url_length = base_length
+                + time_length
+                + longest_join(&parameter_lengths, sizes.parameters)
+                + longest_join(&location_lengths, sizes.points)
+                + 6;
+            if url_length <= self.max_url_length {
+                break;
+            }
+            if sizes.points > 1 {
+                sizes.points = sizes.points.div_ceil(2);
+            } else if sizes.parameters > 1 {
+                sizes.parameters = sizes.parameters.div_ceil(2);
+            } else {
+                break;
+            }
+        }
+        sizes
+    }
+
+    /// Checks if a query with the given number of locations, parameters and time steps is split.
+    pub fn requires_split(&self, sizes: &ChunkSizes, n_points: usize, n_parameters: usize, time_series: &TimeSeries) -> bool {
+        sizes.points < n_points
+            || sizes.parameters < n_parameters
+            || matches!((sizes.time_steps, count_time_steps(time_series)), (Some(k), Some(n)) if k < n)
+    }
+}
+
+// Length of the longest joined chunk (including the separators) of the given item lengths.
+fn longest_join(lengths: &[usize], chunk_size: usize) -> usize {
+    lengths
+        .chunks(chunk_size.max(1))
+        .map(|chunk| chunk.iter().sum::<usize>() + chunk.len().saturating_sub(1))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns the number of time steps of a time series (```None``` if it has no time step).
+pub fn count_time_steps(time_series: &TimeSeries) -> Option<usize> {
+    let step = time_series.timedelta?.num_milliseconds();
+    if step <= 0 {
+        return None;
+    }
+    let span = (time_series.end - time_series.start).num_milliseconds().max(0);
+    Some((span / step) as usize + 1)
+}
+
+/// Splits a time series into consecutive windows with at most ```steps_per_window``` time steps. The
+/// windows do not overlap, the next window starts one time step after the end of the previous one.
+pub fn time_windows(time_series: &TimeSeries, steps_per_window: usize) -> Vec<TimeSeries> {
+    let (Some(step), Some(n)) = (time_series.timedelta, count_time_steps(time_series)) else {
+        return vec![time_series.clone()];
+    };
+    let steps_per_window = steps_per_window.max(1);
+    (0..n)
+        .step_by(steps_per_window)
+        .map(|first| {
+            let start = time_series.start + step * first as i32;
+            let last = (first + steps_per_window - 1).min(n - 1);
+            TimeSeries {
+                start,
+                end: time_series.start + step * last as i32,
+                timedelta: Some(step),
+            }
+        })
+        .collect()
+}
+
+/// Combines the results for different parameter chunks (same locations and times) side by side. The
+/// key columns (e.g. ```lat```, ```lon``` and ```validdate```, or ```station_id```) are taken from the
+/// first frame, the other frames contribute their parameter columns. Fails if the key columns of the
+/// chunks are not equal, since the rows would not match.
+///
+/// # Arguments
+///
+/// * `frames` - The results of the parameter chunks, in the order of the chunks.
+/// * `parameter_chunks` - The parameter names of each chunk.
+pub fn combine_parameter_chunks(
+    frames: Vec<DataFrame>,
+    parameter_chunks: &[Vec<String>],
+) -> std::result::Result<DataFrame, ConnectorError> {
+    let mut frames = frames.into_iter().zip(parameter_chunks);
+    let (mut df, _) = frames
+        .next()
+        .ok_or_else(|| ConnectorError::LibraryError(String::from("No chunks to combine")))?;
+    for (other, parameters) in frames {
+        if other.height() != df.height() {
+            return Err(ConnectorError::LibraryError(format!(
+                "Parameter chunks differ in length ({} vs. {} rows)", df.height(), other.height()
+            )));
+        }
+        // Ensemble queries return several columns per parameter (e.g. "t_2m:C-m1").
+        let (columns, keys): (Vec<&Series>, Vec<&Series>) = other
+            .get_columns()
+            .iter()
+            .partition(|s| parameters.iter().any(|p| is_parameter_column(s.name(), p)));
+        for key in keys {
+            if !df.column(key.name()).is_ok_and(|s| s.series_equal_missing(key)) {
+                return Err(ConnectorError::LibraryError(format!(
+                    "Parameter chunks differ in column {}", key.name()
+                )));
+            }
+        }
+        let columns: Vec<Series> = columns.into_iter().cloned().collect();
+        df = df.hstack(&columns).map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
+    }
+    Ok(df)
+}
+
+/// Stacks the results of different location chunks and time windows. Columns are cast to the types of
+/// the first frame (e.g. if a column only contains missing values in one chunk).
+pub fn concat_frames(frames: Vec<DataFrame>) -> std::result::Result<DataFrame, ConnectorError> {
+    let mut frames = frames.into_iter();
+    let mut df = frames
+        .next()
+        .ok_or_else(|| ConnectorError::LibraryError(String::from("No chunks to combine")))?;
+    for other in frames {
+        let mut columns = Vec::with_capacity(df.width());
+        for series in df.get_columns() {
+            let column = other
+                .column(series.name())
+                .and_then(|c| c.cast(series.dtype()))
+                .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
+            columns.push(column);
+        }
+        let other = DataFrame::new(columns).map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
+        df.vstack_mut(&other).map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
+    }
+    Ok(df)
+}
+
+/// Reorders the rows such that all rows of a location are contiguous (in the order in which the
+/// locations first appear), as in the response of an unsplit query. The location is identified by
+/// all columns before ```validdate```. The order within a location is kept.
+pub fn group_by_location(df: DataFrame) -> std::result::Result<DataFrame, ConnectorError> {
+    let names = df.get_column_names();
+    let n_keys = match names.iter().position(|name| *name == "validdate") {
+        Some(0) | None => return Ok(df),
+        Some(n) => n,
+    };
+    let keys: Vec<&Series> = df.get_columns()[..n_keys].iter().collect();
+
+    let mut groups: HashMap<String, usize> = HashMap::new();
+    let mut rows: Vec<(usize, usize)> = Vec::with_capacity(df.height());
+    for row in 0..df.height() {
+        let key = keys.iter().map(|s| s.get(row).to_string()).collect::<Vec<String>>().join(";");
+        let next = groups.len();
+        let group = *groups.entry(key).or_insert(next);
+        rows.push((group, row));
+    }
+    rows.sort();
+    let indices: Vec<IdxSize> = rows.into_iter().map(|(_, row)| row as IdxSize).collect();
+    df.take(&IdxCa::from_vec("", indices)).map_err(|e| ConnectorError::PolarsError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::location::Point;
+    use crate::split::{combine_parameter_chunks, concat_frames, group_by_location, time_windows, ChunkSizes, SplitLimits};
+    use crate::util::TimeSeries;
+    use chrono::{Duration, TimeZone, Utc};
+    use polars::prelude::*;
+
+    fn time_series(hours: i64) -> TimeSeries {
+        let start = Utc.with_ymd_and_hms(2022, 5, 17, 0, 0, 0).unwrap();
+        TimeSeries { start, end: start + Duration::hours(hours), timedelta: Option::from(Duration::hours(1)) }
+    }
+
+    #[tokio::test]
+    async fn chunk_sizes_respect_counts_and_url_length() {
+        let parameters: Vec<String> = (0..12).map(|i| format!("t_{}m:C", i)).collect();
+        let points: Vec<Point> = (0..30).map(|i| Point { lat: 47.0 + i as f64, lon: 9.123456 }).collect();
+
+        let limits = SplitLimits { max_points: 20, max_time_steps: 10, max_url_length: 10_000, ..SplitLimits::default() };
+        let sizes = limits.chunk_sizes(30, &time_series(23), &parameters, &points);
+        assert_eq!(sizes, ChunkSizes { points: 20, parameters: 10, time_steps: Some(10) });
+        assert!(limits.requires_split(&sizes, points.len(), parameters.len(), &time_series(23)));
+
+        // A short URL limit halves the number of points per chunk.
+        let limits = SplitLimits { max_url_length: 250, ..limits };
+        let sizes = limits.chunk_sizes(30, &time_series(23), &parameters, &points);
+        assert_eq!(sizes.points, 5);
+
+        // Small queries are not split.
+        let limits = SplitLimits::default();
+        let sizes = limits.chunk_sizes(30, &time_series(23), &parameters[..2], &points[..2]);
+        assert!(!limits.requires_split(&sizes, 2, 2, &time_series(23)));
+    }
+
+    #[tokio::test]
+    async fn time_windows_do_not_overlap() {
+        let windows = time_windows(&time_series(23), 10);
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows[0].end + Duration::hours(1), windows[1].start);
+        assert_eq!(windows[2].start, time_series(23).start + Duration::hours(20));
+        assert_eq!(windows[2].end, time_series(23).end);
+    }
+
+    #[tokio::test]
+    async fn frames_are_combined_and_regrouped() {
+        let p1 = df!("lat" => &[1.0, 2.0], "lon" => &[5.0, 5.0], "validdate" => &["t1", "t1"], "t_2m:C" => &[10.0, 20.0]).unwrap();
+        let p2 = df!("lat" => &[1.0, 2.0], "lon" => &[5.0, 5.0], "validdate" => &["t1", "t1"], "precip_1h:mm" => &[0.1, 0.2]).unwrap();
+        let chunks = vec![vec![String::from("t_2m:C")], vec![String::from("precip_1h:mm")]];
+        let window1 = combine_parameter_chunks(vec![p1, p2], &chunks).unwrap();
+        assert_eq!(window1.get_column_names(), &["lat", "lon", "validdate", "t_2m:C", "precip_1h:mm"]);
+
+        // Chunks with the same length but other locations are not combined.
+        let p1 = df!("lat" => &[1.0, 2.0], "lon" => &[5.0, 5.0], "validdate" => &["t1", "t1"], "t_2m:C" => &[10.0, 20.0]).unwrap();
+        let p2 = df!("lat" => &[2.0, 1.0], "lon" => &[5.0, 5.0], "validdate" => &["t1", "t1"], "precip_1h:mm" => &[0.2, 0.1]).unwrap();
+        assert!(combine_parameter_chunks(vec![p1, p2], &chunks).is_err());
+
+        let window2 = df!("lat" => &[1.0, 2.0], "lon" => &[5.0, 5.0], "validdate" => &["t2", "t2"], "t_2m:C" => &[11.0, 21.0], "precip_1h:mm" => &[0.0, 0.0]).unwrap();
+        let df = group_by_location(concat_frames(vec![window1, window2]).unwrap()).unwrap();
+        let dates: Vec<&str> = df.column("validdate").unwrap().utf8().unwrap().into_no_null_iter().collect();
+        assert_eq!(dates, vec!["t1", "t2", "t1", "t2"]);
+        let lats: Vec<f64> = df.column("lat").unwrap().f64().unwrap().into_no_null_iter().collect();
+        assert_eq!(lats, vec![1.0, 1.0, 2.0, 2.0]);
+    }
+}
@@ -0,0 +1,657 @@
+//! # Testing
+//! An in-process fake of the Meteomatics API for tests of code that uses the [`crate::APIClient`].
+//! The module is only available with the ```test-support``` feature:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! meteomatics = { version = "0.2", features = ["test-support"] }
+//! ```
+//!
+//! The [`FakeServer`] listens on a random local port and implements the endpoints used by the client
+//! (see [`Endpoint`]). By default it answers with plausible data derived from the query (e.g. one row
+//! per requested point in time and location). Every endpoint can be programmed to answer differently,
+//! e.g. with an HTTP error, an empty lightning list or a truncated NetCDF file. Point the client to
+//! the server with [`crate::APIClientBuilder::base_url`] or use [`FakeServer::client_builder`].
+//!
+//! ```rust, no_run
+//! use meteomatics::testing::{Endpoint, FakeResponse, FakeServer};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let server = FakeServer::start().await;
+//!     server.respond(Endpoint::Lightning, FakeResponse::csv("stroke_time:sql;stroke_lat:d;stroke_lon:d;stroke_current:kA\n"));
+//!     server.respond_once(Endpoint::UserStats, FakeResponse::error(403, "Forbidden"));
+//!
+//!     let client = server.client_builder().build().unwrap();
+//!     assert!(client.query_user_features().await.is_err());
+//!     assert!(client.query_user_features().await.is_ok());
+//!     assert_eq!(server.requests().len(), 2);
+//! }
+//! ```
+
+use crate::APIClientBuilder;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+// A valid 1x1 pixel grayscale PNG.
+const PNG: [u8; 67] = [
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x00, 0x00, 0x00, 0x00, 0x3a, 0x7e, 0x9b,
+    0x55, 0x00, 0x00, 0x00, 0x0a, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x60, 0x00, 0x00, 0x00,
+    0x02, 0x00, 0x01, 0x48, 0xaf, 0xa4, 0x71, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae,
+    0x42, 0x60, 0x82,
+];
+
+// The header of an empty NetCDF classic file (no dimensions, attributes or variables).
+const NETCDF: [u8; 32] = [
+    b'C', b'D', b'F', 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// The endpoints of the API that the fake server distinguishes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    /// Time series CSV for points, postal codes or stations.
+    TimeSeries,
+    /// Route CSV (```?route=true```).
+    Route,
+    /// Pivoted grid CSV (a single parameter at a single point in time).
+    GridPivoted,
+    /// Unpivoted grid CSV (several parameters or points in time).
+    GridUnpivoted,
+    /// Grid as PNG.
+    Png,
+    /// Grid as NetCDF.
+    NetCdf,
+    /// ```find_station```.
+    FindStation,
+    /// ```get_lightning_list```.
+    Lightning,
+    /// ```get_init_date```.
+    InitDate,
+    /// ```get_time_range```.
+    TimeRange,
+    /// ```user_stats_json```.
+    UserStats,
+    /// The token endpoint (```api/v1/token```, see [`FakeServer::token_url`]).
+    Token,
+    /// Anything else, answered with 404 by default.
+    Unknown,
+}
+
+/// A request received by the fake server.
+#[derive(Clone, Debug)]
+pub struct FakeRequest {
+    /// The endpoint the request was routed to.
+    pub endpoint: Endpoint,
+    /// The decoded path segments (without the leading '/').
+    pub segments: Vec<String>,
+    /// The decoded query parameters.
+    pub query: Vec<(String, String)>,
+    /// The request headers (names in lower case).
+    pub headers: Vec<(String, String)>,
+}
+
+impl FakeRequest {
+    /// Returns the username sent with HTTP basic authentication.
+    pub fn username(&self) -> Option<String> {
+        let value = self.header("authorization")?.strip_prefix("Basic ")?;
+        let decoded = String::from_utf8(STANDARD.decode(value).ok()?).ok()?;
+        decoded.split(':').next().map(|user| user.to_string())
+    }
+
+    /// Returns the access token sent as ```Authorization: Bearer``` header or ```access_token```
+    /// query parameter.
+    pub fn access_token(&self) -> Option<&str> {
+        self.header("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .or_else(|| self.query_param("access_token"))
+    }
+
+    /// Returns the value of the first header with the given name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the value of the first query parameter with the given name.
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    // The time, parameters and location of queries of the form {time}/{parameters}/{location}/{format}.
+    fn query_parts(&self) -> Option<(&str, &str, &str)> {
+        match self.segments.len() {
+            n if n >= 4 => Some((&self.segments[n - 4], &self.segments[n - 3], &self.segments[n - 2])),
+            _ => None,
+        }
+    }
+}
+
+/// A programmed response of the fake server.
+#[derive(Clone, Debug)]
+pub struct FakeResponse {
+    /// The HTTP status code.
+    pub status: u16,
+    /// The response headers.
+    pub headers: Vec<(String, String)>,
+    /// The response body.
+    pub body: Vec<u8>,
+    /// Close the connection after this many bytes of the body, although the ```Content-Length```
+    /// announces the complete body.
+    pub truncate_after: Option<usize>,
+}
+
+impl FakeResponse {
+    /// A response with the given status, content type and body.
+    pub fn new(status: u16, content_type: &str, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            headers: vec![(String::from("content-type"), content_type.to_string())],
+            body,
+            truncate_after: None,
+        }
+    }
+
+    /// A successful CSV response.
+    pub fn csv(body: &str) -> Self {
+        Self::new(200, "text/csv", body.as_bytes().to_vec())
+    }
+
+    /// A successful JSON response.
+    pub fn json(body: &str) -> Self {
+        Self::new(200, "application/json", body.as_bytes().to_vec())
+    }
+
+    /// A successful binary response (e.g. PNG or NetCDF).
+    pub fn bytes(content_type: &str, body: Vec<u8>) -> Self {
+        Self::new(200, content_type, body)
+    }
+
+    /// An error response with a plain text body.
+    pub fn error(status: u16, message: &str) -> Self {
+        Self::new(status, "text/plain", message.as_bytes().to_vec())
+    }
+
+    /// Adds a header to the response.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Closes the connection after the given number of body bytes.
+    pub fn truncated(mut self, after: usize) -> Self {
+        self.truncate_after = Some(after);
+        self
+    }
+}
+
+type Handler = Arc<dyn Fn(&FakeRequest) -> FakeResponse + Send + Sync>;
+
+#[derive(Default)]
+struct State {
+    once: HashMap<Endpoint, VecDeque<FakeResponse>>,
+    handlers: HashMap<Endpoint, Handler>,
+    requests: Vec<FakeRequest>,
+}
+
+/// Handle to a running fake server. The server stops when the handle is dropped.
+pub struct FakeServer {
+    url: String,
+    state: Arc<Mutex<State>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl std::fmt::Debug for FakeServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("FakeServer").field("url", &self.url).finish()
+    }
+}
+
+impl FakeServer {
+    /// Binds to a random local port and starts serving.
+    pub async fn start() -> Self {
+        // Safe to unwrap, binding to a random local port only fails if the system is out of ports.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let shared = Arc::clone(&state);
+        let task = tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(serve(socket, Arc::clone(&shared)));
+            }
+        });
+        Self { url, state, task }
+    }
+
+    /// Returns the base URL of the server (e.g. ```http://127.0.0.1:34567/```).
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns a builder for a client that sends its queries to this server.
+    pub fn client_builder(&self) -> APIClientBuilder {
+        APIClientBuilder::new("fake_user", "fake_password").base_url(&self.url)
+    }
+
+    /// Answers all following requests to the endpoint with the given response.
+    pub fn respond(&self, endpoint: Endpoint, response: FakeResponse) {
+        self.respond_with(endpoint, move |_| response.clone());
+    }
+
+    /// Answers all following requests to the endpoint with the response computed by the handler.
+    pub fn respond_with<F>(&self, endpoint: Endpoint, handler: F)
+    where
+        F: Fn(&FakeRequest) -> FakeResponse + Send + Sync + 'static,
+    {
+        self.state.lock().unwrap().handlers.insert(endpoint, Arc::new(handler));
+    }
+
+    /// Answers the next request to the endpoint with the given response. Responses queued this way
+    /// are used in order before the programmed or default response.
+    pub fn respond_once(&self, endpoint: Endpoint, response: FakeResponse) {
+        self.state.lock().unwrap().once.entry(endpoint).or_default().push_back(response);
+    }
+
+    /// Restores the default responses of all endpoints.
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.once.clear();
+        state.handlers.clear();
+    }
+
+    /// Returns the URL of the token endpoint of the server (see [`crate::auth::TokenConfig`]).
+    pub fn token_url(&self) -> String {
+        format!("{}api/v1/token", self.url)
+    }
+
+    /// Returns all requests received so far.
+    pub fn requests(&self) -> Vec<FakeRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+// Reads a single request from the connection and answers it.
+async fn serve(mut socket: TcpStream, state: Arc<Mutex<State>>) {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 4096];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        match socket.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => head.extend_from_slice(&buf[..n]),
+        }
+    }
+    let request = match parse_request(&String::from_utf8_lossy(&head)) {
+        Some(request) => request,
+        None => return,
+    };
+
+    let response = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(request.clone());
+        let queued = state.once.get_mut(&request.endpoint).and_then(|queue| queue.pop_front());
+        match (queued, state.handlers.get(&request.endpoint)) {
+            (Some(response), _) => response,
+            (None, Some(handler)) => handler(&request),
+            (None, None) => default_response(&request),
+        }
+    };
+
+    let mut out = format!(
+        "HTTP/1.1 {} Fake\r\ncontent-length: {}\r\nconnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        out.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    out.push_str("\r\n");
+    let body = match response.truncate_after {
+        Some(n) => &response.body[..n.min(response.body.len())],
+        None => &response.body[..],
+    };
+    let _ = socket.write_all(out.as_bytes()).await;
+    let _ = socket.write_all(body).await;
+    let _ = socket.shutdown().await;
+}
+
+fn parse_request(head: &str) -> Option<FakeRequest> {
+    let mut lines = head.split("\r\n");
+    let target = lines.next()?.split(' ').nth(1)?;
+    let url = url::Url::parse(&format!("http://fake{}", target)).ok()?;
+    let segments: Vec<String> = url
+        .path_segments()?
+        .filter(|s| !s.is_empty())
+        .map(decode_segment)
+        .collect();
+    // The '+' of UTC offsets in query parameters is not encoded by the client.
+    let query = url.query().map(|q| url::form_urlencoded::parse(q.replace('+', "%2B").as_bytes()).into_owned().collect()).unwrap_or_default();
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let mut request = FakeRequest { endpoint: Endpoint::Unknown, segments, query, headers };
+    request.endpoint = route(&request);
+    Some(request)
+}
+
+// Decodes a path segment. The '+' is kept, it only stands for a space in query strings.
+fn decode_segment(segment: &str) -> String {
+    let escaped = segment.replace('+', "%2B").replace('&', "%26");
+    url::form_urlencoded::parse(format!("s={}", escaped).as_bytes())
+        .next()
+        .map(|(_, value)| value.into_owned())
+        .unwrap_or_default()
+}
+
+// Determines the endpoint from the path of the request.
+fn route(request: &FakeRequest) -> Endpoint {
+    match request.segments.last().map(|s| s.as_str()) {
+        Some("user_stats_json") => return Endpoint::UserStats,
+        Some("token") => return Endpoint::Token,
+        Some("find_station") => return Endpoint::FindStation,
+        Some("get_lightning_list") => return Endpoint::Lightning,
+        Some("get_init_date") => return Endpoint::InitDate,
+        Some("get_time_range") => return Endpoint::TimeRange,
+        _ => {}
+    }
+    let (time, parameters, location) = match request.query_parts() {
+        Some(parts) => parts,
+        None => return Endpoint::Unknown,
+    };
+    match request.segments.last().map(|s| s.as_str()) {
+        Some("png") => Endpoint::Png,
+        Some("netcdf") => Endpoint::NetCdf,
+        Some("csv") if request.query_param("route") == Some("true") => Endpoint::Route,
+        Some("csv") if parse_bbox(location).is_some() => {
+            if parameters.contains(',') || time.contains("--") || time.contains(',') {
+                Endpoint::GridUnpivoted
+            } else {
+                Endpoint::GridPivoted
+            }
+        }
+        Some("csv") => Endpoint::TimeSeries,
+        _ => Endpoint::Unknown,
+    }
+}
+
+// Generates the default response of the endpoint from the query.
+fn default_response(request: &FakeRequest) -> FakeResponse {
+    let parts = request.query_parts();
+    let dates = parts.map(|(time, _, _)| expand_dates(time)).unwrap_or_default();
+    let parameters: Vec<&str> = parts.map(|(_, p, _)| p.split(',').collect()).unwrap_or_default();
+    let location = parts.map(|(_, _, l)| l).unwrap_or_default();
+
+    match request.endpoint {
+        Endpoint::TimeSeries => {
+            let locations: Vec<&str> = location.split('+').collect();
+            let mut header = Vec::new();
+            if locations.len() > 1 {
+                match parse_point(locations[0]) {
+                    Some(_) => header.extend(["lat", "lon"]),
+                    None => header.push("station_id"),
+                }
+            }
+            header.push("validdate");
+            header.extend(&parameters);
+            let mut csv = header.join(";") + "\n";
+            for loc in &locations {
+                for (i, date) in dates.iter().enumerate() {
+                    let mut row = Vec::new();
+                    if locations.len() > 1 {
+                        match parse_point(loc) {
+                            Some((lat, lon)) => row.extend([lat.to_string(), lon.to_string()]),
+                            None => row.push(loc.to_string()),
+                        }
+                    }
+                    row.push(format_date(date));
+                    row.extend((0..parameters.len()).map(|p| value(i, p)));
+                    csv.push_str(&(row.join(";") + "\n"));
+                }
+            }
+            FakeResponse::csv(&csv)
+        }
+        Endpoint::Route => {
+            let mut csv = format!("lat;lon;validdate;{}\n", parameters.join(";"));
+            for (i, (loc, date)) in location.split('+').zip(dates.iter()).enumerate() {
+                let (lat, lon) = parse_point(loc).unwrap_or((0.0, 0.0));
+                let values: Vec<String> = (0..parameters.len()).map(|p| value(i, p)).collect();
+                csv.push_str(&format!("{};{};{};{}\n", lat, lon, format_date(date), values.join(";")));
+            }
+            FakeResponse::csv(&csv)
+        }
+        Endpoint::GridPivoted => {
+            let (lats, lons) = parse_bbox(location).unwrap_or_default();
+            let date = dates.first().map(format_date).unwrap_or_default();
+            let lon_header: Vec<String> = lons.iter().map(|lon| lon.to_string()).collect();
+            let mut csv = format!("data_source;fake\nvalid_date;{}\ndata;{}\n", date, lon_header.join(";"));
+            for (i, lat) in lats.iter().enumerate() {
+                let values: Vec<String> = (0..lons.len()).map(|j| value(i, j)).collect();
+                csv.push_str(&format!("{};{}\n", lat, values.join(";")));
+            }
+            FakeResponse::csv(&csv)
+        }
+        Endpoint::GridUnpivoted => {
+            let (lats, lons) = parse_bbox(location).unwrap_or_default();
+            let mut csv = format!("lat;lon;validdate;{}\n", parameters.join(";"));
+            let mut i = 0;
+            for lat in &lats {
+                for lon in &lons {
+                    for date in &dates {
+                        let values: Vec<String> = (0..parameters.len()).map(|p| value(i, p)).collect();
+                        csv.push_str(&format!("{};{};{};{}\n", lat, lon, format_date(date), values.join(";")));
+                        i += 1;
+                    }
+                }
+            }
+            FakeResponse::csv(&csv)
+        }
+        Endpoint::Png => FakeResponse::bytes("image/png", PNG.to_vec()),
+        Endpoint::NetCdf => FakeResponse::bytes("application/netcdf", NETCDF.to_vec()),
+        Endpoint::FindStation => FakeResponse::csv(
+            "Station Category;Station Type;ID Hash;WMO ID;Alternative IDs;Name;Location Lat,Lon;Elevation;Start Date;End Date;Horizontal Distance;Vertical Distance;Effective Distance\n\
+             SYNOP;SYNA;1234567890;66810;;Fake Station;47.4,9.4;779m;2000-01-01T00:00:00Z;2100-01-01T00:00:00Z;0;0;0\n"
+        ),
+        Endpoint::Lightning => {
+            let time_range = request.query_param("time_range").unwrap_or_default();
+            let start = expand_dates(time_range).first().map(format_date).unwrap_or_default();
+            let (lat, lon) = request
+                .query_param("bounding_box")
+                .and_then(|b| b.split('_').next())
+                .and_then(parse_point)
+                .unwrap_or((0.0, 0.0));
+            FakeResponse::csv(&format!(
+                "stroke_time:sql;stroke_lat:d;stroke_lon:d;stroke_current:kA\n{};{};{};-12.5\n",
+                start, lat, lon
+            ))
+        }
+        Endpoint::InitDate => {
+            // Every valid time is covered by the 00 UTC run of the same day.
+            let valid_dates = expand_dates(request.query_param("valid_date").unwrap_or_default());
+            let parameters = request.query_param("parameters").unwrap_or_default();
+            let mut csv = format!("validdate;{}\n", parameters.replace(',', ";"));
+            for date in &valid_dates {
+                let run = date.date_naive().and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+                csv += &format_date(date);
+                for _ in parameters.split(',') {
+                    csv += &format!(";{}", format_date(&run));
+                }
+                csv += "\n";
+            }
+            FakeResponse::csv(&csv)
+        }
+        Endpoint::TimeRange => {
+            let mut csv = String::from("parameter;min_date;max_date\n");
+            for parameter in request.query_param("parameters").unwrap_or_default().split(',') {
+                csv += &format!("{};2000-01-01T00:00:00Z;2100-01-01T00:00:00Z\n", parameter);
+            }
+            FakeResponse::csv(&csv)
+        }
+        Endpoint::UserStats => {
+            let username = request.username().unwrap_or_default();
+            let limit = |hard: u32| format!(r#"{{"used": 0, "soft limit": 0, "hard limit": {}}}"#, hard);
+            FakeResponse::json(&format!(
+                r#"{{"message": "", "user statistics": {{"username": "{}", "requests total": {}, "requests since last UTC midnight": {}, "requests since HH:00:00": {}, "requests in the last 60 seconds": {}, "requests in parallel": {}, "historic request option": "-infinity--+infinity", "area request option": true, "model set": ["mix"], "error message": "", "contact emails": []}}}}"#,
+                username, limit(0), limit(0), limit(0), limit(6000), limit(500)
+            ))
+        }
+        Endpoint::Token => FakeResponse::json(&format!(
+            r#"{{"access_token": "fake-token-{:016x}", "token_type": "bearer"}}"#,
+            rand::random::<u64>()
+        )),
+        Endpoint::Unknown => FakeResponse::error(404, "Not Found"),
+    }
+}
+
+// Deterministic values that differ between rows and parameters.
+fn value(row: usize, parameter: usize) -> String {
+    format!("{:.1}", (row % 100) as f64 * 0.5 + parameter as f64)
+}
+
+fn format_date(date: &chrono::DateTime<chrono::Utc>) -> String {
+    date.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+fn parse_point(spec: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = spec.split_once(',')?;
+    Some((lat.parse().ok()?, lon.parse().ok()?))
+}
+
+// Returns the latitudes (north to south) and longitudes (west to east) of a bounding box of the form
+// "lat_max,lon_min_lat_min,lon_max:lat_res,lon_res".
+fn parse_bbox(spec: &str) -> Option<(Vec<f64>, Vec<f64>)> {
+    let (corners, resolution) = spec.split_once(':')?;
+    let (north_west, south_east) = corners.split_once('_')?;
+    let (lat_max, lon_min) = parse_point(north_west)?;
+    let (lat_min, lon_max) = parse_point(south_east)?;
+    let (lat_res, lon_res) = parse_point(resolution)?;
+    if lat_res <= 0.0 || lon_res <= 0.0 {
+        return None;
+    }
+    let steps = |from: f64, to: f64, res: f64| ((to - from) / res).round().max(0.0) as usize;
+    let round = |x: f64| (x * 1e6).round() / 1e6;
+    let lats = (0..=steps(lat_min, lat_max, lat_res)).map(|i| round(lat_max - i as f64 * lat_res)).collect();
+    let lons = (0..=steps(lon_min, lon_max, lon_res)).map(|i| round(lon_min + i as f64 * lon_res)).collect();
+    Some((lats, lons))
+}
+
+// Expands a time specification (a date, a list of dates or "start--end:step") into the points in time.
+fn expand_dates(spec: &str) -> Vec<chrono::DateTime<chrono::Utc>> {
+    let parse = |s: &str| chrono::DateTime::parse_from_rfc3339(s).ok().map(|d| d.with_timezone(&chrono::Utc));
+    let mut dates = Vec::new();
+    for part in spec.split(',') {
+        match part.split_once("--") {
+            Some((start, rest)) => {
+                let (end, step) = match rest.split_once(":P") {
+                    Some((end, step)) => (end, parse_step(step)),
+                    None => (rest, None),
+                };
+                let (Some(start), Some(end)) = (parse(start), parse(end)) else { continue };
+                let step = step.filter(|s| *s > chrono::Duration::zero()).unwrap_or(end - start);
+                let mut date = start;
+                while date <= end {
+                    dates.push(date);
+                    if step.is_zero() {
+                        break;
+                    }
+                    date += step;
+                }
+            }
+            None => dates.extend(parse(part)),
+        }
+    }
+    dates
+}
+
+// Parses the time step of a time series after the 'P'. The client writes the step with the
+// ```Display``` of ```chrono::Duration```, which always uses seconds (e.g. "T3600S" or "T1.5S").
+fn parse_step(spec: &str) -> Option<chrono::Duration> {
+    let seconds: f64 = spec.strip_prefix('T')?.strip_suffix('S')?.parse().ok()?;
+    Some(chrono::Duration::milliseconds((seconds * 1000.0) as i64))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::errors::ConnectorError;
+    use crate::location::{BBox, Point};
+    use crate::testing::{decode_segment, parse_step, Endpoint, FakeResponse, FakeServer};
+    use crate::util::TimeSeries;
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[tokio::test]
+    async fn default_responses_follow_the_query() {
+        let server = FakeServer::start().await;
+        let client = server.client_builder().build().unwrap();
+        let start_date = Utc.with_ymd_and_hms(2022, 5, 17, 0, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date + Duration::days(1), timedelta: Some(Duration::hours(6)) };
+        let parameters = vec![String::from("t_2m:C"), String::from("precip_1h:mm")];
+
+        let coordinates = vec![Point { lat: 47.42, lon: 9.37 }, Point { lat: 46.21, lon: 6.14 }];
+        let df = client.query_time_series(&time_series, &parameters, &coordinates, &None).await.unwrap();
+        assert_eq!(df.shape(), (2 * 5, 5));
+
+        let bbox = BBox { lat_min: 47.0, lat_max: 47.5, lon_min: 8.0, lon_max: 9.0, lat_res: 0.5, lon_res: 0.5 };
+        let df = client.query_grid_pivoted(&start_date, &parameters[0], &bbox, &None).await.unwrap();
+        assert_eq!(df.shape(), (2, 4));
+        let df = client.query_grid_unpivoted(&start_date, &parameters, &bbox, &None).await.unwrap();
+        assert_eq!(df.shape(), (6, 5));
+        let df = client.query_lightning(&time_series, &bbox).await.unwrap();
+        assert_eq!(df.height(), 1);
+
+        let stats = client.query_user_features().await.unwrap();
+        assert_eq!(stats.stats.username, "fake_user");
+
+        let endpoints: Vec<Endpoint> = server.requests().iter().map(|r| r.endpoint).collect();
+        assert_eq!(endpoints, vec![
+            Endpoint::TimeSeries, Endpoint::GridPivoted, Endpoint::GridUnpivoted, Endpoint::Lightning, Endpoint::UserStats
+        ]);
+    }
+
+    #[tokio::test]
+    async fn responses_are_programmable() {
+        let server = FakeServer::start().await;
+        let client = server.client_builder().build().unwrap();
+        let start_date = Utc.with_ymd_and_hms(2022, 5, 17, 0, 0, 0).unwrap();
+        let time_series = TimeSeries { start: start_date, end: start_date + Duration::hours(1), timedelta: Some(Duration::hours(1)) };
+        let bbox = BBox { lat_min: 47.0, lat_max: 47.5, lon_min: 8.0, lon_max: 9.0, lat_res: 0.5, lon_res: 0.5 };
+
+        server.respond(Endpoint::Lightning, FakeResponse::csv("stroke_time:sql;stroke_lat:d;stroke_lon:d;stroke_current:kA\n"));
+        assert_eq!(client.query_lightning(&time_series, &bbox).await.unwrap().height(), 0);
+
+        server.respond_once(Endpoint::UserStats, FakeResponse::error(403, "Forbidden"));
+        let result = client.query_user_features().await;
+        assert!(matches!(result, Err(ConnectorError::HttpError(_, _, status)) if status == 403));
+        assert!(client.query_user_features().await.is_ok());
+
+        server.respond_with(Endpoint::Png, |request| {
+            FakeResponse::bytes("image/png", request.segments[1].as_bytes().to_vec()).truncated(2)
+        });
+        let response = reqwest::get(format!("{}2022-05-17T00:00:00Z/t_2m:C/47,8_46,9:1,1/png", server.url())).await.unwrap();
+        assert!(response.bytes().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn segments_and_steps_are_decoded() {
+        assert_eq!(decode_segment("2022-05-17T00:00:00+00:00"), "2022-05-17T00:00:00+00:00");
+        assert_eq!(decode_segment("postal_CH%209000"), "postal_CH 9000");
+        for step in [Duration::hours(6), Duration::days(2), Duration::milliseconds(1500)] {
+            let spec = step.to_string();
+            assert_eq!(parse_step(spec.strip_prefix('P').unwrap()), Some(step));
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! # Tiling
+//! Unpivoted grid queries send the complete [`BBox`] in a single request. For continental areas at a
+//! fine resolution the request either fails or times out. With [`TileLimits`] configured on the
+//! client (see [`crate::APIClientBuilder::grid_tiling`]) the bounding box is split into tiles that
+//! are aligned to the resolution grid of the original box. The tiles are fetched concurrently and
+//! stitched back into a single [`DataFrame`]. Neighbouring tiles do not share grid rows or columns,
+//! such that the stitched DataFrame contains every grid point exactly once.
+//!
+//! ```rust, no_run
+//! use meteomatics::{APIClient, TileLimits};
+//!
+//! let client = APIClient::builder("ferris_loves_rustaceans", "0123456789")
+//!     .grid_tiling(TileLimits { max_rows: 200, max_cols: 200, ..TileLimits::default() })
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use crate::errors::ConnectorError;
+use crate::location::BBox;
+use crate::split::concat_frames;
+use polars::prelude::*;
+
+// Coordinates of the tiles are rounded to this precision to avoid floating point artefacts
+// (e.g. 47.500000000001) in the query.
+const PRECISION: f64 = 1e9;
+
+/// Maximum size of a tile (in grid points) and the number of tiles requested at the same time.
+#[derive(Clone, Debug)]
+pub struct TileLimits {
+    /// Maximum number of grid rows (latitudes) per tile.
+    pub max_rows: usize,
+    /// Maximum number of grid columns (longitudes) per tile.
+    pub max_cols: usize,
+    /// Maximum number of tiles requested at the same time.
+    pub concurrency: usize,
+}
+
+impl Default for TileLimits {
+    fn default() -> Self {
+        Self {
+            max_rows: 500,
+            max_cols: 500,
+            concurrency: 4,
+        }
+    }
+}
+
+// Tolerance for spans that are a multiple of the resolution up to floating point errors (e.g. 2.0 / 0.1).
+const EPSILON: f64 = 1e-9;
+
+/// Returns the number of grid rows (latitudes) and columns (longitudes) of a bounding box. The grid
+/// starts at the north west corner, a span that is not a multiple of the resolution ends at the last
+/// grid point inside the box.
+pub fn grid_shape(bbox: &BBox) -> Option<(usize, usize)> {
+    if bbox.lat_res <= 0.0 || bbox.lon_res <= 0.0 {
+        return None;
+    }
+    let count = |span: f64, res: f64| (span / res + EPSILON).floor().max(0.0) as usize + 1;
+    Some((count(bbox.lat_max - bbox.lat_min, bbox.lat_res), count(bbox.lon_max - bbox.lon_min, bbox.lon_res)))
+}
+
+/// Splits a bounding box into tiles with at most ```max_rows``` x ```max_cols``` grid points. The
+/// tiles are ordered from north to south and west to east. A box without a valid resolution or one
+/// that already fits is returned as a single tile.
+pub fn tiles(bbox: &BBox, limits: &TileLimits) -> Vec<BBox> {
+    let Some((rows, cols)) = grid_shape(bbox) else {
+        return vec![*bbox];
+    };
+    let max_rows = limits.max_rows.max(1);
+    let max_cols = limits.max_cols.max(1);
+
+    let mut tiles = Vec::new();
+    for first_row in (0..rows).step_by(max_rows) {
+        let last_row = (first_row + max_rows - 1).min(rows - 1);
+        for first_col in (0..cols).step_by(max_cols) {
+            let last_col = (first_col + max_cols - 1).min(cols - 1);
+            tiles.push(BBox {
+                lat_max: round(bbox.lat_max - first_row as f64 * bbox.lat_res),
+                lat_min: if last_row == rows - 1 { bbox.lat_min } else { round(bbox.lat_max - last_row as f64 * bbox.lat_res) },
+                lon_min: round(bbox.lon_min + first_col as f64 * bbox.lon_res),
+                lon_max: if last_col == cols - 1 { bbox.lon_max } else { round(bbox.lon_min + last_col as f64 * bbox.lon_res) },
+                lat_res: bbox.lat_res,
+                lon_res: bbox.lon_res,
+            });
+        }
+    }
+    tiles
+}
+
+fn round(value: f64) -> f64 {
+    (value * PRECISION).round() / PRECISION
+}
+
+/// Stitches the results of the tiles into a single DataFrame sorted from north to south, west to
+/// east and by time. Grid points that were returned by more than one tile (e.g. if the API snaps the
+/// tile edges to its own grid) are only kept once.
+pub fn stitch_tiles(frames: Vec<DataFrame>) -> std::result::Result<DataFrame, ConnectorError> {
+    let df = concat_frames(frames)?;
+    let keys: Vec<String> = ["lat", "lon", "validdate"]
+        .iter()
+        .filter(|key| df.get_column_names().contains(key))
+        .map(|key| key.to_string())
+        .collect();
+    let df = df
+        .unique_stable(Some(&keys), UniqueKeepStrategy::First)
+        .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
+    let reverse: Vec<bool> = keys.iter().map(|key| key == "lat").collect();
+    df.sort(keys, reverse).map_err(|e| ConnectorError::PolarsError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::location::BBox;
+    use crate::tiling::{grid_shape, stitch_tiles, tiles, TileLimits};
+    use polars::prelude::*;
+
+    #[tokio::test]
+    async fn tiles_are_aligned_and_disjoint() {
+        let bbox = BBox { lat_min: 45.8, lat_max: 47.8, lon_min: 6.0, lon_max: 10.5, lat_res: 0.1, lon_res: 0.1 };
+        assert_eq!(grid_shape(&bbox), Some((21, 46)));
+
+        let limits = TileLimits { max_rows: 10, max_cols: 20, ..TileLimits::default() };
+        let tiles = tiles(&bbox, &limits);
+        assert_eq!(tiles.len(), 3 * 3);
+
+        // Every grid point is covered exactly once.
+        let points: usize = tiles.iter().map(|t| grid_shape(t).map(|(r, c)| r * c).unwrap()).sum();
+        assert_eq!(points, 21 * 46);
+
+        // The first tile starts in the north west, the next row of tiles one grid step further south.
+        assert_eq!(format!("{}", tiles[0]), "47.8,6_46.9,7.9:0.1,0.1");
+        assert_eq!(tiles[3].lat_max, 46.8);
+        assert_eq!(tiles[8].lat_min, 45.8);
+        assert_eq!(tiles[8].lon_max, 10.5);
+    }
+
+    #[tokio::test]
+    async fn unaligned_boxes_stay_inside_the_box() {
+        // The span of 3.6 degrees holds only 4 grid rows (3.6, 2.6, 1.6 and 0.6).
+        let bbox = BBox { lat_min: 0.0, lat_max: 3.6, lon_min: 0.0, lon_max: 1.0, lat_res: 1.0, lon_res: 1.0 };
+        assert_eq!(grid_shape(&bbox), Some((4, 2)));
+
+        let limits = TileLimits { max_rows: 4, ..TileLimits::default() };
+        assert_eq!(tiles(&bbox, &limits), vec![bbox]);
+
+        let limits = TileLimits { max_rows: 3, ..TileLimits::default() };
+        let tiles = tiles(&bbox, &limits);
+        assert_eq!(tiles.len(), 2);
+        assert!(tiles.iter().all(|t| t.lat_min <= t.lat_max && t.lat_min >= bbox.lat_min));
+        assert_eq!((tiles[1].lat_max, tiles[1].lat_min), (0.6, 0.0));
+    }
+
+    #[tokio::test]
+    async fn small_boxes_are_not_tiled() {
+        let bbox = BBox { lat_min: 52.4, lat_max: 52.5, lon_min: 13.4, lon_max: 13.5, lat_res: 0.05, lon_res: 0.05 };
+        assert_eq!(tiles(&bbox, &TileLimits::default()), vec![bbox]);
+    }
+
+    #[tokio::test]
+    async fn stitching_removes_duplicated_edges() {
+        let north = df!("lat" => &[2.0, 2.0, 1.0], "lon" => &[5.0, 6.0, 5.0], "validdate" => &["t1", "t1", "t1"], "t_2m:C" => &[1.0, 2.0, 3.0]).unwrap();
+        let south = df!("lat" => &[1.0, 0.0], "lon" => &[5.0, 5.0], "validdate" => &["t1", "t1"], "t_2m:C" => &[3.0, 4.0]).unwrap();
+        let df = stitch_tiles(vec![south, north]).unwrap();
+        assert_eq!(df.height(), 4);
+        let lats: Vec<f64> = df.column("lat").unwrap().f64().unwrap().into_no_null_iter().collect();
+        assert_eq!(lats, vec![2.0, 2.0, 1.0, 0.0]);
+    }
+}
@@ -0,0 +1,171 @@
+//! # Units
+//! Converts the values of a DataFrame column to another unit without querying the API again (e.g.
+//! ```t_2m:C``` to ```t_2m:F``` or ```wind_speed_10m:ms``` to ```wind_speed_10m:kn```). The unit is
+//! taken from the column name (see [`crate::parameter::Parameter`]) and the name is rewritten
+//! together with the values.
+//!
+//! Supported are temperature (```C```, ```F```, ```K```), speed (```ms```, ```kmh```, ```kn```,
+//! ```mph```), pressure (```Pa```, ```hPa```, ```kPa```, ```bar```, ```psi```, ```inHg```),
+//! precipitation (```mm```, ```cm```, ```in```, ```kgm2```), length (```mm```, ```cm```, ```m```,
+//! ```km```, ```in```, ```ft```, ```mi```, ```nmi```) and energy (```J```, ```Ws```, ```kJ```,
+//! ```MJ```, ```Wh```, ```kWh```). Non-linear scales like Beaufort are not supported.
+//!
+//! ```rust
+//! use meteomatics::units::convert_column;
+//! use polars::prelude::*;
+//!
+//! let mut df = df!("validdate" => &["1989-11-09T18:00:00Z"], "t_2m:C" => &[20.0]).unwrap();
+//! let column = convert_column(&mut df, "t_2m:C", "F").unwrap();
+//! assert_eq!(column, "t_2m:F");
+//! let value = df.column("t_2m:F").unwrap().f64().unwrap().get(0).unwrap();
+//! assert!((value - 68.0).abs() < 1e-9);
+//! ```
+
+use crate::errors::ConnectorError;
+use crate::parameter::Parameter;
+use polars::prelude::*;
+
+/// Value of the API for invalid data (see [`crate::options::OnInvalid`]), which is never converted.
+pub const INVALID_VALUE: f64 = -999.0;
+
+// Units of a quantity with the factor and offset to its base unit: base = value * factor + offset.
+const TEMPERATURE: &[(&str, f64, f64)] = &[("K", 1.0, 0.0), ("C", 1.0, 273.15), ("F", 5.0 / 9.0, 273.15 - 32.0 * 5.0 / 9.0)];
+const SPEED: &[(&str, f64, f64)] = &[("ms", 1.0, 0.0), ("kmh", 1.0 / 3.6, 0.0), ("kn", 1852.0 / 3600.0, 0.0), ("mph", 0.44704, 0.0)];
+const PRESSURE: &[(&str, f64, f64)] = &[
+    ("Pa", 1.0, 0.0), ("hPa", 100.0, 0.0), ("kPa", 1000.0, 0.0), ("bar", 100_000.0, 0.0),
+    ("psi", 6894.757, 0.0), ("inHg", 3386.389, 0.0),
+];
+const PRECIPITATION: &[(&str, f64, f64)] = &[("mm", 1.0, 0.0), ("kgm2", 1.0, 0.0), ("cm", 10.0, 0.0), ("in", 25.4, 0.0)];
+const LENGTH: &[(&str, f64, f64)] = &[
+    ("m", 1.0, 0.0), ("mm", 0.001, 0.0), ("cm", 0.01, 0.0), ("km", 1000.0, 0.0), ("in", 0.0254, 0.0),
+    ("ft", 0.3048, 0.0), ("mi", 1609.344, 0.0), ("nmi", 1852.0, 0.0),
+];
+const ENERGY: &[(&str, f64, f64)] = &[
+    ("J", 1.0, 0.0), ("Ws", 1.0, 0.0), ("kJ", 1000.0, 0.0), ("MJ", 1_000_000.0, 0.0), ("Wh", 3600.0, 0.0),
+    ("kWh", 3_600_000.0, 0.0),
+];
+const QUANTITIES: &[&[(&str, f64, f64)]] = &[TEMPERATURE, SPEED, PRESSURE, PRECIPITATION, LENGTH, ENERGY];
+
+/// A linear conversion between two units: ```to = from * factor + offset```.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Conversion {
+    /// Factor of the conversion.
+    pub factor: f64,
+    /// Offset of the conversion (only for temperatures).
+    pub offset: f64,
+}
+
+impl Conversion {
+    /// Returns the conversion between two units of the same quantity. Fails with
+    /// [`ConnectorError::UnsupportedConversion`] if the units are unknown or of different quantities.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The unit of the values (e.g. "C").
+    /// * `to` - The requested unit (e.g. "F").
+    pub fn between(from: &str, to: &str) -> std::result::Result<Self, ConnectorError> {
+        QUANTITIES
+            .iter()
+            .find_map(|units| {
+                let (_, from_factor, from_offset) = units.iter().find(|(unit, _, _)| *unit == from)?;
+                let (_, to_factor, to_offset) = units.iter().find(|(unit, _, _)| *unit == to)?;
+                Some(Self { factor: from_factor / to_factor, offset: (from_offset - to_offset) / to_factor })
+            })
+            .ok_or_else(|| ConnectorError::UnsupportedConversion(from.to_string(), to.to_string()))
+    }
+
+    /// Converts a single value.
+    pub fn apply(&self, value: f64) -> f64 {
+        value * self.factor + self.offset
+    }
+}
+
+/// Converts a value between two units (see [`Conversion::between`]).
+///
+/// # Arguments
+///
+/// * `value` - The value to convert.
+/// * `from` - The unit of the value (e.g. "ms").
+/// * `to` - The requested unit (e.g. "kn").
+pub fn convert_value(value: f64, from: &str, to: &str) -> std::result::Result<f64, ConnectorError> {
+    Ok(Conversion::between(from, to)?.apply(value))
+}
+
+/// Converts the values of a parameter column to another unit and renames the column accordingly
+/// (e.g. ```t_2m:C``` to ```t_2m:F```, or ```t_2m:C-m1``` to ```t_2m:F-m1``` for ensemble members).
+/// Missing values and the invalid value of the API (-999) are kept. Returns the new name of the
+/// column.
+///
+/// # Arguments
+///
+/// * `df` - The DataFrame with the column.
+/// * `column` - The name of the column (e.g. "t_2m:C").
+/// * `unit` - The requested unit (e.g. "F").
+pub fn convert_column(df: &mut DataFrame, column: &str, unit: &str) -> std::result::Result<String, ConnectorError> {
+    let polars_error = |e: PolarsError| ConnectorError::PolarsError(e.to_string());
+    let parameter = Parameter::from_column(column).ok_or_else(|| {
+        ConnectorError::LibraryError(format!("Column {} is not named after a parameter", column))
+    })?;
+    let conversion = Conversion::between(&parameter.unit, unit)?;
+    // Keep the suffix of ensemble columns (e.g. "-m1").
+    let suffix = &column[parameter.to_string().len().min(column.len())..];
+    let name = format!("{}{}", parameter.with_unit(unit), suffix);
+
+    let index = df.find_idx_by_name(column).ok_or_else(|| {
+        ConnectorError::PolarsError(format!("Column {} not found", column))
+    })?;
+    if name != column && df.find_idx_by_name(&name).is_some() {
+        return Err(ConnectorError::LibraryError(format!("Column {} already exists", name)));
+    }
+    let values = df.column(column).and_then(|s| s.cast(&DataType::Float64)).map_err(polars_error)?;
+    let mut converted = values
+        .f64()
+        .map_err(polars_error)?
+        .apply(|value| if value == INVALID_VALUE { value } else { conversion.apply(value) })
+        .into_series();
+    converted.rename(&name);
+    df.replace_at_idx(index, converted).map_err(polars_error)?;
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::errors::ConnectorError;
+    use crate::units::{convert_column, convert_value};
+    use polars::prelude::*;
+
+    fn assert_close(value: f64, expected: f64) {
+        assert!((value - expected).abs() < 1e-9, "{} != {}", value, expected);
+    }
+
+    #[tokio::test]
+    async fn values_and_columns_are_converted() {
+        assert_close(convert_value(0.0, "C", "K").unwrap(), 273.15);
+        assert_close(convert_value(-40.0, "C", "F").unwrap(), -40.0);
+        assert_close(convert_value(212.0, "F", "C").unwrap(), 100.0);
+        assert_close(convert_value(10.0, "ms", "kmh").unwrap(), 36.0);
+        assert_close(convert_value(1852.0, "m", "nmi").unwrap(), 1.0);
+        assert_close(convert_value(1013.25, "hPa", "Pa").unwrap(), 101_325.0);
+        assert_close(convert_value(25.4, "mm", "in").unwrap(), 1.0);
+        assert_close(convert_value(1.0, "kWh", "MJ").unwrap(), 3.6);
+        assert!(matches!(convert_value(1.0, "C", "ms"), Err(ConnectorError::UnsupportedConversion(_, _))));
+        assert!(matches!(convert_value(1.0, "bft", "ms"), Err(ConnectorError::UnsupportedConversion(_, _))));
+
+        let mut df = df!(
+            "validdate" => &["t1", "t2"],
+            "t_2m:C" => &[Some(10), None],
+            "wind_speed_10m:ms-m1" => &[10.0, -999.0]
+        ).unwrap();
+        assert_eq!(convert_column(&mut df, "t_2m:C", "K").unwrap(), "t_2m:K");
+        assert_eq!(convert_column(&mut df, "wind_speed_10m:ms-m1", "kn").unwrap(), "wind_speed_10m:kn-m1");
+        assert_eq!(df.get_column_names(), &["validdate", "t_2m:K", "wind_speed_10m:kn-m1"]);
+        let t: Vec<Option<f64>> = df.column("t_2m:K").unwrap().f64().unwrap().into_iter().collect();
+        assert_eq!(t, vec![Some(283.15), None]);
+        let wind: Vec<f64> = df.column("wind_speed_10m:kn-m1").unwrap().f64().unwrap().into_no_null_iter().collect();
+        assert_close(wind[0], 19.438444924406046);
+        assert_eq!(wind[1], -999.0);
+
+        assert!(convert_column(&mut df, "validdate", "K").is_err());
+    }
+}
@@ -7,18 +7,22 @@
 
 // Crates
 use serde::{Deserialize, Serialize};
-use std::fs::File;
 use reqwest::Response;
+use bytes::Bytes;
+use futures::stream::{BoxStream, Stream, StreamExt};
 use url::{ParseError, Url};
-use crate::errors::ConnectorError;
+use crate::availability::format_query_date;
+use crate::errors::{parse_api_error, ConnectorError};
+use crate::download::stream_to_file;
 use std::path::Path;
 use std::fs;
 use polars::prelude::*;
 use crate::location::Point;
+use crate::options::{append_options, ToQueryOptions};
 use std::fmt;
 
 // Default API URL
-const BASE_URL: &str = "https://api.meteomatics.com";
+pub(crate) const BASE_URL: &str = "https://api.meteomatics.com";
 
 /// Container for time series information. This allows functions to use less parameters. 
 /// 
@@ -42,7 +46,7 @@ const BASE_URL: &str = "https://api.meteomatics.com";
 /// 
 /// println!("Time series: {}", time_series);
 /// ```
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct TimeSeries{
     pub start: chrono::DateTime<chrono::Utc>,
     pub end: chrono::DateTime<chrono::Utc>,
@@ -51,13 +55,16 @@ pub struct TimeSeries{
 
 impl fmt::Display for TimeSeries {
     fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f, 
-            "{}--{}:{}", 
-            &self.start.to_rfc3339(),
-            &self.end.to_rfc3339(),
-            &self.timedelta.unwrap()
-        )
+        match &self.timedelta {
+            Some(timedelta) => write!(
+                f, 
+                "{}--{}:{}", 
+                &self.start.to_rfc3339(),
+                &self.end.to_rfc3339(),
+                timedelta
+            ),
+            None => write!(f, "{}--{}", &self.start.to_rfc3339(), &self.end.to_rfc3339()),
+        }
     }
 }
 
@@ -98,6 +105,33 @@ pub struct UserStats{
     pub contact: Vec<String>,
 }
 
+impl UserStats {
+    /// Checks if the model set of the account may include a model (e.g. "ecmwf-ifs"). A model listed
+    /// in the set passes and a model excluded by an ```all_minus_{name}``` entry fails, where the name
+    /// matches the model with or without its provider prefix (e.g. "euro1k" for "mm-euro1k") or a
+    /// leading part of it (e.g. "ecmwf" for "ecmwf-ifs"). Otherwise the model passes only if the set
+    /// holds ```all```, an ```all_minus_{name}``` entry or a group name without a '-' (e.g. "basic"),
+    /// since groups are not known here and the API decides. A set of explicit model names that does
+    /// not list the model fails. The ```mix``` is always available and an empty set is not checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The name of the model as used in the ```model``` option.
+    pub fn has_model(&self, model: &str) -> bool {
+        if model == "mix" || self.models.is_empty() || self.models.iter().any(|set| set == model) {
+            return true;
+        }
+        let base = model.split_once('-').map_or(model, |(_, base)| base);
+        let excludes = |name: &str| {
+            [model, base].iter().any(|m| m.strip_prefix(name).is_some_and(|rest| rest.is_empty() || rest.starts_with('-')))
+        };
+        if self.models.iter().filter_map(|set| set.strip_prefix("all_minus_")).any(excludes) {
+            return false;
+        }
+        self.models.iter().any(|set| set == "all" || set.starts_with("all_minus_") || !set.contains('-'))
+    }
+}
+
 /// The Limit struct is used to de-serialize the limit attributes of the account (e.g. how many 
 /// requests in parallel are allowed etc.)
 #[derive(Debug, Deserialize, Serialize)]
@@ -114,24 +148,83 @@ pub async fn extract_user_statistics(response: Response) -> std::result::Result<
     match response.json::<UStatsResponse>()
         .await {
             Ok(json) => Ok(json),
-            Err(e) => Err(ConnectorError::ReqwestError(e.to_string())),
+            Err(e) => Err(ConnectorError::ReqwestError(e)),
         }
 }
 
-/// Writes the HTTP response to a file. 
+/// Writes the HTTP response to a file. The body is streamed to disk chunk by chunk and the file only
+/// appears at the target path once the download is complete.
 /// 
 /// # Arguments
 /// 
 /// * `response` - The HTTP response object. 
 /// * `file_name` - The name for the file to be written (complete with path). 
 /// 
-pub async fn write_file(response: Response, file_name: &String) -> std::result::Result<(), ConnectorError> {
-    let body = response.bytes().await.map_err(|e| ConnectorError::ReqwestError(e.to_string())).unwrap();
-    let mut content = std::io::Cursor::new(body);
+pub async fn write_file(response: Response, file_name: &str) -> std::result::Result<(), ConnectorError> {
+    stream_to_file(response, file_name, None, None).await
+}
+
+/// Converts an unsuccessful HTTP response into the matching [`ConnectorError`] (see
+/// [`crate::errors::parse_api_error`]). Consumes the HTTP response.
+/// 
+/// # Arguments
+/// 
+/// * `response` - The HTTP response from the query to the meteomatics API.
+/// 
+pub async fn error_from_response(response: Response) -> ConnectorError {
+    let status = response.status();
+    match response.text().await {
+        Ok(body) => parse_api_error(status, &body),
+        Err(e) => ConnectorError::ReqwestError(e),
+    }
+}
 
-    let mut file = File::create(file_name)?;
-    std::io::copy(&mut content, &mut file)?;
-    Ok(())
+/// Rebuilds a response around a new body stream, keeping the status and the headers (e.g. to
+/// attach state to the body or to put back the part of the body that was already read).
+/// 
+/// # Arguments
+/// 
+/// * `response` - The HTTP response object. 
+/// * `body` - Maps the body stream of the response to the new body stream. 
+/// 
+pub(crate) fn map_body_stream<F, S>(response: Response, body: F) -> Response
+where
+    F: FnOnce(BoxStream<'static, reqwest::Result<Bytes>>) -> S,
+    S: Stream<Item = reqwest::Result<Bytes>> + Send + 'static,
+{
+    let mut builder = http::Response::builder().status(response.status()).version(response.version());
+    if let Some(headers) = builder.headers_mut() {
+        *headers = response.headers().clone();
+    }
+    let body = body(response.bytes_stream().boxed());
+    // Safe to unwrap, status, version and headers stem from a valid response.
+    Response::from(builder.body(reqwest::Body::wrap_stream(body)).unwrap())
+}
+
+/// Returns all points in time of a time series (from start to end in steps of the timedelta).
+/// Fails if the time series has no positive timedelta.
+/// 
+/// # Arguments
+/// 
+/// * `time_series` - The time series to expand.
+/// 
+pub(crate) fn expand_time_series(time_series: &TimeSeries) -> std::result::Result<Vec<chrono::DateTime<chrono::Utc>>, ConnectorError> {
+    let step = match time_series.timedelta {
+        Some(step) if step > chrono::Duration::zero() => step,
+        _ => return Err(ConnectorError::LibraryError(
+            format!(
+                "The time series from {} to {} requires a positive timedelta",
+                time_series.start.to_rfc3339(), time_series.end.to_rfc3339()
+            )
+        )),
+    };
+    let mut dates = Vec::new();
+    let mut date = time_series.start;
+    while date <= time_series.end {
+        dates.push(date);
+        date += step;
+    }
+    Ok(dates)
 }
 
 /// Creates a path if it does not already exist.
@@ -142,16 +235,9 @@ pub async fn write_file(response: Response, file_name: &String) -> std::result::
 /// 
 pub async fn create_path(file_name: &String) -> std::result::Result<(), ConnectorError> {
     // https://www.programming-idioms.org/idiom/212/check-if-folder-exists
-    let dir: &Path = Path::new(file_name).parent().unwrap();
-    let b: bool = dir.is_dir();
-
-    if !b {
-       match fs::create_dir_all(dir) {
-           Ok(_) => Ok(()),
-           Err(_) => Err(ConnectorError::FileIOError)
-       }
-    } else {
-        Ok(())
+    match Path::new(file_name).parent() {
+        Some(dir) if !dir.is_dir() => Ok(fs::create_dir_all(dir)?),
+        _ => Ok(()),
     }
 
 }
@@ -162,7 +248,7 @@ pub async fn create_path(file_name: &String) -> std::result::Result<(), Connecto
 /// 
 /// * `df_in` - DataFrame as derived from the HTTP response, with missing columns for lat/lon
 /// * `point` - The specific point in space (with latitude and longitude) from which to extract the 
-/// lat / lon value for the column.
+///   lat / lon value for the column.
 /// 
 pub async fn df_add_latlon(df_in: polars::frame::DataFrame, point: &Point) -> 
 std::result::Result<polars::frame::DataFrame, polars::error::PolarsError> {
@@ -208,20 +294,33 @@ std::result::Result<polars::frame::DataFrame, polars::error::PolarsError> {
 /// 
 pub async fn parse_response_to_df(
     response: Response,
-) -> std::result::Result<polars::frame::DataFrame, polars::error::PolarsError> {
+) -> std::result::Result<polars::frame::DataFrame, ConnectorError> {
     // Get the response text:
-    let body = response.text().await.unwrap();
+    let body = response.text().await?;
+
+    // A response without rows (e.g. no lightning strokes) only contains the header, which the CSV 
+    // reader cannot handle.
+    use polars::prelude::*; 
+    if body.trim_end().lines().count() <= 1 {
+        let columns: Vec<Series> = body
+            .trim_end()
+            .split(';')
+            .filter(|name| !name.is_empty())
+            .map(|name| Series::new_empty(name, &DataType::Utf8))
+            .collect();
+        return DataFrame::new(columns).map_err(|e| ConnectorError::PolarsError(e.to_string()));
+    }
 
     // Parse the response to a DataFrame
     let file = std::io::Cursor::new(&body);
-    use polars::prelude::*; 
     let dataframe = polars::io::csv::CsvReader::new(file)
         .infer_schema(Some(100))
         .with_delimiter(b';')
         .has_header(true)
         .with_parse_dates(false)
         .with_ignore_parser_errors(false)
-        .finish()?;
+        .finish()
+        .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
 
     Ok(dataframe)
 }
@@ -236,9 +335,9 @@ pub async fn parse_response_to_df(
 /// 
 pub async fn parse_grid_response_to_df(
     response: Response,
-) -> std::result::Result<polars::frame::DataFrame, polars::error::PolarsError> {
+) -> std::result::Result<polars::frame::DataFrame, ConnectorError> {
         // Get the response text:
-        let body = response.text().await.unwrap();
+        let body = response.text().await?;
 
         // Parse the response to a DataFrame
         let file = std::io::Cursor::new(&body);
@@ -250,7 +349,8 @@ pub async fn parse_grid_response_to_df(
             .with_skip_rows(2)
             .with_parse_dates(false)
             .with_ignore_parser_errors(false)
-            .finish()?;
+            .finish()
+            .map_err(|e| ConnectorError::PolarsError(e.to_string()))?;
     
         Ok(dataframe)
 }
@@ -300,17 +400,18 @@ pub async fn build_station_list_query_specs(
 /// * `time_series` - Defines the temporal extent (time and date of start and a timedelta).
 /// * `parameters` - Names of individual parameters (e.g. "t_2m:C", "wind_speed_10m:ms"). 
 /// * `coords_str` - Specifies the locations for the API (formatted according to the API rules, e.g.
-/// '47.0,8+46.5,9')
-/// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
+///   '47.0,8+46.5,9')
+/// * `optionals` - Optional parameters for the request (e.g. a [`crate::QueryOptions`] or raw
+///   options like "calibrated=true"). Fails with [`ConnectorError::InvalidOption`] if they are invalid.
 /// * `format` - Specifies the file format for the request (e.g. "csv" or "netcdf")
 /// 
-pub async fn build_ts_query_specs(
+pub async fn build_ts_query_specs<O: ToQueryOptions + ?Sized>(
     time_series: &TimeSeries,
     parameters: &[String],
     coords_str: &str,
-    optionals: &Option<Vec<String>>,
+    optionals: &O,
     format: &str,
-) -> String {
+) -> std::result::Result<String, ConnectorError> {
     let query_specs = format!(
         "{}/{}/{}/{}",
         time_series,
@@ -320,18 +421,7 @@ pub async fn build_ts_query_specs(
     );
 
     // Handles optional parameters 
-    let query_specs = match optionals {
-        None => query_specs,
-        Some(_) => {
-            format!(
-                "{}?{}",
-                query_specs,
-                optionals.as_ref().unwrap().join("&")
-            )
-        }
-    };
-    
-    query_specs
+    Ok(append_options(query_specs, &optionals.to_query_options()?))
 }
 
 /// Builds the query specifications ('specs') for a grid query according to the Meteomatics API
@@ -344,17 +434,18 @@ pub async fn build_ts_query_specs(
 /// * `timestamp` - Date and time for the request.
 /// * `parameter` - Name of an individual parameter (e.g. "t_2m:C" or "wind_speed_10m:ms"). 
 /// * `coords_str` - Specifies the locations for the API (formatted according to the API rules, e.g.
-/// '47.0,8+46.5,9')
-/// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
+///   '47.0,8+46.5,9')
+/// * `optionals` - Optional parameters for the request (e.g. a [`crate::QueryOptions`] or raw
+///   options like "calibrated=true"). Fails with [`ConnectorError::InvalidOption`] if they are invalid.
 /// * `format` - Specifies the file format for the request (e.g. "csv" or "netcdf")
 /// 
-pub async fn build_grid_query_specs(
+pub async fn build_grid_query_specs<O: ToQueryOptions + ?Sized>(
     timestamp: &chrono::DateTime<chrono::Utc>,
     parameter: &String,
     coords_str: &str,
-    optionals: &Option<Vec<String>>,
+    optionals: &O,
     format: &str,
-) -> String {
+) -> std::result::Result<String, ConnectorError> {
     let query_specs = format!(
         "{}/{}/{}/{}",
         timestamp.to_rfc3339(),
@@ -364,18 +455,7 @@ pub async fn build_grid_query_specs(
     );
 
     // Handles optional parameters 
-    let query_specs = match optionals {
-        None => query_specs,
-        Some(_) => {
-            format!(
-                "{}?{}",
-                query_specs,
-                optionals.as_ref().unwrap().join("&")
-            )
-        }
-    };
-    
-    query_specs
+    Ok(append_options(query_specs, &optionals.to_query_options()?))
 }
 
 /// Builds the query specifications ('specs') for a time series grid query according to the Meteomatics 
@@ -388,17 +468,18 @@ pub async fn build_grid_query_specs(
 /// * `time_series` - Defines the temporal extent (time and date of start and a timedelta).
 /// * `parameter` - Name of an individual parameter (e.g. "t_2m:C" or "wind_speed_10m:ms"). 
 /// * `coords_str` - Specifies the locations for the API (formatted according to the API rules, e.g.
-/// '47.0,8+46.5,9')
+///   '47.0,8+46.5,9')
 /// * `format` - Specifies the file format for the request (e.g. "csv" or "netcdf")
-/// * `optionals` - Optional parameters for the request (e.g. "calibrated=true").
+/// * `optionals` - Optional parameters for the request (e.g. a [`crate::QueryOptions`] or raw
+///   options like "calibrated=true"). Fails with [`ConnectorError::InvalidOption`] if they are invalid.
 /// 
-pub async fn build_grid_ts_query_specs(
+pub async fn build_grid_ts_query_specs<O: ToQueryOptions + ?Sized>(
     time_series: &TimeSeries,
     parameter: &String,
     coords_str: &str,
     format: &str,
-    optionals: &Option<Vec<String>>,
-) -> String {
+    optionals: &O,
+) -> std::result::Result<String, ConnectorError> {
     let query_specs = format!(
         "{}/{}/{}/{}",
         time_series,
@@ -408,18 +489,7 @@ pub async fn build_grid_ts_query_specs(
     );
 
     // Handles optional parameters 
-    let query_specs = match optionals {
-        None => query_specs,
-        Some(_) => {
-            format!(
-                "{}?{}",
-                query_specs,
-                optionals.as_ref().unwrap().join("&")
-            )
-        }
-    };
-    
-    query_specs
+    Ok(append_options(query_specs, &optionals.to_query_options()?))
 }
 
 /// This query is used to get information about lightning in a defined area and over a certain amount
@@ -429,7 +499,7 @@ pub async fn build_grid_ts_query_specs(
 /// 
 /// * `time_series` - Defines the temporal extent (time and date of start and a timedelta).
 /// * `coords_str` - Specifies the locations for the API (formatted according to the API rules, e.g.
-/// '47.0,8+46.5,9')
+///   '47.0,8+46.5,9')
 /// 
 pub async fn build_grid_ts_lightning_query_specs(
     time_series: &TimeSeries,
@@ -444,6 +514,52 @@ pub async fn build_grid_ts_lightning_query_specs(
     query_specs 
 }
 
+/// Builds the query specifications for the model runs (```get_init_date```) of a model. The API
+/// returns the initialization time of the run for every valid time of the time series and parameter.
+/// 
+/// # Arguments
+/// 
+/// * `model` - The model (e.g. "ecmwf-ifs").
+/// * `time_series` - The valid times (time and date of start and a timedelta).
+/// * `parameters` - Names of individual parameters (e.g. "t_2m:C" or "wind_speed_10m:ms").
+/// 
+pub async fn build_init_date_query_specs(
+    model: &str,
+    time_series: &TimeSeries,
+    parameters: &[String]
+) -> String {
+    let valid_date = format!(
+        "{}--{}",
+        format_query_date(&time_series.start),
+        format_query_date(&time_series.end)
+    );
+    let valid_date = match &time_series.timedelta {
+        Some(timedelta) => format!("{}:{}", valid_date, timedelta),
+        None => valid_date,
+    };
+    format!(
+        "get_init_date?model={}&valid_date={}&parameters={}",
+        model,
+        valid_date,
+        parameters.join(",")
+    )
+}
+
+/// Builds the query specifications for the time ranges (```get_time_range```) for which a model has
+/// data of the parameters.
+/// 
+/// # Arguments
+/// 
+/// * `model` - The model (e.g. "mix" or "ecmwf-ifs").
+/// * `parameters` - Names of individual parameters (e.g. "t_2m:C" or "wind_speed_10m:ms").
+/// 
+pub async fn build_time_range_query_specs(
+    model: &str,
+    parameters: &[String]
+) -> String {
+    format!("get_time_range?model={}&parameters={}", model, parameters.join(","))
+}
+
 /// Creates the query specs for the route query type.
 /// 
 /// # Arguments
@@ -451,7 +567,7 @@ pub async fn build_grid_ts_lightning_query_specs(
 /// * `dates` - These dates specify the points in time for the respective locations. 
 /// * `parameters` - Names of individual parameters (e.g. "t_2m:C" or "wind_speed_10m:ms").
 /// * `coords_str` - Specifies the locations for the API (formatted according to the API rules, e.g.
-/// '47.0,8+46.5,9')
+///   '47.0,8+46.5,9')
 /// 
 pub async fn build_route_query_specs(
     dates: &str,
@@ -470,6 +586,19 @@ pub async fn build_route_query_specs(
 /// Combines the default base API URL with the query specific information.
 pub async fn build_url(url_fragment: &str) -> std::result::Result<Url, ParseError> {
     let base_url = Url::parse(BASE_URL).expect("Base URL is known to be valid");
+    build_url_from(&base_url, url_fragment).await
+}
+
+/// Combines a custom base URL (e.g. a gateway or a local stand-in server) with the query specific 
+/// information. The base URL is expected to end with a '/' if it contains a path, otherwise the last
+/// path segment is replaced by the query (see [`Url::join`]).
+/// 
+/// # Arguments
+/// 
+/// * `base_url` - The URL under which the API is reachable (e.g. "https://api.meteomatics.com").
+/// * `url_fragment` - The query specs (e.g. "user_stats_json").
+/// 
+pub async fn build_url_from(base_url: &Url, url_fragment: &str) -> std::result::Result<Url, ParseError> {
     let full_url = base_url.join(url_fragment)?;
     Ok(full_url)
 }
@@ -497,7 +626,7 @@ mod tests {
         crate::util::create_path(&file_name).await.unwrap();
         let dir: &Path = Path::new(&file_name).parent().unwrap();
         let check: bool = dir.is_dir();
-        assert_eq!(check, true);
+        assert!(check);
         fs::remove_dir_all(dir).unwrap();
     }
 
@@ -515,7 +644,7 @@ mod tests {
     // checks if the query specs are correctly built
     async fn check_ts_query_specs_string() {
         // seconds
-        let start_date = Utc.ymd(2022, 5, 17).and_hms(12, 00, 00);
+        let start_date = Utc.with_ymd_and_hms(2022, 5, 17, 12, 00, 00).unwrap();
         let time_series = TimeSeries{
             start: start_date,
             end: start_date + Duration::days(1),
@@ -529,14 +658,14 @@ mod tests {
 
         let query_s = crate::util::build_ts_query_specs(
             &time_series, &parameters, &coord_str, &None, &String::from("csv")
-        ).await;
+        ).await.unwrap();
         assert_eq!(
             "2022-05-17T12:00:00+00:00--2022-05-18T12:00:00+00:00:PT3600S/t_2m:C/52.520551,13.461804/csv", 
             query_s
         );
         
         // microseconds
-        let start_date = Utc.ymd(2022, 5, 17).and_hms_micro(12, 00, 00, 453_829);
+        let start_date = Utc.with_ymd_and_hms(2022, 5, 17, 12, 00, 00).unwrap() + Duration::microseconds(453_829);
         let time_series = TimeSeries{
             start: start_date,
             end: start_date + Duration::days(1),
@@ -545,14 +674,14 @@ mod tests {
 
         let query_ms = crate::util::build_ts_query_specs(
             &time_series, &parameters, &coord_str, &None, &String::from("csv")
-        ).await;
+        ).await.unwrap();
         assert_eq!(
             "2022-05-17T12:00:00.453829+00:00--2022-05-18T12:00:00.453829+00:00:PT3600S/t_2m:C/52.520551,13.461804/csv", 
             query_ms
         );
 
         // nanoseconds
-        let start_date = Utc.ymd(2022, 5, 17).and_hms_nano(12, 00, 00, 453_829_123);
+        let start_date = Utc.with_ymd_and_hms(2022, 5, 17, 12, 00, 00).unwrap() + Duration::nanoseconds(453_829_123);
         let time_series = TimeSeries{
             start: start_date,
             end: start_date + Duration::days(1),
@@ -561,7 +690,7 @@ mod tests {
 
         let query_ns = crate::util::build_ts_query_specs(
             &time_series, &parameters, &coord_str, &None, &String::from("csv")
-        ).await;
+        ).await.unwrap();
         assert_eq!(
             "2022-05-17T12:00:00.453829123+00:00--2022-05-18T12:00:00.453829123+00:00:PT3600S/t_2m:C/52.520551,13.461804/csv", 
             query_ns
@@ -626,6 +755,26 @@ mod tests {
 
         // Check if the contact was correctly deserialized.
         assert_eq!(json.stats.contact[0], "rustythecrab@meteomatics.com");
+
+        // Exclusions fail the model check, also next to group names.
+        let mut stats = json.stats;
+        assert!(stats.has_model("ecmwf-ifs") && !stats.has_model("mm-euro1k") && stats.has_model("mix"));
+        stats.models.push(String::from("basic"));
+        assert!(!stats.has_model("mm-euro1k") && stats.has_model("ukmo-euro4"));
+
+        // Exclusions match model names, not substrings of them.
+        stats.models = vec![String::from("all_minus_ecmwf"), String::from("all_minus_eu")];
+        assert!(!stats.has_model("ecmwf-ifs") && !stats.has_model("ecmwf-ens"));
+        assert!(stats.has_model("dwd-icon-eu") && stats.has_model("mm-swiss1k"));
+
+        // A set of explicit model names only holds the listed models.
+        stats.models = vec![String::from("ecmwf-ifs"), String::from("dwd-icon-eu")];
+        assert!(stats.has_model("ecmwf-ifs") && stats.has_model("dwd-icon-eu") && stats.has_model("mix"));
+        assert!(!stats.has_model("ukmo-euro4"));
+
+        // Unknown group names get the benefit of the doubt.
+        stats.models.push(String::from("basic"));
+        assert!(stats.has_model("ukmo-euro4"));
     }
 
     /// Query for all stations on the globe no matter which parameters are measured:
@@ -638,11 +787,11 @@ mod tests {
         let startdate = None;
         let enddate = None;
         let query_string = crate::util::build_station_list_query_specs(
-            &Option::from(location), 
-            &Option::from(elevation),
-            &Option::from(parameters),
-            &Option::from(startdate),
-            &Option::from(enddate)
+            &location, 
+            &elevation,
+            &parameters,
+            &startdate,
+            &enddate
         ).await;
         assert_eq!(all_stations, query_string);
     }
@@ -661,8 +810,8 @@ mod tests {
             &Option::from(location),
             &Option::from(parameters), 
             &Option::from(elevation),
-            &Option::from(startdate),
-            &Option::from(enddate)
+            &startdate,
+            &enddate
         ).await;
         assert_eq!(all_stations, query_string);
     }
@@ -674,14 +823,14 @@ mod tests {
         let location = "50.705502,10.467007";
         let elevation = None;
         let parameters = vec!["t_2m:C"];
-        let startdate = Utc.ymd(2018, 1, 1).and_hms(0, 0, 0);
+        let startdate = Utc.with_ymd_and_hms(2018, 1, 1, 0, 0, 0).unwrap();
         let enddate = None;
         let query_string = crate::util::build_station_list_query_specs(
             &Option::from(location), 
             &Option::from(parameters),
-            &Option::from(elevation),
+            &elevation,
             &Option::from(startdate),
-            &Option::from(enddate)
+            &enddate
         ).await;
         assert_eq!(all_stations, query_string);
     }
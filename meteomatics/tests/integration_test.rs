@@ -1,5 +1,5 @@
 use meteomatics::APIClient;
-use chrono::{Duration, Utc, prelude::*};
+use chrono::{Duration, Utc, TimeZone};
 use dotenv::dotenv;
 use std::env;
 use meteomatics::{Point, BBox, TimeSeries};
@@ -42,7 +42,7 @@ async fn call_query_time_series_with_options() {
     );
 
     // Create time series
-    let time_series_start = Utc.ymd(1989, 11, 9).and_hms_micro(18, 0, 0, 0);
+    let time_series_start = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
     let time_series = TimeSeries{ 
         start: time_series_start, 
         end: time_series_start + Duration::days(1), 
@@ -50,9 +50,7 @@ async fn call_query_time_series_with_options() {
     };
 
     // Create Parameters
-    let mut parameters = Vec::new();
-    parameters.push(String::from("t_2m:C"));
-    parameters.push(String::from("precip_1h:mm"));
+    let parameters = vec![String::from("t_2m:C"), String::from("precip_1h:mm")];
 
     // Create Locations
     let p1: Point = Point { lat: 52.520551, lon: 13.461804};
@@ -60,9 +58,7 @@ async fn call_query_time_series_with_options() {
     let coords: Vec<Point> = vec![p1, p2];
 
     // Create Optionals
-    let mut optionals = Vec::new();
-    optionals.push(String::from("source=mix"));
-    optionals.push(String::from("calibrated=true"));
+    let optionals = vec![String::from("source=mix"), String::from("calibrated=true")];
 
     // Call endpoint
     let df_q = meteomatics_connector
@@ -109,7 +105,7 @@ async fn call_query_time_series_without_options() {
     );
 
     // Create time series
-    let time_series_start = Utc.ymd(1989, 11, 9).and_hms_micro(18, 0, 0, 0);
+    let time_series_start = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
     let time_series = TimeSeries{ 
         start: time_series_start, 
         end: time_series_start + Duration::days(1), 
@@ -117,9 +113,7 @@ async fn call_query_time_series_without_options() {
     };
 
     // Create Parameters
-    let mut parameters = Vec::new();
-    parameters.push(String::from("t_2m:C"));
-    parameters.push(String::from("precip_1h:mm"));
+    let parameters = vec![String::from("t_2m:C"), String::from("precip_1h:mm")];
 
     // Create Locations
     let p1: Point = Point { lat: 52.520551, lon: 13.461804};
@@ -166,7 +160,7 @@ async fn query_time_series_one_point_one_param() {
     );
 
     // Create time series
-    let time_series_start = Utc.ymd(1989, 11, 9).and_hms_micro(18, 0, 0, 0);
+    let time_series_start = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
     let time_series = TimeSeries{ 
         start: time_series_start, 
         end: time_series_start + Duration::days(1), 
@@ -220,7 +214,7 @@ async fn query_time_series_one_point_two_param() {
     );
 
     // Create time series
-    let time_series_start = Utc.ymd(1989, 11, 9).and_hms_micro(18, 0, 0, 0);
+    let time_series_start = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
     let time_series = TimeSeries{ 
         start: time_series_start, 
         end: time_series_start + Duration::days(1), 
@@ -277,7 +271,7 @@ async fn query_time_series_two_point_two_param() {
     );
 
     // Create time series
-    let time_series_start = Utc.ymd(1989, 11, 9).and_hms_micro(18, 0, 0, 0);
+    let time_series_start = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
     let time_series = TimeSeries{ 
         start: time_series_start, 
         end: time_series_start + Duration::days(1), 
@@ -332,7 +326,7 @@ async fn query_time_series_one_postal_one_param() {
     );
 
     // Create time series
-    let time_series_start = Utc.ymd(1989, 11, 9).and_hms_micro(18, 0, 0, 0);
+    let time_series_start = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
     let time_series = TimeSeries{ 
         start: time_series_start, 
         end: time_series_start + Duration::days(1), 
@@ -388,7 +382,7 @@ async fn query_time_series_two_postal_two_param() {
     );
 
     // Create time series
-    let time_series_start = Utc.ymd(1989, 11, 9).and_hms_micro(18, 0, 0, 0);
+    let time_series_start = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
     let time_series = TimeSeries{ 
         start: time_series_start, 
         end: time_series_start + Duration::days(1), 
@@ -441,7 +435,7 @@ async fn query_grid_pivoted() {
     );
 
     // Create time information
-    let start_date = Utc.ymd(1989, 11, 9).and_hms_micro(18, 0, 0, 0);
+    let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
 
     // Create Parameters
     let parameter = String::from("t_2m:C");
@@ -504,7 +498,7 @@ async fn query_grid_unpivoted() {
     );
 
     // Create time information
-    let start_date = Utc.ymd(1989, 11, 9).and_hms_micro(18, 0, 0, 0);
+    let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
 
     // Create Parameters
     let parameters = vec![String::from("t_2m:C"), String::from("precip_1h:mm")];
@@ -585,7 +579,7 @@ async fn query_grid_unpivoted_time_series() {
     );
 
     // Create time series
-    let time_series_start = Utc.ymd(1989, 11, 9).and_hms_micro(18, 0, 0, 0);
+    let time_series_start = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
     let time_series = TimeSeries{ 
         start: time_series_start, 
         end: time_series_start + Duration::days(1), 
@@ -633,7 +627,7 @@ async fn query_netcdf() {
     );
 
     // Create time series
-    let time_series_start = Utc.ymd(1989, 11, 9).and_hms_micro(18, 0, 0, 0);
+    let time_series_start = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
     let time_series = TimeSeries{ 
         start: time_series_start, 
         end: time_series_start + Duration::days(1), 
@@ -685,7 +679,7 @@ async fn query_netcdf() {
     // Remove the file    
     let dir: &Path = Path::new(&file_name).parent().unwrap();
     fs::remove_file(&file_name).unwrap();
-    fs::remove_dir_all(&dir).unwrap();
+    fs::remove_dir_all(dir).unwrap();
     // Check if the file and the directory were removed.
     assert!(!Path::new(&file_name).exists());
     assert!(!Path::new(&dir).exists());
@@ -708,7 +702,7 @@ async fn query_png() {
 
     // Create time information
     // 1989-11-09 19:00:00 --> 18:00:00 UTC
-    let start_date = Utc.ymd(1989, 11, 9).and_hms_micro(18, 0, 0, 0);
+    let start_date = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
 
     // Create Parameters
     let parameter = String::from("t_2m:C");
@@ -744,7 +738,7 @@ async fn query_png() {
     // Remove the file    
     let dir: &Path = Path::new(&file_name).parent().unwrap();
     fs::remove_file(&file_name).unwrap();
-    fs::remove_dir_all(&dir).unwrap();
+    fs::remove_dir_all(dir).unwrap();
     // Check if the file and the directory were removed.
     assert!(!Path::new(&file_name).exists());
     assert!(!Path::new(&dir).exists());
@@ -766,7 +760,7 @@ async fn query_grid_png_timeseries() {
     );
 
     // Create time series
-    let time_series_start = Utc.ymd(1989, 11, 9).and_hms_micro(18, 0, 0, 0);
+    let time_series_start = Utc.with_ymd_and_hms(1989, 11, 9, 18, 0, 0).unwrap();
     let time_series = TimeSeries{ 
         start: time_series_start, 
         end: time_series_start + Duration::days(1), 
@@ -810,7 +804,7 @@ async fn query_grid_png_timeseries() {
     // Remove the file    
     let dir: &Path = Path::new(&file_name).parent().unwrap();
     fs::remove_file(&file_name).unwrap();
-    fs::remove_dir_all(&dir).unwrap();
+    fs::remove_dir_all(dir).unwrap();
     // Check if the file and the directory were removed.
     assert!(!Path::new(&file_name).exists());
     assert!(!Path::new(&dir).exists());
@@ -851,7 +845,7 @@ async fn query_lightning(){
     );
 
     // Create time information
-    let start_date = Utc.ymd(2022, 5, 20).and_hms_micro(10, 0, 0, 0);
+    let start_date = Utc.with_ymd_and_hms(2022, 5, 20, 10, 0, 0).unwrap();
     let time_series = TimeSeries {
         start: start_date,
         end: start_date + Duration::days(1),
@@ -905,10 +899,10 @@ async fn query_route_points(){
     );
 
     // Create time information
-    let date1 = Utc.ymd(2021, 5, 25).and_hms_micro(12, 0, 0, 0);
-    let date2 = Utc.ymd(2021, 5, 25).and_hms_micro(13, 0, 0, 0);
-    let date3 = Utc.ymd(2021, 5, 25).and_hms_micro(14, 0, 0, 0);
-    let date4 = Utc.ymd(2021, 5, 25).and_hms_micro(15, 0, 0, 0);
+    let date1 = Utc.with_ymd_and_hms(2021, 5, 25, 12, 0, 0).unwrap();
+    let date2 = Utc.with_ymd_and_hms(2021, 5, 25, 13, 0, 0).unwrap();
+    let date3 = Utc.with_ymd_and_hms(2021, 5, 25, 14, 0, 0).unwrap();
+    let date4 = Utc.with_ymd_and_hms(2021, 5, 25, 15, 0, 0).unwrap();
     let dates = vec![date1, date2, date3, date4];
 
     // Create Parameters
@@ -960,10 +954,10 @@ async fn query_route_postal(){
     );
 
     // Create time information
-    let date1 = Utc.ymd(2021, 5, 25).and_hms_micro(12, 0, 0, 0);
-    let date2 = Utc.ymd(2021, 5, 25).and_hms_micro(13, 0, 0, 0);
-    let date3 = Utc.ymd(2021, 5, 25).and_hms_micro(14, 0, 0, 0);
-    let date4 = Utc.ymd(2021, 5, 25).and_hms_micro(15, 0, 0, 0);
+    let date1 = Utc.with_ymd_and_hms(2021, 5, 25, 12, 0, 0).unwrap();
+    let date2 = Utc.with_ymd_and_hms(2021, 5, 25, 13, 0, 0).unwrap();
+    let date3 = Utc.with_ymd_and_hms(2021, 5, 25, 14, 0, 0).unwrap();
+    let date4 = Utc.with_ymd_and_hms(2021, 5, 25, 15, 0, 0).unwrap();
     let dates = vec![date1, date2, date3, date4];
 
     // Create Parameters
@@ -1097,15 +1091,15 @@ async fn query_station_list(){
     let location: &str = &Point{ lat: 50.705502, lon: 10.467007}.to_string();
     let parameters = vec!["t_2m:C"];
     let elevation = None;
-    let startdate = Utc.ymd(2018, 1, 1).and_hms(0, 0, 0);
+    let startdate = Utc.with_ymd_and_hms(2018, 1, 1, 0, 0, 0).unwrap();
     let enddate = None;
 
     let df_r = meteomatics_connector.query_station_list(
         &Option::from(location),
         &Option::from(parameters),
-        &Option::from(elevation),
+        &elevation,
         &Option::from(startdate),
-        &Option::from(enddate)
+        &enddate
     ).await.unwrap();
     assert_eq!(df_s.select(["ID Hash"]).unwrap().sum(), df_r.select(["ID Hash"]).unwrap().sum());
     assert_eq!(